@@ -134,6 +134,145 @@ val=$($VALKEY_CLI -h "$HOST" am.getint doc nonexistent)
 assert_equals "$val" ""
 echo "   ✓ Non-existent fields return null"
 
+echo "Test 9: AM.INCRDOUBLE increments doubles..."
+$VALKEY_CLI -h "$HOST" del incrdouble_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new incrdouble_test > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.incrdouble incrdouble_test stats.average 1.5)
+assert_equals "$val" "1.5"
+$VALKEY_CLI -h "$HOST" am.putdouble incrdouble_test score 10 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.incrdouble incrdouble_test score -2.5)
+assert_equals "$val" "7.5"
+val=$($VALKEY_CLI -h "$HOST" am.getdouble incrdouble_test score)
+assert_equals "$val" "7.5"
+$VALKEY_CLI -h "$HOST" am.putint incrdouble_test count 3 > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.incrdouble incrdouble_test count 1 2>&1)
+if ! echo "$result" | grep -q "not a double"; then
+    echo "   ✗ Expected type error: $result"
+    exit 1
+fi
+echo "   ✓ AM.INCRDOUBLE works"
+
+echo "Test 10: AM.INCRBY increments plain integers..."
+$VALKEY_CLI -h "$HOST" del incrby_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new incrby_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint incrby_test stock 10 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.incrby incrby_test stock 5)
+assert_equals "$val" "15"
+val=$($VALKEY_CLI -h "$HOST" am.incrby incrby_test stock -20)
+assert_equals "$val" "-5"
+val=$($VALKEY_CLI -h "$HOST" am.getint incrby_test stock)
+assert_equals "$val" "-5"
+$VALKEY_CLI -h "$HOST" am.putcounter incrby_test views 1 > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.incrby incrby_test views 1 2>&1)
+if ! echo "$result" | grep -q "use AM.INCCOUNTER"; then
+    echo "   ✗ Expected counter error: $result"
+    exit 1
+fi
+echo "   ✓ AM.INCRBY works"
+
+echo "Test 11: AM.DUMP/AM.RESTORE text round trip..."
+$VALKEY_CLI -h "$HOST" del dump_test dump_copy > /dev/null
+$VALKEY_CLI -h "$HOST" am.new dump_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext dump_test user.name "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint dump_test user.age 30 > /dev/null
+original=$($VALKEY_CLI -h "$HOST" --raw am.tojson dump_test)
+for encoding in BASE64 HEX; do
+    dumped=$($VALKEY_CLI -h "$HOST" --raw am.dump dump_test $encoding)
+    $VALKEY_CLI -h "$HOST" del dump_copy > /dev/null
+    $VALKEY_CLI -h "$HOST" am.restore dump_copy "$dumped" $encoding > /dev/null
+    restored=$($VALKEY_CLI -h "$HOST" --raw am.tojson dump_copy)
+    assert_equals "$restored" "$original"
+done
+dumped=$($VALKEY_CLI -h "$HOST" --raw am.dump dump_test)
+$VALKEY_CLI -h "$HOST" del dump_copy > /dev/null
+$VALKEY_CLI -h "$HOST" am.restore dump_copy "$dumped" > /dev/null
+restored=$($VALKEY_CLI -h "$HOST" --raw am.tojson dump_copy)
+assert_equals "$restored" "$original"
+result=$($VALKEY_CLI -h "$HOST" am.restore dump_copy "xyz" HEX 2>&1)
+if ! echo "$result" | grep -q "invalid hex data"; then
+    echo "   ✗ Expected hex error: $result"
+    exit 1
+fi
+result=$($VALKEY_CLI -h "$HOST" am.dump dump_test binary 2>&1)
+if ! echo "$result" | grep -q "encoding must be BASE64 or HEX"; then
+    echo "   ✗ Expected encoding error: $result"
+    exit 1
+fi
+echo "   ✓ AM.DUMP/AM.RESTORE work"
+
+echo "Test 12: RESP3 typed replies..."
+$VALKEY_CLI -h "$HOST" del resp3_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new resp3_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.putbool resp3_test active true > /dev/null
+$VALKEY_CLI -h "$HOST" am.putdouble resp3_test ratio 2.5 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.getbool resp3_test active)
+assert_equals "$val" "1"
+val=$($VALKEY_CLI -h "$HOST" -3 am.getbool resp3_test active)
+assert_equals "$val" "(true)"
+val=$($VALKEY_CLI -h "$HOST" am.getdouble resp3_test ratio)
+assert_equals "$val" "2.5"
+val=$($VALKEY_CLI -h "$HOST" -3 am.getdouble resp3_test ratio)
+assert_equals "$val" "(double) 2.5"
+echo "   ✓ RESP3 clients get native booleans and doubles"
+
+echo "Test 13: COERCE reads numbers across int and double..."
+$VALKEY_CLI -h "$HOST" del coerce_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new coerce_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.putdouble coerce_test score 7.9 > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint coerce_test count 3 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.getint coerce_test score)
+assert_equals "$val" ""
+val=$($VALKEY_CLI -h "$HOST" am.getint coerce_test score COERCE)
+assert_equals "$val" "7"
+val=$($VALKEY_CLI -h "$HOST" am.getdouble coerce_test count)
+assert_equals "$val" ""
+val=$($VALKEY_CLI -h "$HOST" am.getdouble coerce_test count coerce)
+assert_equals "$val" "3"
+result=$($VALKEY_CLI -h "$HOST" am.getint coerce_test score ROUND 2>&1)
+if ! echo "$result" | grep -q "expected COERCE"; then
+    echo "   ✗ Expected option error: $result"
+    exit 1
+fi
+echo "   ✓ COERCE converts between int and double"
+
+echo "Test 14: Doubles must be finite..."
+$VALKEY_CLI -h "$HOST" del finite_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new finite_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist finite_test readings > /dev/null
+for value in nan inf -inf 1e309; do
+    for cmd in putdouble appenddouble; do
+        path=ratio
+        [ "$cmd" = "appenddouble" ] && path=readings
+        result=$($VALKEY_CLI -h "$HOST" am.$cmd finite_test $path "$value" 2>&1)
+        if ! echo "$result" | grep -q "value must be a finite double"; then
+            echo "   ✗ Expected am.$cmd to reject $value: $result"
+            exit 1
+        fi
+    done
+done
+$VALKEY_CLI -h "$HOST" am.putdouble finite_test ratio 1.7976931348623157e308 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.getdouble finite_test ratio)
+assert_equals "$val" "1.7976931348623157e+308"
+result=$($VALKEY_CLI -h "$HOST" am.incrdouble finite_test ratio 1.7976931348623157e308 2>&1)
+if ! echo "$result" | grep -q "NaN or Infinity"; then
+    echo "   ✗ Expected overflow error: $result"
+    exit 1
+fi
+echo "   ✓ NaN and Infinity are rejected"
+
+echo "Test 15: WRONGTYPE for keys holding other types..."
+$VALKEY_CLI -h "$HOST" del plain_string > /dev/null
+$VALKEY_CLI -h "$HOST" set plain_string "hello" > /dev/null
+for cmd in "am.gettext plain_string title" "am.puttext plain_string title x" "am.tojson plain_string" "am.fromjson plain_string {}"; do
+    result=$($VALKEY_CLI -h "$HOST" $cmd 2>&1)
+    if ! echo "$result" | grep -q "^WRONGTYPE Operation against a key holding the wrong kind of value"; then
+        echo "   ✗ Expected WRONGTYPE from $cmd: $result"
+        exit 1
+    fi
+done
+assert_equals "$($VALKEY_CLI -h "$HOST" get plain_string)" "hello"
+echo "   ✓ Non-Automerge keys get WRONGTYPE"
+
 rm -f /tmp/saved.bin
 
 echo ""
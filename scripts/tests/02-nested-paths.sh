@@ -99,6 +99,72 @@ assert_equals "$val1" "flat value"
 assert_equals "$val2" "nested value"
 echo "   ✓ Mixed flat and nested keys work"
 
+echo "Test 6: AM.RENAME moves values and subtrees..."
+$VALKEY_CLI -h "$HOST" del rename_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new rename_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext rename_test title "Draft" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext rename_test user.name "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint rename_test user.age 30 > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.rename rename_test title name)
+assert_equals "$result" "OK"
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext rename_test name)
+assert_equals "$val" "Draft"
+exists=$($VALKEY_CLI -h "$HOST" am.exists rename_test title)
+assert_equals "$exists" "0"
+# The whole subtree moves, into parents created on the way
+$VALKEY_CLI -h "$HOST" am.rename rename_test user account.owner > /dev/null
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext rename_test account.owner.name)
+assert_equals "$val" "Alice"
+val=$($VALKEY_CLI -h "$HOST" am.getint rename_test account.owner.age)
+assert_equals "$val" "30"
+exists=$($VALKEY_CLI -h "$HOST" am.exists rename_test user)
+assert_equals "$exists" "0"
+result=$($VALKEY_CLI -h "$HOST" am.rename rename_test missing other 2>&1)
+if ! echo "$result" | grep -q "source path does not exist"; then
+    echo "   ✗ Expected missing source error: $result"
+    exit 1
+fi
+echo "   ✓ AM.RENAME works"
+
+echo "Test 7: AM.COPYPATH duplicates a subtree..."
+$VALKEY_CLI -h "$HOST" del copypath_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new copypath_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext copypath_test template.status "todo" > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist copypath_test template.tags > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext copypath_test template.tags "new" > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.copypath copypath_test template tasks.first)
+assert_equals "$result" "OK"
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext copypath_test tasks.first.status)
+assert_equals "$val" "todo"
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext copypath_test "tasks.first.tags[0]")
+assert_equals "$val" "new"
+# The copy is independent of the source
+$VALKEY_CLI -h "$HOST" am.puttext copypath_test tasks.first.status "done" > /dev/null
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext copypath_test template.status)
+assert_equals "$val" "todo"
+echo "   ✓ AM.COPYPATH works"
+
+echo "Test 8: AM.GETOBJID returns stable object IDs..."
+$VALKEY_CLI -h "$HOST" del objid_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new objid_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist objid_test todo > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist objid_test done > /dev/null
+todo=$($VALKEY_CLI -h "$HOST" --raw am.getobjid objid_test todo)
+done_id=$($VALKEY_CLI -h "$HOST" --raw am.getobjid objid_test done)
+if [ "$todo" = "$done_id" ]; then
+    echo "   ✗ Expected different IDs for two lists, got $todo"
+    exit 1
+fi
+$VALKEY_CLI -h "$HOST" am.appendtext objid_test todo "write tests" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext objid_test title "Tasks" > /dev/null
+val=$($VALKEY_CLI -h "$HOST" --raw am.getobjid objid_test todo)
+assert_equals "$val" "$todo"
+val=$($VALKEY_CLI -h "$HOST" --raw am.getobjid objid_test "")
+assert_equals "$val" "_root"
+val=$($VALKEY_CLI -h "$HOST" --raw am.getobjid objid_test title)
+assert_equals "$val" ""
+echo "   ✓ AM.GETOBJID works"
+
 rm -f /tmp/nested-saved.bin
 
 echo ""
@@ -66,6 +66,110 @@ assert_equals "$val1" "Alice"
 assert_equals "$val2" "Bob"
 echo "   ✓ List persistence works"
 
+echo "Test 6: Nested writes create list items..."
+$VALKEY_CLI -h "$HOST" del doc7 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new doc7 > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext doc7 'users[0].name' "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext doc7 'users[1].name' "Bob" > /dev/null
+len=$($VALKEY_CLI -h "$HOST" am.listlen doc7 users)
+assert_equals "$len" "2"
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext doc7 'users[1].name')
+assert_equals "$val" "Bob"
+result=$($VALKEY_CLI -h "$HOST" am.puttext doc7 'users[5].name' "Eve" 2>&1)
+if ! echo "$result" | grep -qi "err"; then
+    echo "   ✗ Expected an error for a sparse index: $result"
+    exit 1
+fi
+len=$($VALKEY_CLI -h "$HOST" am.listlen doc7 users)
+assert_equals "$len" "2"
+echo "   ✓ Nested writes append list items"
+
+echo "Test 7: AM.LPOP/AM.RPOP..."
+$VALKEY_CLI -h "$HOST" del doc8 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new doc8 > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist doc8 jobs > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext doc8 jobs "first" > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext doc8 jobs "middle" > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendint doc8 jobs 3 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" --raw am.lpop doc8 jobs)
+assert_equals "$val" "first"
+val=$($VALKEY_CLI -h "$HOST" am.rpop doc8 jobs)
+assert_equals "$val" "3"
+val=$($VALKEY_CLI -h "$HOST" --raw am.rpop doc8 jobs)
+assert_equals "$val" "middle"
+val=$($VALKEY_CLI -h "$HOST" --raw am.lpop doc8 jobs)
+assert_equals "$val" ""
+len=$($VALKEY_CLI -h "$HOST" am.listlen doc8 jobs)
+assert_equals "$len" "0"
+$VALKEY_CLI -h "$HOST" am.puttext doc8 title "Jobs" > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.lpop doc8 title 2>&1)
+if ! echo "$result" | grep -q "path is not a list"; then
+    echo "   ✗ Expected list error: $result"
+    exit 1
+fi
+echo "   ✓ AM.LPOP/AM.RPOP work"
+
+echo "Test 8: AM.PREPEND..."
+$VALKEY_CLI -h "$HOST" del doc9 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new doc9 > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist doc9 queue > /dev/null
+$VALKEY_CLI -h "$HOST" am.prepend doc9 queue text "last" > /dev/null
+$VALKEY_CLI -h "$HOST" am.prepend doc9 queue int 1 > /dev/null
+len=$($VALKEY_CLI -h "$HOST" am.listlen doc9 queue)
+assert_equals "$len" "2"
+val=$($VALKEY_CLI -h "$HOST" am.getint doc9 'queue[0]')
+assert_equals "$val" "1"
+val=$($VALKEY_CLI -h "$HOST" --raw am.gettext doc9 'queue[1]')
+assert_equals "$val" "last"
+result=$($VALKEY_CLI -h "$HOST" am.prepend doc9 missing text "x" 2>&1)
+if ! echo "$result" | grep -qi "err"; then
+    echo "   ✗ Expected an error for a missing list: $result"
+    exit 1
+fi
+echo "   ✓ AM.PREPEND inserts at the head"
+
+echo "Test 9: AM.LINDEXOF..."
+$VALKEY_CLI -h "$HOST" del doc10 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new doc10 > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist doc10 values > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendbool doc10 values true > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendint doc10 values 1 > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext doc10 values "one" > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.lindexof doc10 values int 1)
+assert_equals "$val" "1"
+val=$($VALKEY_CLI -h "$HOST" am.lindexof doc10 values bool true)
+assert_equals "$val" "0"
+val=$($VALKEY_CLI -h "$HOST" am.lindexof doc10 values text "one")
+assert_equals "$val" "2"
+val=$($VALKEY_CLI -h "$HOST" --raw am.lindexof doc10 values text "two")
+assert_equals "$val" ""
+echo "   ✓ AM.LINDEXOF finds values by type"
+
+echo "Test 10: AM.SPLICE with trailing options..."
+$VALKEY_CLI -h "$HOST" del doc11 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new doc11 > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist doc11 items > /dev/null
+$VALKEY_CLI -h "$HOST" am.splice doc11 items 0 0 text "a" text "d" > /dev/null
+hash=$($VALKEY_CLI -h "$HOST" am.splice doc11 items 1 0 text "b" int 3 MSG "insert" WITHHASH)
+if [ -z "$hash" ] || [ "$hash" = "OK" ]; then
+    echo "   ✗ AM.SPLICE WITHHASH should return a change hash, got: $hash"
+    exit 1
+fi
+json=$($VALKEY_CLI -h "$HOST" am.tojson doc11)
+assert_equals "$json" '{"items":["a","b",3,"d"]}'
+if ! $VALKEY_CLI -h "$HOST" am.history doc11 | grep -q "^insert$"; then
+    echo "   ✗ AM.SPLICE MSG should set the change message"
+    exit 1
+fi
+result=$($VALKEY_CLI -h "$HOST" am.splice doc11 items 0 1 MSG "remove")
+assert_equals "$result" "OK"
+result=$($VALKEY_CLI -h "$HOST" am.splice doc11 items 0 0 text 2>&1)
+if ! echo "$result" | grep -qi "wrong number"; then
+    echo "   ✗ AM.SPLICE with a missing value should fail, got: $result"
+    exit 1
+fi
+echo "   ✓ AM.SPLICE accepts MSG, TIME and WITHHASH"
+
 rm -f /tmp/list-saved.bin
 
 echo ""
@@ -142,5 +142,58 @@ echo "   ✓ AM.SPLICETEXT persistence works"
 
 rm -f /tmp/splice-saved.bin
 
+echo "Test 11: AM.CREATETEXT creates a Text object..."
+$VALKEY_CLI -h "$HOST" del createtext_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new createtext_test > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.createtext createtext_test title "Hello World")
+assert_equals "$result" "OK"
+type=$($VALKEY_CLI -h "$HOST" am.type createtext_test title)
+assert_equals "$type" "text"
+$VALKEY_CLI -h "$HOST" am.createtext createtext_test body > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.gettext createtext_test body)
+assert_equals "$val" ""
+# Marks apply without a prior splice
+result=$($VALKEY_CLI -h "$HOST" am.markcreate createtext_test title bold true 6 11)
+assert_equals "$result" "OK"
+marks=$($VALKEY_CLI -h "$HOST" --raw am.marks createtext_test title | tr '\n' ' ')
+assert_equals "$marks" "bold 1 6 11 "
+# A trailing MSG is an option, not the initial text
+$VALKEY_CLI -h "$HOST" am.createtext createtext_test notes MSG "add notes" > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.gettext createtext_test notes)
+assert_equals "$val" ""
+echo "   ✓ AM.CREATETEXT works"
+
+echo "Test 12: automerge.text-as-object is fixed at load time..."
+$VALKEY_CLI -h "$HOST" del textobj_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new textobj_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext textobj_test scalar "Hello" > /dev/null
+type=$($VALKEY_CLI -h "$HOST" am.type textobj_test scalar)
+assert_equals "$type" "str"
+# Replicas replay AM.PUTTEXT, so switching the mode at runtime would let them diverge
+result=$($VALKEY_CLI -h "$HOST" config set automerge.text-as-object yes 2>&1)
+if ! echo "$result" | grep -q "ERR"; then
+    echo "   ✗ Expected CONFIG SET to be rejected: $result"
+    exit 1
+fi
+mode=$($VALKEY_CLI -h "$HOST" --raw config get automerge.text-as-object | sed -n 2p)
+assert_equals "$mode" "no"
+echo "   ✓ automerge.text-as-object can't be changed at runtime"
+
+echo "Test 13: AM.GETRANGE returns a slice of text..."
+$VALKEY_CLI -h "$HOST" del range_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new range_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext range_test title "Hello world" > /dev/null
+$VALKEY_CLI -h "$HOST" am.createtext range_test body "héllo 👋 wörld" > /dev/null
+val=$($VALKEY_CLI -h "$HOST" --raw am.getrange range_test title 2 3)
+assert_equals "$val" "llo"
+val=$($VALKEY_CLI -h "$HOST" --raw am.getrange range_test title 6 100)
+assert_equals "$val" "world"
+val=$($VALKEY_CLI -h "$HOST" --raw am.getrange range_test body 6 3)
+assert_equals "$val" "👋 w"
+$VALKEY_CLI -h "$HOST" am.putint range_test count 1 > /dev/null
+val=$($VALKEY_CLI -h "$HOST" am.getrange range_test count 0 1)
+assert_equals "$val" ""
+echo "   ✓ AM.GETRANGE slices by character"
+
 echo ""
 echo "✅ All text operation tests passed!"
@@ -92,5 +92,34 @@ num_changes=$($VALKEY_CLI -h "$HOST" am.numchanges changes_test7)
 assert_equals "$num_changes" "3"
 echo "   ✓ AM.NUMCHANGES tracks nested path operations correctly"
 
+echo "Test 8: AM.CHANGES and AM.NUMCHANGES in a read-only context..."
+$VALKEY_CLI -h "$HOST" del ro_changes > /dev/null
+$VALKEY_CLI -h "$HOST" am.new ro_changes > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext ro_changes title "Draft" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint ro_changes version 1 > /dev/null
+# EVAL_RO only runs commands flagged readonly, as a replica serving reads would
+count=$($VALKEY_CLI -h "$HOST" eval_ro "return #redis.call('am.changes', KEYS[1])" 1 ro_changes)
+assert_equals "$count" "2"
+count=$($VALKEY_CLI -h "$HOST" eval_ro "return redis.call('am.numchanges', KEYS[1])" 1 ro_changes)
+assert_equals "$count" "2"
+result=$($VALKEY_CLI -h "$HOST" eval_ro "return redis.call('am.putint', KEYS[1], 'version', 2)" 1 ro_changes 2>&1)
+if ! echo "$result" | grep -qi "write"; then
+    echo "   ✗ Expected write command to be refused: $result"
+    exit 1
+fi
+echo "   ✓ Change reads work without write access"
+
+echo "Test 9: AM.CHANGES accepts SINCE only before the hashes..."
+count=$($VALKEY_CLI -h "$HOST" eval "return #redis.call('am.changes', KEYS[1], 'SINCE')" 1 ro_changes)
+assert_equals "$count" "2"
+for args in "LIMIT 1 SINCE" "SINCE SINCE"; do
+    result=$($VALKEY_CLI -h "$HOST" am.changes ro_changes $args 2>&1)
+    if ! echo "$result" | grep -q "syntax error"; then
+        echo "   ✗ Expected a syntax error for '$args': $result"
+        exit 1
+    fi
+done
+echo "   ✓ A misplaced SINCE is rejected"
+
 echo ""
 echo "✅ All change management tests passed!"
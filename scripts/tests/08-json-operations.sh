@@ -361,5 +361,97 @@ assert_equals "$item1" "c"
 assert_equals "$item2" "d"
 echo "   ✓ AM.DELETE array element works"
 
+echo "Test 21: AM.FROMJSON MERGE keeps existing fields and history..."
+$VALKEY_CLI -h "$HOST" del merge_test1 > /dev/null
+$VALKEY_CLI -h "$HOST" am.fromjson merge_test1 '{"user":{"name":"Alice","age":30}}' > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext merge_test1 title "Doc" > /dev/null
+changes_before=$($VALKEY_CLI -h "$HOST" am.numchanges merge_test1)
+heads_before=$($VALKEY_CLI -h "$HOST" --raw am.getheads merge_test1 | xxd -p)
+$VALKEY_CLI -h "$HOST" am.fromjson merge_test1 '{"user":{"age":31,"city":"Paris"}}' MERGE > /dev/null
+exported=$($VALKEY_CLI -h "$HOST" --raw am.tojson merge_test1)
+assert_equals "$(echo "$exported" | jq -r '.user.name')" "Alice"
+assert_equals "$(echo "$exported" | jq -r '.user.age')" "31"
+assert_equals "$(echo "$exported" | jq -r '.user.city')" "Paris"
+assert_equals "$(echo "$exported" | jq -r '.title')" "Doc"
+# History continues: exactly one new change on top of the previous heads
+changes_after=$($VALKEY_CLI -h "$HOST" am.numchanges merge_test1)
+assert_equals "$changes_after" "$((changes_before + 1))"
+heads_after=$($VALKEY_CLI -h "$HOST" --raw am.getheads merge_test1 | xxd -p)
+if [ "$heads_before" = "$heads_after" ]; then
+    echo "   ✗ Heads did not advance after MERGE"
+    exit 1
+fi
+# MERGE takes commit metadata for its change
+$VALKEY_CLI -h "$HOST" am.fromjson merge_test1 '{"user":{"age":32}}' MERGE MSG "birthday" TIME 1700000000000 > /dev/null
+if ! $VALKEY_CLI -h "$HOST" am.history merge_test1 | grep -q "^birthday$"; then
+    echo "   ✗ AM.FROMJSON MERGE MSG should set the change message"
+    exit 1
+fi
+result=$($VALKEY_CLI -h "$HOST" am.fromjson merge_test1 '{"a":1}' MSG "no merge" 2>&1)
+if ! echo "$result" | grep -q "expected MERGE"; then
+    echo "   ✗ Options without MERGE should fail, got: $result"
+    exit 1
+fi
+echo "   ✓ AM.FROMJSON MERGE preserves history"
+
+echo "Test 22: AM.FROMJSON MERGE on a missing key creates it..."
+$VALKEY_CLI -h "$HOST" del merge_test2 > /dev/null
+$VALKEY_CLI -h "$HOST" am.fromjson merge_test2 '{"name":"Bob"}' MERGE > /dev/null
+result=$($VALKEY_CLI -h "$HOST" --raw am.gettext merge_test2 name)
+assert_equals "$result" "Bob"
+echo "   ✓ AM.FROMJSON MERGE creates missing documents"
+
+echo "Test 23: AM.NEW with an initial JSON body..."
+$VALKEY_CLI -h "$HOST" del new_json new_empty > /dev/null
+$VALKEY_CLI -h "$HOST" am.new new_empty > /dev/null
+assert_equals "$($VALKEY_CLI -h "$HOST" --raw am.tojson new_empty)" "{}"
+result=$($VALKEY_CLI -h "$HOST" am.new new_json JSON '{"name":"Alice","tags":["admin","dev"]}')
+assert_equals "$result" "OK"
+assert_equals "$($VALKEY_CLI -h "$HOST" --raw am.gettext new_json name)" "Alice"
+assert_equals "$($VALKEY_CLI -h "$HOST" am.listlen new_json tags)" "2"
+# Seeding an existing key is refused and leaves it untouched
+result=$($VALKEY_CLI -h "$HOST" am.new new_json JSON '{"name":"Bob"}' 2>&1)
+if ! echo "$result" | grep -q "key already exists"; then
+    echo "   ✗ Expected key already exists error: $result"
+    exit 1
+fi
+assert_equals "$($VALKEY_CLI -h "$HOST" --raw am.gettext new_json name)" "Alice"
+# REPLACE seeds over the existing document
+$VALKEY_CLI -h "$HOST" am.new new_json JSON '{"name":"Bob"}' REPLACE > /dev/null
+assert_equals "$($VALKEY_CLI -h "$HOST" --raw am.tojson new_json)" '{"name":"Bob"}'
+$VALKEY_CLI -h "$HOST" del new_json > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.new new_json JSON '[1,2]' 2>&1)
+if [ "$result" = "OK" ]; then
+    echo "   ✗ Expected a non-object JSON body to be rejected"
+    exit 1
+fi
+assert_equals "$($VALKEY_CLI -h "$HOST" exists new_json)" "0"
+echo "   ✓ AM.NEW seeds documents from JSON"
+
+echo "Test 24: AM.NEW refuses to overwrite existing keys..."
+$VALKEY_CLI -h "$HOST" del new_twice new_string > /dev/null
+$VALKEY_CLI -h "$HOST" am.new new_twice > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext new_twice title "Keep me" > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.new new_twice 2>&1)
+if ! echo "$result" | grep -q "key already exists, use AM.CLEAR or DEL first"; then
+    echo "   ✗ Expected key already exists error: $result"
+    exit 1
+fi
+assert_equals "$($VALKEY_CLI -h "$HOST" --raw am.gettext new_twice title)" "Keep me"
+result=$($VALKEY_CLI -h "$HOST" am.new new_twice REPLACE)
+assert_equals "$result" "OK"
+assert_equals "$($VALKEY_CLI -h "$HOST" --raw am.tojson new_twice)" "{}"
+# A key of another type is a type error, even with REPLACE
+$VALKEY_CLI -h "$HOST" set new_string "plain" > /dev/null
+for flag in "" REPLACE; do
+    result=$($VALKEY_CLI -h "$HOST" am.new new_string $flag 2>&1)
+    if ! echo "$result" | grep -q "WRONGTYPE"; then
+        echo "   ✗ Expected WRONGTYPE error: $result"
+        exit 1
+    fi
+done
+assert_equals "$($VALKEY_CLI -h "$HOST" get new_string)" "plain"
+echo "   ✓ AM.NEW only replaces documents with REPLACE"
+
 echo ""
 echo "✅ All JSON operation tests passed!"
@@ -214,5 +214,29 @@ else
     echo "   ⚠ Skipped restart test (Docker not available)"
 fi
 
+echo "Test 6: Pinned actor survives AOF rewrite..."
+$VALKEY_CLI -h "$HOST" del aof_actor_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new aof_actor_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.setactor aof_actor_test 0a1b2c3d4e5f > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext aof_actor_test data "Pinned" > /dev/null
+
+# The rewritten AOF no longer contains the original AM.SETACTOR command
+$VALKEY_CLI -h "$HOST" BGREWRITEAOF > /dev/null
+sleep 2  # Wait for rewrite to complete
+
+if restart_redis; then
+    actor=$($VALKEY_CLI -h "$HOST" --raw am.getactor aof_actor_test)
+    data=$($VALKEY_CLI -h "$HOST" --raw am.gettext aof_actor_test data)
+
+    if [ "$actor" = "0a1b2c3d4e5f" ] && [ "$data" = "Pinned" ]; then
+        echo "   ✓ Pinned actor survives AOF rewrite and restart"
+    else
+        echo "   ✗ Actor lost after AOF rewrite (actor='$actor', data='$data')"
+        exit 1
+    fi
+else
+    echo "   ⚠ Skipped restart test (Docker not available)"
+fi
+
 echo ""
 echo "✅ All AOF persistence tests passed!"
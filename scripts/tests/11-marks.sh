@@ -227,5 +227,37 @@ result=$($VALKEY_CLI -h "$HOST" am.markclear marks_clear_expand text temp 0 5 bo
 assert_equals "$result" "OK"
 echo "   ✓ AM.MARKCLEAR accepts expand parameter"
 
+echo "Test 18: AM.MARKS NAME filter..."
+$VALKEY_CLI -h "$HOST" del marks_filter > /dev/null
+$VALKEY_CLI -h "$HOST" am.new marks_filter > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext marks_filter text "Hello brave world" > /dev/null
+$VALKEY_CLI -h "$HOST" am.markcreate marks_filter text bold true 0 5 > /dev/null
+$VALKEY_CLI -h "$HOST" am.markcreate marks_filter text comment "check this" 6 11 > /dev/null
+marks=$($VALKEY_CLI -h "$HOST" am.marks marks_filter text NAME comment)
+if [[ "$marks" != *"comment"* ]] || [[ "$marks" == *"bold"* ]]; then
+    echo "   ✗ Expected only the comment mark: $marks"
+    exit 1
+fi
+marks=$($VALKEY_CLI -h "$HOST" am.marks marks_filter text name italic)
+assert_equals "$marks" ""
+result=$($VALKEY_CLI -h "$HOST" am.marks marks_filter text NAMES bold 2>&1)
+if ! echo "$result" | grep -q "expected NAME"; then
+    echo "   ✗ Expected syntax error: $result"
+    exit 1
+fi
+echo "   ✓ AM.MARKS filters by name"
+
+echo "Test 19: AM.MARKS resolves overlapping marks..."
+$VALKEY_CLI -h "$HOST" del marks_overlap > /dev/null
+$VALKEY_CLI -h "$HOST" am.new marks_overlap > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext marks_overlap text "Hello World" > /dev/null
+$VALKEY_CLI -h "$HOST" am.markcreate marks_overlap text color red 0 7 > /dev/null
+$VALKEY_CLI -h "$HOST" am.markcreate marks_overlap text color blue 4 11 > /dev/null
+marks=$($VALKEY_CLI -h "$HOST" am.marks marks_overlap text | tr '\n' ' ')
+assert_equals "$marks" "color red 0 4 color blue 4 11 "
+marks=$($VALKEY_CLI -h "$HOST" am.marks marks_overlap text name size)
+assert_equals "$marks" ""
+echo "   ✓ AM.MARKS returns resolved segments"
+
 echo ""
 echo "✅ All marks tests passed!"
@@ -30,8 +30,8 @@ if echo "$result" | grep -qi "error\|ERR"; then
     exit 1
 fi
 
-# Verify result contains patch data (Debug format starts with '[')
-if echo "$result" | grep -q "^\["; then
+# Verify result is a JSON array of patches
+if echo "$result" | jq -e 'type == "array"' > /dev/null 2>&1; then
     echo "   ✓ Diff from empty to current state returns patch data"
 else
     echo "   ✗ Result doesn't look like patch data: $result"
@@ -180,6 +180,92 @@ len=$($VALKEY_CLI -h "$HOST" am.maplen mapdoc10 outer.another)
 assert_equals "$len" "1"
 echo "   ✓ Empty nested maps work"
 
+# Test 11: AM.GETALL returns sorted key/value pairs
+echo "Test 11: AM.GETALL returns sorted key/value pairs..."
+$VALKEY_CLI -h "$HOST" del mapdoc11 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new mapdoc11 > /dev/null
+# Empty map returns an empty array
+result=$($VALKEY_CLI -h "$HOST" am.getall mapdoc11 "")
+assert_equals "$result" ""
+$VALKEY_CLI -h "$HOST" am.puttext mapdoc11 user.name "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint mapdoc11 user.age 30 > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.getall mapdoc11 user | tr '\n' ' ')
+assert_equals "$result" "age 30 name Alice "
+# Non-map paths return null
+$VALKEY_CLI -h "$HOST" am.createlist mapdoc11 items > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.getall mapdoc11 items)
+assert_equals "$result" ""
+echo "   ✓ AM.GETALL works"
+
+# Test 12: AM.CREATEMAP creates an explicit empty map
+echo "Test 12: AM.CREATEMAP creates an explicit empty map..."
+$VALKEY_CLI -h "$HOST" del mapdoc12 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new mapdoc12 > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.createmap mapdoc12 settings)
+assert_equals "$result" "OK"
+len=$($VALKEY_CLI -h "$HOST" am.maplen mapdoc12 settings)
+assert_equals "$len" "0"
+json=$($VALKEY_CLI -h "$HOST" am.tojson mapdoc12)
+assert_equals "$json" '{"settings":{}}'
+$VALKEY_CLI -h "$HOST" am.mset mapdoc12 settings.theme text dark settings.size int 12 > /dev/null
+len=$($VALKEY_CLI -h "$HOST" am.maplen mapdoc12 settings)
+assert_equals "$len" "2"
+# Trailing options follow the last value, and a path named MSG is still a path
+hash=$($VALKEY_CLI -h "$HOST" am.mset mapdoc12 settings.theme text light MSG int 1 MSG "batch" WITHHASH)
+if [ -z "$hash" ] || [ "$hash" = "OK" ]; then
+    echo "   ✗ AM.MSET WITHHASH should return a change hash, got: $hash"
+    exit 1
+fi
+theme=$($VALKEY_CLI -h "$HOST" am.gettext mapdoc12 settings.theme)
+assert_equals "$theme" "light"
+msg=$($VALKEY_CLI -h "$HOST" am.getint mapdoc12 MSG)
+assert_equals "$msg" "1"
+if ! $VALKEY_CLI -h "$HOST" am.history mapdoc12 | grep -q "^batch$"; then
+    echo "   ✗ AM.MSET MSG should set the change message"
+    exit 1
+fi
+result=$($VALKEY_CLI -h "$HOST" am.mset mapdoc12 settings.theme text 2>&1)
+if ! echo "$result" | grep -qi "wrong number"; then
+    echo "   ✗ AM.MSET with a missing value should fail, got: $result"
+    exit 1
+fi
+# A list element can't hold a map key
+$VALKEY_CLI -h "$HOST" am.createlist mapdoc12 items > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.createmap mapdoc12 "items[0]" 2>&1)
+if ! echo "$result" | grep -qi "err\|fail\|invalid"; then
+    echo "   ✗ Creating a map at a list index should fail, got: $result"
+    exit 1
+fi
+echo "   ✓ AM.CREATEMAP works"
+
+echo "Test 13: AM.CLEAR empties a map or list in place..."
+$VALKEY_CLI -h "$HOST" del clear_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.new clear_test > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext clear_test user.name "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint clear_test user.age 30 > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist clear_test tags > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext clear_test tags "a" > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext clear_test tags "b" > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.clear clear_test user)
+assert_equals "$result" "1"
+len=$($VALKEY_CLI -h "$HOST" am.maplen clear_test user)
+assert_equals "$len" "0"
+type=$($VALKEY_CLI -h "$HOST" am.type clear_test user)
+assert_equals "$type" "map"
+result=$($VALKEY_CLI -h "$HOST" am.clear clear_test tags)
+assert_equals "$result" "1"
+len=$($VALKEY_CLI -h "$HOST" am.listlen clear_test tags)
+assert_equals "$len" "0"
+type=$($VALKEY_CLI -h "$HOST" am.type clear_test tags)
+assert_equals "$type" "list"
+$VALKEY_CLI -h "$HOST" am.puttext clear_test name "Bob" > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.clear clear_test name 2>&1)
+if ! echo "$result" | grep -q "not a map or list"; then
+    echo "   ✗ Expected non-container error: $result"
+    exit 1
+fi
+echo "   ✓ AM.CLEAR works"
+
 rm -f /tmp/map-saved.bin
 
 echo ""
@@ -97,10 +97,12 @@ $VALKEY_CLI -h "$HOST" am.new edgedoc7 > /dev/null
 $VALKEY_CLI -h "$HOST" am.createlist edgedoc7 items > /dev/null
 $VALKEY_CLI -h "$HOST" am.appendtext edgedoc7 items "a" > /dev/null
 $VALKEY_CLI -h "$HOST" am.appendtext edgedoc7 items "b" > /dev/null
-# MAPLEN on a list returns the number of indices (same as list length)
+# MAPLEN on a list returns null rather than the list length
 result=$($VALKEY_CLI -h "$HOST" am.maplen edgedoc7 items)
+assert_equals "$result" ""
+result=$($VALKEY_CLI -h "$HOST" am.listlen edgedoc7 items)
 assert_equals "$result" "2"
-echo "   ✓ MAPLEN on list returns number of indices (same as LISTLEN)"
+echo "   ✓ MAPLEN on list returns null"
 
 # Test 8: LISTLEN on map
 echo "Test 8: LISTLEN on map..."
@@ -108,11 +110,12 @@ $VALKEY_CLI -h "$HOST" del edgedoc8 > /dev/null
 $VALKEY_CLI -h "$HOST" am.new edgedoc8 > /dev/null
 $VALKEY_CLI -h "$HOST" am.puttext edgedoc8 user.name "Bob" > /dev/null
 $VALKEY_CLI -h "$HOST" am.putint edgedoc8 user.age 30 > /dev/null
-# LISTLEN on a map returns the number of keys (same as MAPLEN)
-# This is because in Automerge, both maps and lists support length operation
+# LISTLEN on a map returns null rather than the number of keys
 result=$($VALKEY_CLI -h "$HOST" am.listlen edgedoc8 user)
+assert_equals "$result" ""
+result=$($VALKEY_CLI -h "$HOST" am.maplen edgedoc8 user)
 assert_equals "$result" "2"
-echo "   ✓ LISTLEN on map returns number of keys (same as MAPLEN)"
+echo "   ✓ LISTLEN on map returns null"
 
 # Test 9: Accessing nested map properties within simple list items
 echo "Test 9: List with nested map structure..."
@@ -187,6 +190,58 @@ echo "$json" | jq -e '.nested.values[0] == true' > /dev/null
 echo "$json" | jq -e '.nested.meta.count == 42' > /dev/null
 echo "   ✓ JSON export of complex nested structure works"
 
+echo "Test 12: Empty path refers to the root map..."
+$VALKEY_CLI -h "$HOST" del edgedoc12 > /dev/null
+$VALKEY_CLI -h "$HOST" am.new edgedoc12 > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext edgedoc12 name "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist edgedoc12 tags > /dev/null
+for root in "" '$.'; do
+    result=$($VALKEY_CLI -h "$HOST" am.type edgedoc12 "$root")
+    assert_equals "$result" "map"
+    result=$($VALKEY_CLI -h "$HOST" am.maplen edgedoc12 "$root")
+    assert_equals "$result" "2"
+    result=$($VALKEY_CLI -h "$HOST" am.listlen edgedoc12 "$root")
+    assert_equals "$result" ""
+    result=$($VALKEY_CLI -h "$HOST" am.gettext edgedoc12 "$root")
+    assert_equals "$result" ""
+done
+result=$($VALKEY_CLI -h "$HOST" --raw am.mget edgedoc12 "")
+echo "$result" | jq -e '.name == "Alice" and .tags == []' > /dev/null
+# A bare $ is a key like any other
+$VALKEY_CLI -h "$HOST" am.puttext edgedoc12 '$' "dollar" > /dev/null
+result=$($VALKEY_CLI -h "$HOST" am.gettext edgedoc12 '$')
+assert_equals "$result" "dollar"
+echo "   ✓ Empty path refers to the root map"
+
+echo "Test 13: Missing key errors, missing path is null..."
+$VALKEY_CLI -h "$HOST" del edgedoc13 nosuchdoc > /dev/null
+$VALKEY_CLI -h "$HOST" am.new edgedoc13 > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist edgedoc13 tags > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext edgedoc13 tags "a" > /dev/null
+$VALKEY_CLI -h "$HOST" am.createmap edgedoc13 cfg > /dev/null
+for cmd in gettext textlen getint getdouble getbool getbytes getcounter gettimestamp \
+    listlen maplen keys values getall type getobjid mget; do
+    result=$($VALKEY_CLI -h "$HOST" am.$cmd nosuchdoc tags 2>&1)
+    if ! echo "$result" | grep -q "no such key"; then
+        echo "   ✗ Expected am.$cmd to report a missing key: $result"
+        exit 1
+    fi
+    for path in missing 'tags.x' 'cfg[0]' 'tags[0].x'; do
+        result=$($VALKEY_CLI -h "$HOST" am.$cmd edgedoc13 "$path" 2>&1)
+        assert_equals "$result" ""
+    done
+done
+result=$($VALKEY_CLI -h "$HOST" am.lrange nosuchdoc tags 0 -1 2>&1)
+if ! echo "$result" | grep -q "no such key"; then
+    echo "   ✗ Expected am.lrange to report a missing key: $result"
+    exit 1
+fi
+result=$($VALKEY_CLI -h "$HOST" am.lrange edgedoc13 'cfg[0]' 0 -1 2>&1)
+assert_equals "$result" ""
+result=$($VALKEY_CLI -h "$HOST" am.lindexof edgedoc13 'tags.x' text "a" 2>&1)
+assert_equals "$result" ""
+echo "   ✓ Readers tell a missing key from a missing path"
+
 rm -f /tmp/edge-saved.bin
 
 echo ""
@@ -133,6 +133,7 @@ echo "$status" | grep -q "article:\*"
 echo "$status" | grep -q "enabled"
 echo "$status" | grep -q "title"
 echo "$status" | grep -q "content"
+echo "$status" | grep -q "format"
 echo "   ✓ STATUS command returns configuration"
 
 # Test 10: Non-matching key does not create index
@@ -361,6 +362,321 @@ if echo "$results" | grep -q "am:idx:article:search3"; then
 fi
 echo "   ✓ Content tag search works correctly"
 
+# Test 21: Numeric and boolean fields in the Hash index
+echo "Test 21: Numeric and boolean fields in the Hash index..."
+$VALKEY_CLI -h "$HOST" am.index.configure "product:*" name price stock active > /dev/null
+$VALKEY_CLI -h "$HOST" del "product:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "product:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "product:1" name "Widget" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putdouble "product:1" price 9.5 > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint "product:1" stock 42 > /dev/null
+$VALKEY_CLI -h "$HOST" am.putbool "product:1" active true > /dev/null
+stock=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:product:1" stock)
+active=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:product:1" active)
+price=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:product:1" price)
+assert_equals "$stock" "42"
+assert_equals "$active" "true"
+assert_equals "$price" "9.5"
+$VALKEY_CLI -h "$HOST" del "product:1" "am:idx:product:1" "am:index:config:product:*" > /dev/null
+echo "   ✓ Int, double and bool fields stringified into the shadow Hash"
+
+# Test 22: List and nested map paths in the Hash index
+echo "Test 22: List and nested map paths in the Hash index..."
+$VALKEY_CLI -h "$HOST" am.index.configure "post:*" --separator " " tags author > /dev/null
+$VALKEY_CLI -h "$HOST" del "post:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "post:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.createlist "post:1" tags > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext "post:1" tags rust > /dev/null
+$VALKEY_CLI -h "$HOST" am.appendtext "post:1" tags crdt > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "post:1" author.name "Alice" > /dev/null
+$VALKEY_CLI -h "$HOST" am.putint "post:1" author.age 30 > /dev/null
+tags=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:post:1" tags)
+name=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:post:1" author_name)
+age=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:post:1" author_age)
+assert_equals "$tags" "rust crdt"
+assert_equals "$name" "Alice"
+assert_equals "$age" "30"
+$VALKEY_CLI -h "$HOST" del "post:1" "am:idx:post:1" "am:index:config:post:*" > /dev/null
+echo "   ✓ List joined with separator and map flattened into fields"
+
+# Test 23: Deleting a document removes its shadow index
+echo "Test 23: Deleting a document removes its shadow index..."
+$VALKEY_CLI -h "$HOST" del "article:deleteme" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "article:deleteme" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "article:deleteme" title "Short-lived" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:deleteme")
+assert_equals "$exists" "1"
+$VALKEY_CLI -h "$HOST" del "article:deleteme" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:deleteme")
+assert_equals "$exists" "0"
+# Keys outside any configured pattern are left alone
+$VALKEY_CLI -h "$HOST" set "am:idx:unindexed:1" keep > /dev/null
+$VALKEY_CLI -h "$HOST" set "unindexed:1" value > /dev/null
+$VALKEY_CLI -h "$HOST" del "unindexed:1" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:unindexed:1")
+assert_equals "$exists" "1"
+$VALKEY_CLI -h "$HOST" del "am:idx:unindexed:1" > /dev/null
+# Renaming a document away removes the shadow of its old key
+$VALKEY_CLI -h "$HOST" del "article:renameme" "renamed:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "article:renameme" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "article:renameme" title "Moving" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:renameme")
+assert_equals "$exists" "1"
+$VALKEY_CLI -h "$HOST" rename "article:renameme" "renamed:1" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:renameme")
+assert_equals "$exists" "0"
+$VALKEY_CLI -h "$HOST" del "renamed:1" > /dev/null
+# A restored document is indexed like any other write
+$VALKEY_CLI -h "$HOST" del "article:restored" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "dump:source" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "dump:source" title "Restored" > /dev/null
+dumped=$($VALKEY_CLI -h "$HOST" --raw am.dump "dump:source")
+$VALKEY_CLI -h "$HOST" am.restore "article:restored" "$dumped" > /dev/null
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:article:restored" title)
+assert_equals "$title" "Restored"
+$VALKEY_CLI -h "$HOST" del "dump:source" "article:restored" > /dev/null
+echo "   ✓ Shadow index removed with its document"
+
+# Test 24: Reindex every document matching a pattern
+echo "Test 24: Reindex every document matching a pattern..."
+for i in 1 2 3; do
+    $VALKEY_CLI -h "$HOST" del "note:$i" "am:idx:note:$i" > /dev/null
+    $VALKEY_CLI -h "$HOST" am.new "note:$i" > /dev/null
+    $VALKEY_CLI -h "$HOST" am.puttext "note:$i" title "Note $i" > /dev/null
+done
+$VALKEY_CLI -h "$HOST" set "note:plain" value > /dev/null
+# Configured after the documents were written, so nothing is indexed yet
+$VALKEY_CLI -h "$HOST" am.index.configure "note:*" title > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:note:1")
+assert_equals "$exists" "0"
+count=$($VALKEY_CLI -h "$HOST" am.index.reindexall "note:*")
+assert_equals "$count" "3"
+for i in 1 2 3; do
+    title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:note:$i" title)
+    assert_equals "$title" "Note $i"
+done
+# Cursor mode: step through the keyspace until the cursor returns to 0
+$VALKEY_CLI -h "$HOST" del "am:idx:note:1" "am:idx:note:2" "am:idx:note:3" > /dev/null
+cursor=0
+total=0
+while true; do
+    reply=$($VALKEY_CLI -h "$HOST" --raw am.index.reindexall "note:*" CURSOR "$cursor" COUNT 1)
+    cursor=$(echo "$reply" | sed -n 1p)
+    total=$((total + $(echo "$reply" | sed -n 2p)))
+    [ "$cursor" = "0" ] && break
+done
+assert_equals "$total" "3"
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:note:2" title)
+assert_equals "$title" "Note 2"
+$VALKEY_CLI -h "$HOST" del "note:1" "note:2" "note:3" "note:plain" "am:idx:note:1" "am:idx:note:2" "am:idx:note:3" "am:index:config:note:*" > /dev/null
+echo "   ✓ All matching documents reindexed, in one call and by cursor"
+
+# Test 25: Config matching and status with many configurations
+echo "Test 25: Config matching and status with many configurations..."
+for i in $(seq 1 150); do
+    $VALKEY_CLI -h "$HOST" am.index.configure "bulk$i:*" title > /dev/null
+done
+$VALKEY_CLI -h "$HOST" del "bulk150:doc" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "bulk150:doc" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "bulk150:doc" title "Found" > /dev/null
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:bulk150:doc" title)
+assert_equals "$title" "Found"
+# More configurations than a single SCAN step returns are all reported
+patterns=$($VALKEY_CLI -h "$HOST" --raw am.index.status "bulk*" | grep -c '^bulk[0-9]*:\*$')
+assert_equals "$patterns" "150"
+$VALKEY_CLI -h "$HOST" del "bulk150:doc" "am:idx:bulk150:doc" > /dev/null
+for i in $(seq 1 150); do
+    $VALKEY_CLI -h "$HOST" del "am:index:config:bulk$i:*" > /dev/null
+done
+echo "   ✓ Matching config found and all configs listed via SCAN"
+
+# Test 26: Configuration changes apply to the very next write
+echo "Test 26: Configuration changes apply to the very next write..."
+$VALKEY_CLI -h "$HOST" del "memo:1" "am:idx:memo:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "memo:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "memo:1" title "Before" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:memo:1")
+assert_equals "$exists" "0"
+$VALKEY_CLI -h "$HOST" am.index.configure "memo:*" title > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "memo:1" title "Configured" > /dev/null
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:memo:1" title)
+assert_equals "$title" "Configured"
+$VALKEY_CLI -h "$HOST" am.index.disable "memo:*" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "memo:1" title "Disabled" > /dev/null
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:memo:1" title)
+assert_equals "$title" "Configured"
+$VALKEY_CLI -h "$HOST" am.index.enable "memo:*" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "memo:1" title "Enabled" > /dev/null
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:memo:1" title)
+assert_equals "$title" "Enabled"
+# Removing the configuration key directly is picked up too
+$VALKEY_CLI -h "$HOST" del "am:index:config:memo:*" "am:idx:memo:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "memo:1" title "Removed" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:memo:1")
+assert_equals "$exists" "0"
+$VALKEY_CLI -h "$HOST" del "memo:1" > /dev/null
+echo "   ✓ Configure, disable, enable and removal take effect immediately"
+
+# Test 27: Index matches the document after rapid successive writes
+echo "Test 27: Index matches the document after rapid successive writes..."
+$VALKEY_CLI -h "$HOST" am.index.configure "rapid:*" title version > /dev/null
+$VALKEY_CLI -h "$HOST" del "rapid:1" "am:idx:rapid:1" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "rapid:1" > /dev/null
+# Send the writes in one pipelined batch
+for i in $(seq 1 200); do
+    echo "am.puttext rapid:1 title \"Title $i\""
+    echo "am.putint rapid:1 version $i"
+done | $VALKEY_CLI -h "$HOST" > /dev/null
+title=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:rapid:1" title)
+version=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:rapid:1" version)
+assert_equals "$title" "$($VALKEY_CLI -h "$HOST" --raw am.gettext "rapid:1" title)"
+assert_equals "$title" "Title 200"
+assert_equals "$version" "200"
+for i in 1 2 3; do
+    $VALKEY_CLI -h "$HOST" am.putint "rapid:1" version "$((200 + i))" > /dev/null
+    version=$($VALKEY_CLI -h "$HOST" --raw hget "am:idx:rapid:1" version)
+    assert_equals "$version" "$((200 + i))"
+done
+$VALKEY_CLI -h "$HOST" del "rapid:1" "am:idx:rapid:1" "am:index:config:rapid:*" > /dev/null
+echo "   ✓ Shadow index reflects every write exactly"
+
+# Test 28: Dropping a configuration with PURGE removes its shadow documents
+echo "Test 28: Dropping a configuration with PURGE removes its shadow documents..."
+$VALKEY_CLI -h "$HOST" am.index.configure "ticket:*" title > /dev/null
+for i in 1 2 3; do
+    $VALKEY_CLI -h "$HOST" del "ticket:$i" "am:idx:ticket:$i" > /dev/null
+    $VALKEY_CLI -h "$HOST" am.new "ticket:$i" > /dev/null
+    $VALKEY_CLI -h "$HOST" am.puttext "ticket:$i" title "Ticket $i" > /dev/null
+done
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:ticket:1" "am:idx:ticket:2" "am:idx:ticket:3")
+assert_equals "$exists" "3"
+removed=$($VALKEY_CLI -h "$HOST" am.index.drop "ticket:*" PURGE)
+assert_equals "$removed" "3"
+exists=$($VALKEY_CLI -h "$HOST" exists "am:index:config:ticket:*" "am:idx:ticket:1" "am:idx:ticket:2" "am:idx:ticket:3")
+assert_equals "$exists" "0"
+# Documents themselves are untouched and no longer indexed
+$VALKEY_CLI -h "$HOST" am.puttext "ticket:1" title "After drop" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:ticket:1")
+assert_equals "$exists" "0"
+# Without PURGE only the configuration goes
+$VALKEY_CLI -h "$HOST" am.index.configure "ticket:*" title > /dev/null
+$VALKEY_CLI -h "$HOST" am.index.reindex "ticket:2" > /dev/null
+removed=$($VALKEY_CLI -h "$HOST" am.index.drop "ticket:*")
+assert_equals "$removed" "0"
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:ticket:2")
+assert_equals "$exists" "1"
+result=$($VALKEY_CLI -h "$HOST" am.index.drop "ticket:*" 2>&1 || true)
+if ! echo "$result" | grep -q "No index configuration"; then
+    echo "   ✗ Dropping a missing configuration should fail, got: $result"
+    exit 1
+fi
+$VALKEY_CLI -h "$HOST" del "ticket:1" "ticket:2" "ticket:3" "am:idx:ticket:2" > /dev/null
+echo "   ✓ Configuration and shadow documents removed"
+
+# Test 29: STATUS reports format and matched keys for each configuration
+echo "Test 29: STATUS reports format and matched keys for each configuration..."
+$VALKEY_CLI -h "$HOST" am.index.configure "statushash:*" title > /dev/null
+$VALKEY_CLI -h "$HOST" am.index.configure "statusjson:*" --format json title > /dev/null
+for i in 1 2; do
+    $VALKEY_CLI -h "$HOST" del "statushash:$i" > /dev/null
+    $VALKEY_CLI -h "$HOST" am.new "statushash:$i" > /dev/null
+done
+# Entries are field/value pairs: pattern, enabled, format, paths, separator, matched_keys_estimate
+# The test keyspace fits in one SCAN step, so the estimate is exact
+status=$($VALKEY_CLI -h "$HOST" --raw am.index.status "statushash:*")
+assert_equals "$(echo "$status" | sed -n 2p)" "statushash:*"
+assert_equals "$(echo "$status" | sed -n 4p)" "1"
+assert_equals "$(echo "$status" | sed -n 6p)" "hash"
+assert_equals "$(echo "$status" | sed -n 8p)" "title"
+assert_equals "$(echo "$status" | sed -n 12p)" "2"
+status=$($VALKEY_CLI -h "$HOST" --raw am.index.status "statusjson:*")
+assert_equals "$(echo "$status" | sed -n 6p)" "json"
+assert_equals "$(echo "$status" | sed -n 12p)" "0"
+# Both configurations are listed, in pattern order
+patterns=$($VALKEY_CLI -h "$HOST" --raw am.index.status "status*" | grep '^status.*:\*$' | tr '\n' ' ')
+assert_equals "$patterns" "statushash:* statusjson:* "
+$VALKEY_CLI -h "$HOST" del "statushash:1" "statushash:2" "am:index:config:statushash:*" "am:index:config:statusjson:*" > /dev/null
+echo "   ✓ Format and matched key count reported for hash and json configurations"
+
+# Test 30: An expired document removes its shadow index
+echo "Test 30: An expired document removes its shadow index..."
+$VALKEY_CLI -h "$HOST" del "article:expireme" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "article:expireme" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "article:expireme" title "Short-lived" > /dev/null
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:expireme")
+assert_equals "$exists" "1"
+$VALKEY_CLI -h "$HOST" pexpire "article:expireme" 200 > /dev/null
+sleep 1
+# Touching the key expires it now if the active expiry cycle hasn't yet
+exists=$($VALKEY_CLI -h "$HOST" exists "article:expireme")
+assert_equals "$exists" "0"
+sleep 0.2
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:expireme")
+assert_equals "$exists" "0"
+echo "   ✓ Shadow index removed when its document expires"
+
+# Test 31: Index updates applied by the background worker
+echo "Test 31: Background index updates with automerge.index-async..."
+$VALKEY_CLI -h "$HOST" config set automerge.index-async yes > /dev/null
+$VALKEY_CLI -h "$HOST" del "article:async" > /dev/null
+$VALKEY_CLI -h "$HOST" am.new "article:async" > /dev/null
+$VALKEY_CLI -h "$HOST" am.puttext "article:async" title "Queued" > /dev/null
+sleep 0.2
+title=$($VALKEY_CLI -h "$HOST" hget "am:idx:article:async" title)
+assert_equals "$title" "Queued"
+# Rapid writes: the index settles on the last one
+for i in $(seq 1 200); do
+    echo "am.puttext article:async title \"Revision $i\""
+done | $VALKEY_CLI -h "$HOST" > /dev/null
+sleep 0.5
+title=$($VALKEY_CLI -h "$HOST" hget "am:idx:article:async" title)
+assert_equals "$title" "Revision 200"
+# A delete is queued behind the updates, so the index doesn't come back
+for i in $(seq 1 50); do
+    echo "am.puttext article:async title \"Late $i\""
+done | $VALKEY_CLI -h "$HOST" > /dev/null
+$VALKEY_CLI -h "$HOST" del "article:async" > /dev/null
+sleep 0.5
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:async")
+assert_equals "$exists" "0"
+# Updates are written to the database of the command that queued them
+$VALKEY_CLI -h "$HOST" -n 1 am.index.configure "article:*" title > /dev/null
+$VALKEY_CLI -h "$HOST" -n 1 del "article:async" > /dev/null
+$VALKEY_CLI -h "$HOST" -n 1 am.new "article:async" > /dev/null
+$VALKEY_CLI -h "$HOST" -n 1 am.puttext "article:async" title "Elsewhere" > /dev/null
+sleep 0.2
+title=$($VALKEY_CLI -h "$HOST" -n 1 hget "am:idx:article:async" title)
+assert_equals "$title" "Elsewhere"
+exists=$($VALKEY_CLI -h "$HOST" exists "am:idx:article:async")
+assert_equals "$exists" "0"
+# Updates still queued when their configuration is dropped aren't applied
+$VALKEY_CLI -h "$HOST" -n 1 am.index.configure "draft:*" title > /dev/null
+$VALKEY_CLI -h "$HOST" -n 1 am.new "draft:async" > /dev/null
+{
+    for i in $(seq 1 200); do
+        echo "am.puttext draft:async title \"Draft $i\""
+    done
+    echo "am.index.drop draft:* PURGE"
+} | $VALKEY_CLI -h "$HOST" -n 1 > /dev/null
+sleep 0.5
+exists=$($VALKEY_CLI -h "$HOST" -n 1 exists "am:idx:draft:async")
+assert_equals "$exists" "0"
+$VALKEY_CLI -h "$HOST" -n 1 flushdb > /dev/null
+# Turning the setting off: a synchronous write isn't overwritten by queued updates
+$VALKEY_CLI -h "$HOST" am.new "article:async" > /dev/null
+{
+    for i in $(seq 1 200); do
+        echo "am.puttext article:async title \"Queued $i\""
+    done
+    echo "config set automerge.index-async no"
+    echo "am.puttext article:async title Synchronous"
+} | $VALKEY_CLI -h "$HOST" > /dev/null
+sleep 0.5
+title=$($VALKEY_CLI -h "$HOST" hget "am:idx:article:async" title)
+assert_equals "$title" "Synchronous"
+$VALKEY_CLI -h "$HOST" del "article:async" > /dev/null
+echo "   ✓ Background index updates are applied in order"
+
 # Cleanup
 $VALKEY_CLI -h "$HOST" ft.dropindex idx:test_articles > /dev/null 2>&1 || true
 
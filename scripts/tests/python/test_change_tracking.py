@@ -3,6 +3,8 @@ Change tracking and persistence tests for valkey-automerge.
 
 Tests that changes are properly tracked and preserved through save/load cycles.
 """
+import json
+
 import pytest
 
 
@@ -363,3 +365,168 @@ async def test_change_hash_consistency(redis_client, clean_redis):
     # All changes should be binary data
     assert all(isinstance(c, bytes) for c in changes_before)
     assert all(isinstance(c, bytes) for c in changes_after)
+
+
+@pytest.mark.persistence
+async def test_getat_reads_value_at_old_heads(redis_client, clean_redis):
+    """Test that AM.GETAT reads a field as it was at earlier heads."""
+    await redis_client.execute_command('AM.NEW', 'test16')
+    await redis_client.execute_command('AM.PUTTEXT', 'test16', 'status', 'draft')
+    await redis_client.execute_command('AM.PUTINT', 'test16', 'revision', 1)
+    heads = await redis_client.execute_command('AM.GETHEADS', 'test16')
+
+    await redis_client.execute_command('AM.PUTTEXT', 'test16', 'status', 'published')
+    await redis_client.execute_command('AM.PUTINT', 'test16', 'revision', 2)
+    await redis_client.execute_command('AM.PUTTEXT', 'test16', 'author', 'Alice')
+
+    assert await redis_client.execute_command('AM.GETAT', 'test16', 'status', *heads) == b'draft'
+    assert await redis_client.execute_command('AM.GETAT', 'test16', 'revision', *heads) == 1
+    assert await redis_client.execute_command('AM.GETAT', 'test16', 'author', *heads) is None
+
+    # The current value is untouched
+    assert await redis_client.execute_command('AM.GETTEXT', 'test16', 'status') == b'published'
+
+    with pytest.raises(Exception, match='unknown change hash'):
+        await redis_client.execute_command('AM.GETAT', 'test16', 'status', b'\x00' * 32)
+
+
+@pytest.mark.persistence
+async def test_tojsonat_exports_document_at_old_heads(redis_client, clean_redis):
+    """Test that AM.TOJSONAT exports the document as it was at earlier heads."""
+    await redis_client.execute_command('AM.NEW', 'test17')
+    await redis_client.execute_command('AM.PUTTEXT', 'test17', 'name', 'Alice')
+    await redis_client.execute_command('AM.PUTINT', 'test17', 'age', 30)
+    heads = await redis_client.execute_command('AM.GETHEADS', 'test17')
+    await redis_client.execute_command('AM.PUTTEXT', 'test17', 'email', 'alice@example.com')
+
+    snapshot = json.loads(await redis_client.execute_command('AM.TOJSONAT', 'test17', *heads))
+    assert snapshot == {'name': 'Alice', 'age': 30}
+
+    pretty = await redis_client.execute_command('AM.TOJSONAT', 'test17', 'true', *heads)
+    assert json.loads(pretty) == snapshot
+    assert b'\n' in pretty
+
+    with pytest.raises(Exception, match='unknown change hash'):
+        await redis_client.execute_command('AM.TOJSONAT', 'test17', b'\x00' * 32)
+
+
+@pytest.mark.persistence
+async def test_history_lists_change_metadata(redis_client, clean_redis):
+    """Test that AM.HISTORY returns change metadata in commit order."""
+    await redis_client.execute_command('AM.NEW', 'test18')
+    await redis_client.execute_command(
+        'AM.PUTTEXT', 'test18', 'title', 'Draft', 'MSG', 'Create draft', 'TIME', 1000
+    )
+    await redis_client.execute_command('AM.PUTINT', 'test18', 'revision', 1)
+    await redis_client.execute_command(
+        'AM.PUTTEXT', 'test18', 'title', 'Final', 'MSG', 'Publish'
+    )
+
+    history = await redis_client.execute_command('AM.HISTORY', 'test18')
+    assert len(history) == 3
+    assert [entry[2] for entry in history] == [1, 2, 3]
+    assert [entry[4] for entry in history] == [b'Create draft', None, b'Publish']
+    assert history[0][3] == 1000
+    assert all(entry[5] == 1 for entry in history)
+    assert len({entry[1] for entry in history}) == 1
+
+    heads = await redis_client.execute_command('AM.GETHEADS', 'test18')
+    assert heads == [history[-1][0]]
+
+    limited = await redis_client.execute_command('AM.HISTORY', 'test18', 'LIMIT', 2)
+    assert limited == history[:2]
+
+
+@pytest.mark.persistence
+async def test_stats_actors_counts_changes_per_actor(redis_client, clean_redis):
+    """Test that AM.STATS.ACTORS groups changes by their actor."""
+    alice = '01' * 16
+    bob = '02' * 16
+    await redis_client.execute_command('AM.NEW', 'test19')
+    await redis_client.execute_command('AM.SETACTOR', 'test19', alice)
+    await redis_client.execute_command('AM.PUTTEXT', 'test19', 'title', 'Draft')
+    await redis_client.execute_command('AM.PUTINT', 'test19', 'revision', 1)
+
+    await redis_client.execute_command('AM.NEW', 'test20')
+    await redis_client.execute_command('AM.SETACTOR', 'test20', bob)
+    await redis_client.execute_command('AM.PUTTEXT', 'test20', 'author', 'Bob')
+
+    changes = await redis_client.execute_command('AM.CHANGES', 'test20')
+    await redis_client.execute_command('AM.APPLY', 'test19', *changes)
+
+    stats = await redis_client.execute_command('AM.STATS.ACTORS', 'test19')
+    assert stats == [alice.encode(), 2, bob.encode(), 1]
+
+    stats = await redis_client.execute_command('AM.STATS.ACTORS', 'test20')
+    assert stats == [bob.encode(), 1]
+
+
+@pytest.mark.persistence
+async def test_change_reads_open_the_key_read_only(redis_client, clean_redis):
+    """Test that AM.CHANGES and AM.NUMCHANGES open the document for reading."""
+    await redis_client.execute_command('AM.NEW', 'test21')
+    await redis_client.execute_command('AM.PUTTEXT', 'test21', 'title', 'Draft')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'test21')
+    await redis_client.execute_command('AM.PUTINT', 'test21', 'revision', 1)
+    await redis_client.execute_command('AM.PUTINT', 'test21', 'revision', 2)
+
+    # Only read lookups count towards keyspace_hits; a key opened for writing
+    # wouldn't move it
+    async def keyspace_hits():
+        return (await redis_client.info('stats'))['keyspace_hits']
+
+    hits = await keyspace_hits()
+    changes = await redis_client.execute_command('AM.CHANGES', 'test21', 'SINCE', *heads)
+    assert len(changes) == 2
+    assert await keyspace_hits() == hits + 1
+
+    hits = await keyspace_hits()
+    count = await redis_client.execute_command('AM.NUMCHANGES', 'test21', *heads)
+    assert count == 2
+    assert await keyspace_hits() == hits + 1
+
+
+@pytest.mark.persistence
+async def test_pinned_actor_survives_dump_restore(redis_client, clean_redis):
+    """Test that an actor set with AM.SETACTOR is kept in the RDB payload."""
+    actor = '0a1b2c3d4e5f'
+    await redis_client.execute_command('AM.NEW', 'test22')
+    await redis_client.execute_command('AM.SETACTOR', 'test22', actor)
+    await redis_client.execute_command('AM.PUTTEXT', 'test22', 'title', 'Draft')
+
+    payload = await redis_client.execute_command('DUMP', 'test22')
+    await redis_client.execute_command('RESTORE', 'test23', 0, payload)
+    assert await redis_client.execute_command('AM.GETACTOR', 'test23') == actor.encode()
+
+    # A document whose actor was never set still gets a fresh one
+    await redis_client.execute_command('AM.NEW', 'test24')
+    actor24 = await redis_client.execute_command('AM.GETACTOR', 'test24')
+    payload = await redis_client.execute_command('DUMP', 'test24')
+    await redis_client.execute_command('RESTORE', 'test25', 0, payload)
+    assert await redis_client.execute_command('AM.GETACTOR', 'test25') != actor24
+
+
+@pytest.mark.persistence
+async def test_incremental_save_is_relative_to_given_heads(redis_client, clean_redis):
+    """Test that AM.SAVE INCREMENTAL uses the caller's heads, not shared state."""
+    await redis_client.execute_command('AM.NEW', 'test26')
+    await redis_client.execute_command('AM.PUTTEXT', 'test26', 'title', 'Draft')
+    base = await redis_client.execute_command('AM.SAVE', 'test26')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'test26')
+
+    await redis_client.execute_command('AM.PUTINT', 'test26', 'revision', 1)
+    # Another client's full save doesn't move this client's base
+    await redis_client.execute_command('AM.SAVE', 'test26')
+    delta = await redis_client.execute_command('AM.SAVE', 'test26', 'INCREMENTAL', *heads)
+    assert delta
+
+    await redis_client.execute_command('AM.LOAD', 'test27', base + delta)
+    assert await redis_client.execute_command('AM.GETINT', 'test27', 'revision') == 1
+
+    # Saving is read-only, so it runs where only reads are allowed
+    everything = await redis_client.execute_command(
+        'EVAL_RO', "return redis.call('AM.SAVE', KEYS[1], 'INCREMENTAL')", 1, 'test26'
+    )
+    assert everything
+    latest = await redis_client.execute_command('AM.GETHEADS', 'test26')
+    assert await redis_client.execute_command('AM.SAVE', 'test26', 'INCREMENTAL', *latest) == b''
@@ -6,6 +6,7 @@ This is the key capability that bash tests couldn't properly exercise.
 """
 import pytest
 import asyncio
+from redis.exceptions import ResponseError
 
 
 @pytest.mark.sync
@@ -360,3 +361,180 @@ async def test_complex_nested_structure_sync(redis_client, clean_redis):
     assert age == 30
     assert tags_len == 2
     assert views == 100
+
+
+@pytest.mark.sync
+async def test_apply_withnew_skips_known_changes(redis_client, clean_redis):
+    """Test that re-applying a change reports nothing new and publishes nothing."""
+    await redis_client.execute_command('AM.NEW', 'source')
+    await redis_client.execute_command('AM.PUTTEXT', 'source', 'name', 'Alice')
+    changes = await redis_client.execute_command('AM.CHANGES', 'source')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'source')
+
+    await redis_client.execute_command('AM.NEW', 'target')
+    pubsub = redis_client.pubsub()
+    await pubsub.subscribe('changes:target')
+    await pubsub.get_message(timeout=1.0)  # subscribe confirmation
+
+    try:
+        new = await redis_client.execute_command('AM.APPLY', 'target', 'WITHNEW', *changes)
+        assert new == heads
+        message = await pubsub.get_message(ignore_subscribe_messages=True, timeout=1.0)
+        assert message is not None
+
+        new = await redis_client.execute_command('AM.APPLY', 'target', 'WITHNEW', *changes)
+        assert new == []
+        message = await pubsub.get_message(ignore_subscribe_messages=True, timeout=0.5)
+        assert message is None
+    finally:
+        await pubsub.aclose()
+
+    # Without WITHNEW the reply is still OK
+    assert await redis_client.execute_command('AM.APPLY', 'target', *changes) == b'OK'
+
+
+@pytest.mark.sync
+async def test_apply_publishes_only_integrated_changes(redis_client, clean_redis):
+    """Test that re-broadcast changes cause no PUBLISH and rejected ones publish nothing."""
+    await redis_client.execute_command('AM.NEW', 'source')
+    await redis_client.execute_command('AM.PUTTEXT', 'source', 'name', 'Alice')
+    await redis_client.execute_command('AM.PUTINT', 'source', 'age', 30)
+    first, second = await redis_client.execute_command('AM.CHANGES', 'source')
+
+    await redis_client.execute_command('AM.NEW', 'target')
+    pubsub = redis_client.pubsub()
+    await pubsub.subscribe('changes:target')
+    await pubsub.get_message(timeout=1.0)  # subscribe confirmation
+
+    async def published():
+        messages = []
+        while True:
+            message = await pubsub.get_message(ignore_subscribe_messages=True, timeout=0.5)
+            if message is None:
+                return messages
+            messages.append(message)
+
+    try:
+        # The second change depends on the first, so it is rejected
+        with pytest.raises(ResponseError, match='missing dependencies'):
+            await redis_client.execute_command('AM.APPLY', 'target', 'WITHNEW', second)
+        assert await published() == []
+
+        # Out of order but complete, both are integrated and published
+        new = await redis_client.execute_command('AM.APPLY', 'target', 'WITHNEW', second, first)
+        assert len(new) == 2
+        assert len(await published()) == 2
+
+        # Re-broadcasting known changes, duplicates included, publishes nothing
+        await redis_client.execute_command('AM.APPLY', 'target', first, second, first)
+        assert await published() == []
+    finally:
+        await pubsub.aclose()
+
+    assert await redis_client.execute_command('AM.GETINT', 'target', 'age') == 30
+
+
+@pytest.mark.sync
+async def test_apply_rejects_incomplete_or_malformed_batch(redis_client, clean_redis):
+    """Test that a bad batch leaves the document unchanged."""
+    await redis_client.execute_command('AM.NEW', 'source')
+    await redis_client.execute_command('AM.PUTTEXT', 'source', 'name', 'Alice')
+    await redis_client.execute_command('AM.PUTINT', 'source', 'age', 30)
+    await redis_client.execute_command('AM.PUTINT', 'source', 'age', 31)
+    changes = await redis_client.execute_command('AM.CHANGES', 'source')
+
+    await redis_client.execute_command('AM.NEW', 'target')
+
+    with pytest.raises(ResponseError, match='invalid change at index 1'):
+        await redis_client.execute_command('AM.APPLY', 'target', changes[0], b'garbage')
+    assert await redis_client.execute_command('AM.GETHEADS', 'target') == []
+
+    with pytest.raises(ResponseError, match='missing dependencies'):
+        await redis_client.execute_command('AM.APPLY', 'target', changes[2])
+    assert await redis_client.execute_command('AM.GETHEADS', 'target') == []
+
+    await redis_client.execute_command('AM.APPLY', 'target', *reversed(changes))
+    assert await redis_client.execute_command('AM.GETINT', 'target', 'age') == 31
+
+
+@pytest.mark.sync
+async def test_msgpack_round_trip_keeps_types(redis_client, clean_redis):
+    """Test that a document copied through MessagePack keeps every scalar type."""
+    await redis_client.execute_command('AM.NEW', 'source')
+    await redis_client.execute_command('AM.PUTTEXT', 'source', 'name', 'Alice')
+    await redis_client.execute_command('AM.PUTINT', 'source', 'age', 30)
+    await redis_client.execute_command('AM.PUTDOUBLE', 'source', 'score', 30.0)
+    await redis_client.execute_command('AM.PUTBOOL', 'source', 'active', 'true')
+    await redis_client.execute_command('AM.PUTNULL', 'source', 'nickname')
+    await redis_client.execute_command('AM.PUTBYTES', 'source', 'avatar', b'\x00\x9f\xff')
+    await redis_client.execute_command('AM.PUTCOUNTER', 'source', 'views', 10)
+    await redis_client.execute_command('AM.PUTTIMESTAMP', 'source', 'created', 1700000000123)
+
+    packed = await redis_client.execute_command('AM.TOMSGPACK', 'source')
+    await redis_client.execute_command('AM.FROMMSGPACK', 'copy', packed)
+
+    for path, type_name in [
+        ('name', b'text'), ('age', b'int'), ('score', b'double'), ('active', b'bool'),
+        ('nickname', b'null'), ('avatar', b'bytes'), ('views', b'counter'),
+        ('created', b'timestamp'),
+    ]:
+        assert await redis_client.execute_command('AM.TYPE', 'copy', path) == type_name
+    assert await redis_client.execute_command('AM.GETBYTES', 'copy', 'avatar') == b'\x00\x9f\xff'
+    assert await redis_client.execute_command('AM.GETTIMESTAMP', 'copy', 'created') == 1700000000123
+
+    # The counter is still a counter, so increments merge instead of overwriting
+    await redis_client.execute_command('AM.INCCOUNTER', 'copy', 'views', 5)
+    assert await redis_client.execute_command('AM.GETCOUNTER', 'copy', 'views') == 15
+
+    with pytest.raises(ResponseError, match='expected a map'):
+        await redis_client.execute_command('AM.FROMMSGPACK', 'bad', b'\x91\x01')
+    with pytest.raises(ResponseError, match='invalid msgpack data: unexpected end of data'):
+        await redis_client.execute_command('AM.FROMMSGPACK', 'bad', b'\x81\xa1')
+
+
+@pytest.mark.sync
+async def test_ifchanged_noop_put_has_no_side_effects(redis_client, clean_redis):
+    """Test that an IFCHANGED put of the current value commits and propagates nothing."""
+    await redis_client.execute_command('AM.NEW', 'doc')
+    await redis_client.execute_command('AM.PUTTEXT', 'doc', 'title', 'Hello')
+    await redis_client.execute_command('AM.PUTINT', 'doc', 'count', 1)
+    heads = await redis_client.execute_command('AM.GETHEADS', 'doc')
+
+    _, events = (await redis_client.config_get('notify-keyspace-events')).popitem()
+    await redis_client.config_set('notify-keyspace-events', 'KA')
+    pubsub = redis_client.pubsub()
+    await pubsub.subscribe('changes:doc', '__keyspace@0__:doc')
+    for _ in range(2):
+        await pubsub.get_message(timeout=1.0)  # subscribe confirmations
+
+    async def published():
+        messages = []
+        while True:
+            message = await pubsub.get_message(ignore_subscribe_messages=True, timeout=0.5)
+            if message is None:
+                return messages
+            messages.append(message['channel'])
+
+    async def aof_size():
+        # Propagated writes reach the AOF and replicas alike
+        info = await redis_client.info('persistence')
+        return info.get('aof_current_size') if info.get('aof_enabled') else None
+
+    try:
+        size = await aof_size()
+        for command in (
+            ('AM.PUTTEXT', 'doc', 'title', 'Hello', 'IFCHANGED'),
+            ('AM.PUTINT', 'doc', 'count', 1, 'IFCHANGED'),
+        ):
+            assert await redis_client.execute_command(*command) == b'OK'
+            assert await redis_client.execute_command('AM.GETHEADS', 'doc') == heads
+            assert await published() == []
+            assert await aof_size() == size
+
+        # A real change still goes through every path
+        await redis_client.execute_command('AM.PUTTEXT', 'doc', 'title', 'World', 'IFCHANGED')
+        assert await redis_client.execute_command('AM.GETHEADS', 'doc') != heads
+        assert set(await published()) == {b'changes:doc', b'__keyspace@0__:doc'}
+    finally:
+        await pubsub.aclose()
+        await redis_client.config_set('notify-keyspace-events', events)
@@ -0,0 +1,155 @@
+"""
+Blocking change notification tests for valkey-automerge.
+
+Tests that AM.WATCH blocks until another connection writes to the document.
+"""
+import os
+
+import pytest
+import asyncio
+from redis.asyncio import Redis
+
+
+def connect(db=0):
+    """Open a separate connection to the test instance."""
+    return Redis(
+        host=os.getenv('VALKEY_HOST', 'localhost'),
+        port=int(os.getenv('VALKEY_PORT', 6379)),
+        db=db,
+        decode_responses=False,
+        socket_timeout=5.0,
+        socket_connect_timeout=5.0
+    )
+
+
+@pytest.mark.sync
+@pytest.mark.concurrent
+async def test_watch_wakes_on_write_from_another_connection(redis_client, clean_redis):
+    """Test that a watcher is answered with the change made by another client."""
+    await redis_client.execute_command('AM.NEW', 'watched')
+    await redis_client.execute_command('AM.PUTTEXT', 'watched', 'title', 'Draft')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'watched')
+
+    watcher = connect()
+    try:
+        watch = asyncio.create_task(
+            watcher.execute_command('AM.WATCH', 'watched', *heads, 'TIMEOUT', 3000)
+        )
+        # Give the watcher time to block before writing
+        await asyncio.sleep(0.2)
+        assert not watch.done()
+
+        await redis_client.execute_command('AM.PUTTEXT', 'watched', 'title', 'Final')
+        changes = await asyncio.wait_for(watch, timeout=2.0)
+    finally:
+        await watcher.aclose()
+
+    # Same shape as AM.CHANGES: the changes after the watched heads
+    assert changes == await redis_client.execute_command('AM.CHANGES', 'watched', *heads)
+    assert len(changes) == 1
+
+    await redis_client.execute_command('AM.NEW', 'replica')
+    await redis_client.execute_command('AM.APPLY', 'replica', *await redis_client.execute_command('AM.CHANGES', 'watched'))
+    assert await redis_client.execute_command('AM.GETTEXT', 'replica', 'title') == b'Final'
+
+
+@pytest.mark.sync
+async def test_watch_returns_immediately_when_behind(redis_client, clean_redis):
+    """Test that stale heads are answered without blocking."""
+    await redis_client.execute_command('AM.NEW', 'watched')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'watched')
+    await redis_client.execute_command('AM.PUTINT', 'watched', 'version', 1)
+
+    changes = await redis_client.execute_command('AM.WATCH', 'watched', *heads)
+    assert len(changes) == 1
+
+
+@pytest.mark.sync
+async def test_watch_times_out(redis_client, clean_redis):
+    """Test that a watcher is answered with nil when nothing changes."""
+    await redis_client.execute_command('AM.NEW', 'watched')
+    await redis_client.execute_command('AM.PUTTEXT', 'watched', 'title', 'Draft')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'watched')
+
+    result = await redis_client.execute_command('AM.WATCH', 'watched', *heads, 'TIMEOUT', 200)
+    assert result is None
+
+
+@pytest.mark.sync
+@pytest.mark.concurrent
+async def test_watch_wakes_on_load(redis_client, clean_redis):
+    """Test that replacing the whole document answers its watchers."""
+    await redis_client.execute_command('AM.NEW', 'watched')
+    await redis_client.execute_command('AM.PUTTEXT', 'watched', 'title', 'Draft')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'watched')
+
+    await redis_client.execute_command('AM.NEW', 'source')
+    await redis_client.execute_command('AM.PUTTEXT', 'source', 'title', 'Loaded')
+    saved = await redis_client.execute_command('AM.SAVE', 'source')
+
+    watcher = connect()
+    try:
+        watch = asyncio.create_task(
+            watcher.execute_command('AM.WATCH', 'watched', *heads, 'TIMEOUT', 3000)
+        )
+        await asyncio.sleep(0.2)
+        assert not watch.done()
+
+        await redis_client.execute_command('AM.LOAD', 'watched', saved)
+        changes = await asyncio.wait_for(watch, timeout=2.0)
+    finally:
+        await watcher.aclose()
+
+    assert changes == await redis_client.execute_command('AM.CHANGES', 'watched', *heads)
+
+
+@pytest.mark.sync
+@pytest.mark.concurrent
+async def test_watch_errors_when_key_is_deleted(redis_client, clean_redis):
+    """Test that deleting the watched key answers its watchers with an error."""
+    await redis_client.execute_command('AM.NEW', 'watched')
+    heads = await redis_client.execute_command('AM.GETHEADS', 'watched')
+
+    watcher = connect()
+    try:
+        watch = asyncio.create_task(
+            watcher.execute_command('AM.WATCH', 'watched', *heads, 'TIMEOUT', 3000)
+        )
+        await asyncio.sleep(0.2)
+        assert not watch.done()
+
+        await redis_client.delete('watched')
+        with pytest.raises(Exception, match='no such key'):
+            await asyncio.wait_for(watch, timeout=2.0)
+    finally:
+        await watcher.aclose()
+
+
+@pytest.mark.sync
+@pytest.mark.concurrent
+async def test_watch_is_per_database(redis_client, clean_redis):
+    """Test that a write in one database doesn't wake watchers of the same key in another."""
+    other = connect(db=1)
+    watcher = connect(db=1)
+    try:
+        await other.delete('watched')
+        await other.execute_command('AM.NEW', 'watched')
+        heads = await other.execute_command('AM.GETHEADS', 'watched')
+        await redis_client.execute_command('AM.NEW', 'watched')
+
+        watch = asyncio.create_task(
+            watcher.execute_command('AM.WATCH', 'watched', *heads, 'TIMEOUT', 3000)
+        )
+        await asyncio.sleep(0.2)
+
+        await redis_client.execute_command('AM.PUTTEXT', 'watched', 'title', 'Elsewhere')
+        await asyncio.sleep(0.2)
+        assert not watch.done()
+
+        await other.execute_command('AM.PUTTEXT', 'watched', 'title', 'Here')
+        changes = await asyncio.wait_for(watch, timeout=2.0)
+        assert len(changes) == 1
+    finally:
+        await other.delete('watched')
+        await other.aclose()
+        await watcher.aclose()
@@ -0,0 +1,257 @@
+//! Module configuration.
+//!
+//! Settings are registered as Valkey module configs named `automerge.<name>`, so they
+//! can be given as module load arguments (`loadmodule ... publish-encoding raw`), in
+//! `valkey.conf`, or changed at runtime with `CONFIG SET`.
+
+use base64::{engine::general_purpose, Engine as _};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use valkey_module::configuration::ConfigurationContext;
+use valkey_module::{enum_configuration, ValkeyError};
+
+enum_configuration! {
+    /// Encoding of change bytes published to the change channel.
+    ///
+    /// Variant names are the config values, hence lowercase.
+    #[allow(non_camel_case_types)]
+    #[derive(Debug, Copy, PartialEq, Eq)]
+    pub enum PublishEncoding {
+        /// Base64 text, for clients that can't handle binary messages (default)
+        base64 = 0,
+        /// The change bytes as-is; pub/sub payloads are binary-safe
+        raw = 1,
+        /// A JSON envelope `{"key", "op", "change"}` with the change in base64, so
+        /// subscribers can filter by command without decoding the change
+        json = 2,
+    }
+}
+
+impl PublishEncoding {
+    /// Encode the change to `key` made by command `op` as a pub/sub payload.
+    pub fn encode(&self, key: &str, op: &str, change: &[u8]) -> Vec<u8> {
+        match self {
+            PublishEncoding::base64 => general_purpose::STANDARD.encode(change).into_bytes(),
+            PublishEncoding::raw => change.to_vec(),
+            PublishEncoding::json => serde_json::json!({
+                "key": key,
+                "op": op,
+                "change": general_purpose::STANDARD.encode(change),
+            })
+            .to_string()
+            .into_bytes(),
+        }
+    }
+}
+
+/// Default for `automerge.change-channel`
+pub const DEFAULT_CHANGE_CHANNEL: &str = "changes:{key}";
+
+/// `automerge.publish-encoding`: `base64` (default), `raw` or `json`
+pub static PUBLISH_ENCODING: Mutex<PublishEncoding> = Mutex::new(PublishEncoding::base64);
+
+/// `automerge.change-channel`: channel template, `{key}` is replaced by the key name
+pub static CHANGE_CHANNEL: LazyLock<Mutex<String>> =
+    LazyLock::new(|| Mutex::new(DEFAULT_CHANGE_CHANNEL.to_string()));
+
+/// `automerge.publish-changes`: whether writes publish their changes at all
+pub static PUBLISH_CHANGES: AtomicBool = AtomicBool::new(true);
+
+/// `automerge.text-as-object`: whether commands that write strings store `Text` objects
+///
+/// Load time only, since replicas replay the commands and must store the same kind
+/// of value as the primary.
+pub static TEXT_AS_OBJECT: AtomicBool = AtomicBool::new(false);
+
+/// `automerge.index-async`: whether write commands hand index updates to a background thread
+pub static INDEX_ASYNC: AtomicBool = AtomicBool::new(false);
+
+/// Default for `automerge.index-prefix`
+pub const DEFAULT_INDEX_PREFIX: &str = "am:idx:";
+
+/// Default for `automerge.index-config-prefix`
+pub const DEFAULT_INDEX_CONFIG_PREFIX: &str = "am:index:config:";
+
+/// `automerge.index-prefix`: prefix of shadow index documents, fixed at load time
+pub static INDEX_PREFIX: LazyLock<Mutex<String>> =
+    LazyLock::new(|| Mutex::new(DEFAULT_INDEX_PREFIX.to_string()));
+
+/// `automerge.index-config-prefix`: prefix of index configuration keys, fixed at load time
+pub static INDEX_CONFIG_PREFIX: LazyLock<Mutex<String>> =
+    LazyLock::new(|| Mutex::new(DEFAULT_INDEX_CONFIG_PREFIX.to_string()));
+
+/// Reject an empty key prefix, which would put index keys in the document namespace.
+pub fn validate_prefix(
+    _ctx: &ConfigurationContext,
+    name: &str,
+    value: &'static Mutex<String>,
+) -> Result<(), ValkeyError> {
+    let empty = value
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .is_empty();
+    if empty {
+        return Err(ValkeyError::String(format!("{} must not be empty", name)));
+    }
+    Ok(())
+}
+
+/// Snapshot of the settings used by the command handlers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleConfig {
+    pub publish_encoding: PublishEncoding,
+    pub change_channel: String,
+    pub publish_changes: bool,
+    pub index_prefix: String,
+    pub index_config_prefix: String,
+    pub text_as_object: bool,
+    pub index_async: bool,
+}
+
+impl ModuleConfig {
+    /// Read the current values of all settings.
+    pub fn current() -> Self {
+        ModuleConfig {
+            publish_encoding: *PUBLISH_ENCODING
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            change_channel: CHANGE_CHANNEL
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+            publish_changes: PUBLISH_CHANGES.load(Ordering::Relaxed),
+            index_prefix: INDEX_PREFIX
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+            index_config_prefix: INDEX_CONFIG_PREFIX
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+            text_as_object: TEXT_AS_OBJECT.load(Ordering::Relaxed),
+            index_async: INDEX_ASYNC.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Channel that changes to `key` are published to, or None when publishing
+    /// is disabled.
+    pub fn change_channel(&self, key: &str) -> Option<String> {
+        if !self.publish_changes {
+            return None;
+        }
+        Some(self.change_channel.replace("{key}", key))
+    }
+}
+
+impl Default for ModuleConfig {
+    fn default() -> Self {
+        ModuleConfig {
+            publish_encoding: PublishEncoding::base64,
+            change_channel: DEFAULT_CHANGE_CHANNEL.to_string(),
+            publish_changes: true,
+            index_prefix: DEFAULT_INDEX_PREFIX.to_string(),
+            index_config_prefix: DEFAULT_INDEX_CONFIG_PREFIX.to_string(),
+            text_as_object: false,
+            index_async: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::RedisAutomergeClient;
+    use automerge::{transaction::CommitOptions, Change};
+
+    fn change_bytes() -> Vec<u8> {
+        let mut client = RedisAutomergeClient::new();
+        client
+            .put_text_with_change("name", "Alice", CommitOptions::default())
+            .unwrap()
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_base64_encoding_round_trips() {
+        let change = change_bytes();
+        let payload = PublishEncoding::base64.encode("doc", "puttext", &change);
+        assert!(payload.iter().all(|b| b.is_ascii_graphic()));
+
+        let decoded = general_purpose::STANDARD.decode(&payload).unwrap();
+        assert_eq!(decoded, change);
+        assert!(Change::from_bytes(decoded).is_ok());
+    }
+
+    #[test]
+    fn test_raw_encoding_round_trips() {
+        let change = change_bytes();
+        let payload = PublishEncoding::raw.encode("doc", "puttext", &change);
+        assert_eq!(payload, change);
+        assert!(Change::from_bytes(payload).is_ok());
+    }
+
+    #[test]
+    fn test_default_config_uses_base64() {
+        assert_eq!(
+            ModuleConfig::default().publish_encoding,
+            PublishEncoding::base64
+        );
+        assert_eq!(ModuleConfig::current(), ModuleConfig::default());
+    }
+
+    #[test]
+    fn test_change_channel_template() {
+        let config = ModuleConfig::default();
+        assert_eq!(
+            config.change_channel("article:1").as_deref(),
+            Some("changes:article:1")
+        );
+
+        let config = ModuleConfig {
+            change_channel: "am:changes:{key}".to_string(),
+            ..ModuleConfig::default()
+        };
+        assert_eq!(
+            config.change_channel("article:1").as_deref(),
+            Some("am:changes:article:1")
+        );
+
+        // Every occurrence is substituted; a template without {key} is used as-is
+        let config = ModuleConfig {
+            change_channel: "{key}/{key}".to_string(),
+            ..ModuleConfig::default()
+        };
+        assert_eq!(config.change_channel("doc").as_deref(), Some("doc/doc"));
+        let config = ModuleConfig {
+            change_channel: "all-changes".to_string(),
+            ..ModuleConfig::default()
+        };
+        assert_eq!(config.change_channel("doc").as_deref(), Some("all-changes"));
+    }
+
+    #[test]
+    fn test_disabled_publishing_has_no_channel() {
+        let config = ModuleConfig {
+            publish_changes: false,
+            ..ModuleConfig::default()
+        };
+        assert_eq!(config.change_channel("article:1"), None);
+    }
+
+    #[test]
+    fn test_json_envelope_names_key_and_op() {
+        let change = change_bytes();
+        for op in ["puttext", "splicetext", "apply"] {
+            let payload = PublishEncoding::json.encode("article:1", op, &change);
+            let envelope: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+            assert_eq!(envelope["key"], "article:1");
+            assert_eq!(envelope["op"], op);
+
+            let encoded = envelope["change"].as_str().unwrap();
+            let decoded = general_purpose::STANDARD.decode(encoded).unwrap();
+            assert_eq!(decoded, change);
+            assert!(Change::from_bytes(decoded).is_ok());
+        }
+    }
+}
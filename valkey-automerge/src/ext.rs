@@ -12,8 +12,14 @@
 //! The module supports JSON-compatible path syntax:
 //! - Simple keys: `"name"`, `"user"`
 //! - Nested maps: `"user.profile.name"`, `"data.config.port"`
-//! - Array indices: `"users[0]"`, `"items[5].name"`
+//! - Array indices: `"users[0]"`, `"items[5].name"`, `"items[-1]"` (from the end)
 //! - JSONPath style: `"$.user.name"`, `"$.items[0].title"`
+//! - Quoted keys: `data["config.v1"].port`, or escaped: `data.config\.v1.port`
+//!
+//! Methods that write a value, such as `put_text` and `put_int`, create the
+//! maps and lists leading to it as needed. An index in the parent path equal
+//! to the list's length appends a new element, and larger indices are out of
+//! bounds; the final index must already exist.
 //!
 //! # Examples
 //!
@@ -38,11 +44,15 @@
 
 use automerge::{
     marks::{ExpandMark, Mark},
-    transaction::Transactable,
-    Automerge, AutomergeError, Change, ChangeHash, ObjId, Patch, ReadDoc, ScalarValue, Value, ROOT,
+    sync::{self, SyncDoc},
+    transaction::{CommitOptions, Transactable},
+    Automerge, AutomergeError, Change, ChangeHash, ObjId, ObjType, Patch, PatchAction, Prop,
+    ReadDoc, ScalarValue, Value, ROOT,
 };
+use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Utc};
 use serde_json::Value as JsonValue;
+use std::sync::Mutex;
 
 /// Represents a diff operation parsed from unified diff format
 #[derive(Debug, PartialEq)]
@@ -53,6 +63,8 @@ enum DiffOp {
     Delete(String),
     /// Line to be added
     Add(String),
+    /// Start of a hunk, from its `@@ -old_start,old_count +... @@` header
+    Hunk { old_start: usize, old_count: usize },
 }
 
 /// Represents a typed value extracted from an Automerge document
@@ -65,6 +77,7 @@ pub enum TypedValue {
     Bool(bool),
     Timestamp(i64),
     Counter(i64),
+    Bytes(Vec<u8>),
     Array(Vec<TypedValue>),
     Object(std::collections::HashMap<String, TypedValue>),
     Null,
@@ -89,6 +102,7 @@ impl TypedValue {
                 JsonValue::String(dt.to_rfc3339())
             }
             TypedValue::Counter(c) => JsonValue::Number((*c).into()),
+            TypedValue::Bytes(b) => JsonValue::String(general_purpose::STANDARD.encode(b)),
             TypedValue::Array(arr) => {
                 JsonValue::Array(arr.iter().map(|v| v.to_json()).collect())
             }
@@ -104,17 +118,201 @@ impl TypedValue {
     }
 }
 
+/// A mark on a text object as `(name, value, start, end)`
+pub type TextMark = (String, ScalarValue, usize, usize);
+
+/// A change made by a `*_with_change` method as `(hash, raw bytes)`
+pub type CommittedChange = (ChangeHash, Vec<u8>);
+
+/// Summary statistics about a document, as reported by `AM.INFO`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentInfo {
+    /// Number of changes in the document history
+    pub num_changes: usize,
+    /// Number of current heads (more than one means unmerged concurrent changes)
+    pub num_heads: usize,
+    /// Number of distinct actors that have made changes
+    pub num_actors: usize,
+    /// Size of the saved (compacted) document in bytes
+    pub size_bytes: usize,
+    /// Number of changes buffered for AOF persistence
+    pub pending_aof: usize,
+}
+
+/// The first line where a unified diff disagrees with the current text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffMismatch {
+    /// 1-based line number in the current text
+    pub line: usize,
+    /// The context or deleted line the diff expected
+    pub expected: String,
+    /// The line actually found, or `None` past the end of the text
+    pub found: Option<String>,
+}
+
+impl std::fmt::Display for DiffMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.found {
+            Some(found) => write!(
+                f,
+                "diff does not match at line {}: expected '{}', found '{}'",
+                self.line, self.expected, found
+            ),
+            None => write!(
+                f,
+                "diff does not match at line {}: expected '{}', found end of text",
+                self.line, self.expected
+            ),
+        }
+    }
+}
+
+/// Why a JSON Patch was rejected
+#[derive(Debug)]
+pub enum JsonPatchError {
+    /// The patch isn't an array of well-formed operations
+    InvalidPatch(String),
+    /// A `path` that isn't a JSON Pointer
+    InvalidPointer(String),
+    /// A `path` that doesn't lead to a value (or, for `add`, to a container)
+    PathNotFound(String),
+    /// A `test` whose value didn't match the document
+    TestFailed(String),
+    /// The document rejected the write
+    Automerge(AutomergeError),
+}
+
+impl std::fmt::Display for JsonPatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonPatchError::InvalidPatch(reason) => write!(f, "invalid patch: {}", reason),
+            JsonPatchError::InvalidPointer(path) => write!(f, "invalid JSON pointer '{}'", path),
+            JsonPatchError::PathNotFound(path) => write!(f, "path not found: {}", path),
+            JsonPatchError::TestFailed(path) if path.is_empty() => {
+                write!(f, "test failed at the document root")
+            }
+            JsonPatchError::TestFailed(path) => write!(f, "test failed at {}", path),
+            JsonPatchError::Automerge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AutomergeError> for JsonPatchError {
+    fn from(e: AutomergeError) -> Self {
+        JsonPatchError::Automerge(e)
+    }
+}
+
+/// Why an increment of a plain number was rejected
+#[derive(Debug)]
+pub enum IncrementError {
+    /// The field holds a counter, which is incremented with `inc_counter`
+    Counter,
+    /// The field holds something other than an integer
+    NotAnInteger,
+    /// The integer sum doesn't fit in an `i64`
+    Overflow,
+    /// The field holds something other than a double
+    NotADouble,
+    /// The sum is NaN or infinite
+    NotFinite,
+    /// The path is invalid, or the document rejected the write
+    Automerge(AutomergeError),
+}
+
+impl std::fmt::Display for IncrementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IncrementError::Counter => {
+                write!(f, "value is a counter, use AM.INCCOUNTER to increment it")
+            }
+            IncrementError::NotAnInteger => write!(f, "value is not an integer"),
+            IncrementError::Overflow => write!(f, "increment or decrement would overflow"),
+            IncrementError::NotADouble => write!(f, "value is not a double"),
+            IncrementError::NotFinite => write!(f, "increment would produce NaN or Infinity"),
+            IncrementError::Automerge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AutomergeError> for IncrementError {
+    fn from(e: AutomergeError) -> Self {
+        IncrementError::Automerge(e)
+    }
+}
+
+/// Why a text splice was rejected
+#[derive(Debug)]
+pub enum SpliceError {
+    /// The splice reaches outside the text; all values are in characters
+    OutOfBounds { pos: usize, del: isize, len: usize },
+    /// The path doesn't lead to text, or the document rejected the write
+    Automerge(AutomergeError),
+}
+
+impl std::fmt::Display for SpliceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpliceError::OutOfBounds { pos, del, len } => write!(
+                f,
+                "splice out of bounds: pos {} del {} on text of length {}",
+                pos, del, len
+            ),
+            SpliceError::Automerge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AutomergeError> for SpliceError {
+    fn from(e: AutomergeError) -> Self {
+        SpliceError::Automerge(e)
+    }
+}
+
+/// Why MessagePack data couldn't be loaded as a document
+#[derive(Debug)]
+pub enum MsgpackError {
+    /// The data isn't valid MessagePack
+    Decode(crate::msgpack::DecodeError),
+    /// The top-level value isn't a map
+    NotAMap,
+    /// The document rejected the write
+    Automerge(AutomergeError),
+}
+
+impl std::fmt::Display for MsgpackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MsgpackError::Decode(e) => write!(f, "invalid msgpack data: {}", e),
+            MsgpackError::NotAMap => write!(f, "invalid msgpack data, expected a map"),
+            MsgpackError::Automerge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AutomergeError> for MsgpackError {
+    fn from(e: AutomergeError) -> Self {
+        MsgpackError::Automerge(e)
+    }
+}
+
 /// Parse a unified diff into operations
 fn parse_unified_diff(diff: &str) -> Result<Vec<DiffOp>, AutomergeError> {
     let mut ops = Vec::new();
 
     for line in diff.lines() {
-        // Skip header lines
-        if line.starts_with("---") || line.starts_with("+++") || line.starts_with("@@") {
+        // Skip file header lines and "\ No newline at end of file" markers
+        if line.starts_with("---") || line.starts_with("+++") || line.starts_with('\\') {
             continue;
         }
 
-        if let Some(stripped) = line.strip_prefix('-') {
+        if line.starts_with("@@") {
+            let (old_start, old_count) = parse_hunk_header(line)?;
+            ops.push(DiffOp::Hunk {
+                old_start,
+                old_count,
+            });
+        } else if let Some(stripped) = line.strip_prefix('-') {
             ops.push(DiffOp::Delete(stripped.to_string()));
         } else if let Some(stripped) = line.strip_prefix('+') {
             ops.push(DiffOp::Add(stripped.to_string()));
@@ -129,6 +327,154 @@ fn parse_unified_diff(diff: &str) -> Result<Vec<DiffOp>, AutomergeError> {
     Ok(ops)
 }
 
+/// Parse the old-file range of a hunk header like `@@ -12,3 +12,4 @@`.
+///
+/// The count defaults to 1 when omitted, as in `@@ -1 +1 @@`.
+fn parse_hunk_header(line: &str) -> Result<(usize, usize), AutomergeError> {
+    let range = line
+        .trim_start_matches('@')
+        .split_whitespace()
+        .next()
+        .and_then(|r| r.strip_prefix('-'))
+        .ok_or(AutomergeError::Fail)?;
+    let (start, count) = match range.split_once(',') {
+        Some((start, count)) => (start, count),
+        None => (range, "1"),
+    };
+    let start = start.parse().map_err(|_| AutomergeError::Fail)?;
+    let count = count.parse().map_err(|_| AutomergeError::Fail)?;
+    Ok((start, count))
+}
+
+/// A run of consecutive deletions and additions from a unified diff,
+/// expressed as replacing the original lines `start..end` with `lines`
+#[derive(Debug, PartialEq)]
+struct LineEdit {
+    start: usize,
+    end: usize,
+    lines: Vec<String>,
+}
+
+/// Walk diff operations against the current lines and collect the changed
+/// regions, along with the first context or deletion line that didn't match
+fn diff_line_edits(
+    current_lines: &[&str],
+    ops: &[DiffOp],
+) -> Result<(Vec<LineEdit>, Option<DiffMismatch>), AutomergeError> {
+    let mut edits = Vec::new();
+    let mut pending: Option<LineEdit> = None;
+    let mut mismatch: Option<DiffMismatch> = None;
+    let mut current_line_idx = 0;
+    let mut record_mismatch = |idx: usize, expected: &str| {
+        mismatch.get_or_insert_with(|| DiffMismatch {
+            line: idx + 1,
+            expected: expected.to_string(),
+            found: current_lines.get(idx).map(|l| l.to_string()),
+        });
+    };
+
+    for op in ops {
+        match op {
+            DiffOp::Hunk {
+                old_start,
+                old_count,
+            } => {
+                // An empty range names the line the hunk is inserted after
+                let target = if *old_count == 0 {
+                    *old_start
+                } else {
+                    old_start.saturating_sub(1)
+                };
+                // Hunks must not overlap or go backwards
+                if target < current_line_idx {
+                    return Err(AutomergeError::InvalidIndex(*old_start));
+                }
+                let target = target.min(current_lines.len());
+                // A hunk that starts right where the previous edit ended continues it
+                if target != current_line_idx {
+                    edits.extend(pending.take());
+                    current_line_idx = target;
+                }
+            }
+            DiffOp::Context(line) => {
+                if current_lines.get(current_line_idx) != Some(&line.as_str()) {
+                    record_mismatch(current_line_idx, line);
+                }
+                // Context mismatches are tolerated; the current line is kept
+                if current_line_idx < current_lines.len() {
+                    edits.extend(pending.take());
+                    current_line_idx += 1;
+                }
+            }
+            DiffOp::Delete(line) => {
+                // Only remove the current line if it is the one being deleted
+                if current_lines.get(current_line_idx) != Some(&line.as_str()) {
+                    record_mismatch(current_line_idx, line);
+                } else {
+                    current_line_idx += 1;
+                    pending
+                        .get_or_insert_with(|| LineEdit {
+                            start: current_line_idx - 1,
+                            end: current_line_idx - 1,
+                            lines: Vec::new(),
+                        })
+                        .end = current_line_idx;
+                }
+            }
+            DiffOp::Add(line) => {
+                pending
+                    .get_or_insert_with(|| LineEdit {
+                        start: current_line_idx,
+                        end: current_line_idx,
+                        lines: Vec::new(),
+                    })
+                    .lines
+                    .push(line.clone());
+            }
+        }
+    }
+
+    edits.extend(pending);
+    Ok((edits, mismatch))
+}
+
+/// Convert a line edit into a `(pos, del, insert)` text splice.
+///
+/// `line_starts` holds the char offset of every line plus the total text
+/// length. Lines are separated by `\n`; when the text has no trailing newline
+/// the last line is unterminated, so edits touching it adjust the separator
+/// instead of adding one.
+fn line_edit_splice(
+    line_starts: &[usize],
+    trailing_newline: bool,
+    edit: &LineEdit,
+) -> (usize, usize, String) {
+    let line_count = line_starts.len() - 1;
+    let text_len = line_starts[line_count];
+
+    if edit.end < line_count || trailing_newline {
+        let pos = line_starts[edit.start];
+        let insert: String = edit.lines.iter().map(|l| format!("{}\n", l)).collect();
+        return (pos, line_starts[edit.end] - pos, insert);
+    }
+
+    if edit.lines.is_empty() {
+        // Deleting through the unterminated last line also removes the
+        // separator before it
+        let pos = line_starts[edit.start].saturating_sub(1);
+        return (pos, text_len - pos, String::new());
+    }
+
+    let joined = edit.lines.join("\n");
+    if edit.start == line_count && line_count > 0 {
+        // Appending after the unterminated last line
+        (text_len, 0, format!("\n{}", joined))
+    } else {
+        let pos = line_starts[edit.start];
+        (pos, text_len - pos, joined)
+    }
+}
+
 /// Represents a path segment - either a map key or a list index
 #[derive(Debug, PartialEq)]
 enum PathSegment {
@@ -136,14 +482,69 @@ enum PathSegment {
     Index(usize),
 }
 
+/// A path segment as written, before negative indices are resolved.
+#[derive(Debug, PartialEq)]
+enum ParsedSegment {
+    Segment(PathSegment),
+    /// A negative list index, stored as the distance from the end (`[-1]` is 1)
+    FromEnd(usize),
+}
+
 /// Parse a JSON-like path into components.
 /// Supports:
 /// - "foo.bar" or "$.foo.bar" for map keys
 /// - "foo[0]" or "$.foo[0]" for array indices
+/// - "foo[-1]" for indices counted from the end of a list
 /// - "foo[0].bar" for mixed paths
+/// - `foo["a.b"]` or `foo['a.b']` for keys containing `.`, `[` or `]`
+/// - `foo.a\.b` to escape a single character within a key
+///
+/// Negative indices are resolved against the list lengths in `doc`, so the
+/// returned segments only contain non-negative indices. A negative index
+/// beyond the start of a list (or into a list that doesn't exist) fails with
+/// `InvalidIndex`, and one applied to a map fails with `InvalidOp`.
 ///
 /// Returns a vector of path segments.
-fn parse_path(path: &str) -> Result<Vec<PathSegment>, AutomergeError> {
+fn parse_path<T: ReadDoc>(doc: &T, path: &str) -> Result<Vec<PathSegment>, AutomergeError> {
+    let mut segments = Vec::new();
+    // The object the next segment is looked up in, if it exists
+    let mut current = Some(ROOT);
+
+    for parsed in lex_path(path)? {
+        let segment = match parsed {
+            ParsedSegment::Segment(segment) => segment,
+            ParsedSegment::FromEnd(n) => {
+                let obj = current.as_ref().ok_or(AutomergeError::InvalidIndex(n))?;
+                match doc.object_type(obj)? {
+                    ObjType::List | ObjType::Text => {
+                        let len = doc.length(obj);
+                        if n > len {
+                            return Err(AutomergeError::InvalidIndex(n));
+                        }
+                        PathSegment::Index(len - n)
+                    }
+                    obj_type => return Err(AutomergeError::InvalidOp(obj_type)),
+                }
+            }
+        };
+        // Lookup errors (e.g., an index into a map) are left for the caller to report
+        current = match &current {
+            Some(obj) => match get_value_from_parent(doc, obj, &segment) {
+                Ok(Some((Value::Object(_), child))) => Some(child),
+                _ => None,
+            },
+            None => None,
+        };
+        segments.push(segment);
+    }
+
+    Ok(segments)
+}
+
+/// Split a path into segments without consulting the document.
+fn lex_path(path: &str) -> Result<Vec<ParsedSegment>, AutomergeError> {
+    // Only the empty path is the root: a bare "$" stays a key, so fields named
+    // "$" can still be read and written
     let trimmed = path.strip_prefix("$.").unwrap_or(path);
     if trimmed.is_empty() {
         return Ok(vec![]);
@@ -151,64 +552,131 @@ fn parse_path(path: &str) -> Result<Vec<PathSegment>, AutomergeError> {
 
     let mut segments = Vec::new();
     let mut current = String::new();
-    let mut in_bracket = false;
-    let mut bracket_content = String::new();
+    let mut chars = trimmed.chars();
 
-    for ch in trimmed.chars() {
+    while let Some(ch) = chars.next() {
         match ch {
-            '.' if !in_bracket => {
+            '\\' => {
+                // Take the next character literally
+                current.push(chars.next().ok_or(AutomergeError::Fail)?);
+            }
+            '.' | '[' => {
                 if !current.is_empty() {
-                    segments.push(PathSegment::Key(current.clone()));
-                    current.clear();
+                    let key = std::mem::take(&mut current);
+                    segments.push(ParsedSegment::Segment(PathSegment::Key(key)));
+                }
+                if ch == '[' {
+                    segments.push(parse_bracket_segment(&mut chars)?);
                 }
             }
-            '[' if !in_bracket => {
-                if !current.is_empty() {
-                    segments.push(PathSegment::Key(current.clone()));
-                    current.clear();
+            _ => current.push(ch),
+        }
+    }
+
+    if !current.is_empty() {
+        segments.push(ParsedSegment::Segment(PathSegment::Key(current)));
+    }
+
+    Ok(segments)
+}
+
+/// Parse the contents of a `[...]` path segment, after the opening bracket.
+///
+/// Quoted contents (`["key"]` or `['key']`) are map keys, with backslash
+/// escaping inside the quotes; anything else must be a list index.
+fn parse_bracket_segment(chars: &mut std::str::Chars) -> Result<ParsedSegment, AutomergeError> {
+    let mut content = String::new();
+    match chars.next() {
+        Some(quote @ ('"' | '\'')) => {
+            loop {
+                match chars.next().ok_or(AutomergeError::Fail)? {
+                    '\\' => content.push(chars.next().ok_or(AutomergeError::Fail)?),
+                    c if c == quote => break,
+                    c => content.push(c),
                 }
-                in_bracket = true;
-                bracket_content.clear();
             }
-            ']' if in_bracket => {
-                let index = bracket_content
-                    .parse::<usize>()
-                    .map_err(|_| AutomergeError::Fail)?;
-                segments.push(PathSegment::Index(index));
-                in_bracket = false;
-                bracket_content.clear();
+            if chars.next() != Some(']') {
+                return Err(AutomergeError::Fail);
             }
-            _ => {
-                if in_bracket {
-                    bracket_content.push(ch);
-                } else {
-                    current.push(ch);
+            Ok(ParsedSegment::Segment(PathSegment::Key(content)))
+        }
+        mut next => {
+            while let Some(c) = next {
+                if c == ']' {
+                    return match content.strip_prefix('-') {
+                        Some(n) => match n.parse::<usize>() {
+                            Ok(n) if n > 0 => Ok(ParsedSegment::FromEnd(n)),
+                            _ => Err(AutomergeError::Fail),
+                        },
+                        None => content
+                            .parse::<usize>()
+                            .map(|idx| ParsedSegment::Segment(PathSegment::Index(idx)))
+                            .map_err(|_| AutomergeError::Fail),
+                    };
                 }
+                content.push(c);
+                next = chars.next();
             }
+            Err(AutomergeError::Fail) // Unclosed bracket
         }
     }
+}
 
-    if in_bracket {
-        return Err(AutomergeError::Fail); // Unclosed bracket
-    }
+/// Append a map key to a path in this crate's path syntax.
+///
+/// Keys that contain `.`, `[`, `]`, quotes or backslashes, or that are empty,
+/// are written in quoted bracket form (e.g., `data["config.v1"]`) so that the
+/// result parses back to the same key.
+pub fn join_path(path: &str, key: &str) -> String {
+    let mut result = path.to_string();
+    push_path_key(&mut result, key);
+    result
+}
 
-    if !current.is_empty() {
-        segments.push(PathSegment::Key(current));
+/// Push a map key onto a path being built, quoting it when necessary.
+fn push_path_key(out: &mut String, key: &str) {
+    // A leading "$" key would be read back as the "$." root prefix
+    let needs_quotes = key.is_empty()
+        || (out.is_empty() && key == "$")
+        || key
+            .chars()
+            .any(|c| matches!(c, '.' | '[' | ']' | '"' | '\'' | '\\'));
+    if needs_quotes {
+        out.push_str("[\"");
+        for c in key.chars() {
+            if matches!(c, '"' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push_str("\"]");
+    } else {
+        if !out.is_empty() {
+            out.push('.');
+        }
+        out.push_str(key);
     }
-
-    Ok(segments)
 }
 
 /// Navigate to a nested object in the document, creating intermediate objects as needed.
 /// Returns the ObjId of the target object where the final value should be set.
-/// For write operations - does NOT create list elements, only maps.
+///
+/// A missing key is created as a list when the next segment is an index, and
+/// as a map otherwise. An index equal to a list's length appends a new element
+/// the same way, so `items[0].name` can be written into an empty list; any
+/// larger index is out of bounds.
 fn navigate_or_create_path<T: Transactable>(
     tx: &mut T,
     path: &[PathSegment],
 ) -> Result<ObjId, AutomergeError> {
     let mut current = ROOT;
 
-    for segment in path {
+    for (i, segment) in path.iter().enumerate() {
+        // The kind of object the following segment needs, should one be created
+        let create_type = match path.get(i + 1) {
+            Some(PathSegment::Index(_)) => ObjType::List,
+            _ => ObjType::Map,
+        };
         match segment {
             PathSegment::Key(key) => {
                 // Navigate or create map key
@@ -221,13 +689,11 @@ fn navigate_or_create_path<T: Transactable>(
                         return Err(AutomergeError::Fail);
                     }
                     None => {
-                        // Create a new map at this location
-                        current = tx.put_object(&current, key.as_str(), automerge::ObjType::Map)?;
+                        current = tx.put_object(&current, key.as_str(), create_type)?;
                     }
                 }
             }
             PathSegment::Index(idx) => {
-                // Navigate to list index (must already exist)
                 match tx.get(&current, *idx)? {
                     Some((Value::Object(_obj_type), obj_id)) => {
                         current = obj_id;
@@ -236,6 +702,12 @@ fn navigate_or_create_path<T: Transactable>(
                         // Element exists but is not an object
                         return Err(AutomergeError::Fail);
                     }
+                    None if tx.object_type(&current)? == ObjType::List
+                        && *idx == tx.length(&current) =>
+                    {
+                        // One past the end: append a new element
+                        current = tx.insert_object(&current, *idx, create_type)?;
+                    }
                     None => {
                         // Index out of bounds
                         return Err(AutomergeError::Fail);
@@ -257,6 +729,9 @@ fn navigate_path_read(
     let mut current = ROOT;
 
     for segment in path {
+        if !segment_applies(doc, &current, segment)? {
+            return Ok(None);
+        }
         match segment {
             PathSegment::Key(key) => match doc.get(&current, key.as_str())? {
                 Some((Value::Object(_obj_type), obj_id)) => {
@@ -278,12 +753,30 @@ fn navigate_path_read(
     Ok(Some(current))
 }
 
+/// Whether `segment` can address a child of `obj`: keys apply to maps and indices
+/// to lists and text. Automerge rejects the other combinations, but to a reader
+/// they just mean the path doesn't exist.
+fn segment_applies<T: ReadDoc>(
+    doc: &T,
+    obj: &ObjId,
+    segment: &PathSegment,
+) -> Result<bool, AutomergeError> {
+    let obj_type = doc.object_type(obj)?;
+    Ok(match segment {
+        PathSegment::Key(_) => matches!(obj_type, ObjType::Map | ObjType::Table),
+        PathSegment::Index(_) => matches!(obj_type, ObjType::List | ObjType::Text),
+    })
+}
+
 /// Helper to get a value from a parent object using a path segment
 fn get_value_from_parent<'a, T: ReadDoc>(
     doc: &'a T,
     parent: &ObjId,
     segment: &PathSegment,
 ) -> Result<Option<(Value<'a>, ObjId)>, AutomergeError> {
+    if !segment_applies(doc, parent, segment)? {
+        return Ok(None);
+    }
     match segment {
         PathSegment::Key(key) => doc.get(parent, key.as_str()),
         PathSegment::Index(idx) => doc.get(parent, *idx),
@@ -309,108 +802,2380 @@ fn put_value_to_parent<T: Transactable, V: Into<ScalarValue>>(
     }
 }
 
-/// Convenience methods for integrating Automerge with Redis persistence layers.
-pub trait RedisAutomergeExt {
-    /// Load an Automerge document from its persisted binary form.
-    ///
-    /// This is typically used when restoring a document from Redis' RDB
-    /// persistence format.
-    fn load(bytes: &[u8]) -> Result<Self, AutomergeError>
-    where
-        Self: Sized;
+/// Helper to put a string to a parent object using a path segment, as a string
+/// scalar or, with `as_object`, as a new `Text` object holding it
+fn put_text_to_parent<T: Transactable>(
+    tx: &mut T,
+    parent: &ObjId,
+    segment: &PathSegment,
+    value: &str,
+    as_object: bool,
+) -> Result<(), AutomergeError> {
+    if !as_object {
+        return put_value_to_parent(tx, parent, segment, value);
+    }
+    let text_obj = match segment {
+        PathSegment::Key(key) => tx.put_object(parent, key.as_str(), ObjType::Text)?,
+        PathSegment::Index(idx) => tx.put_object(parent, *idx, ObjType::Text)?,
+    };
+    if !value.is_empty() {
+        tx.splice_text(&text_obj, 0, 0, value)?;
+    }
+    Ok(())
+}
 
-    /// Save the current state of the document to a compact binary
-    /// representation suitable for RDB persistence.
-    fn save(&self) -> Vec<u8>;
+/// Helper to insert a string into a list, as a string scalar or, with
+/// `as_object`, as a new `Text` object holding it
+fn insert_text_to_list<T: Transactable>(
+    tx: &mut T,
+    list: &ObjId,
+    index: usize,
+    value: &str,
+    as_object: bool,
+) -> Result<(), AutomergeError> {
+    if !as_object {
+        return tx.insert(list, index, value);
+    }
+    let text_obj = tx.insert_object(list, index, ObjType::Text)?;
+    if !value.is_empty() {
+        tx.splice_text(&text_obj, 0, 0, value)?;
+    }
+    Ok(())
+}
 
-    /// Apply a list of changes to the document.
-    ///
-    /// The raw bytes of the applied changes are recorded internally so that
-    /// they can later be emitted as commands for Redis' AOF persistence.
-    fn apply(&mut self, changes: Vec<Change>) -> Result<(), AutomergeError>;
+/// Helper to write a scalar at `segment` of `parent`, inserting list elements
+/// when `insert` is set. With `text_as_object`, strings become new `Text`
+/// objects as in `put_text_to_parent`.
+fn put_scalar_to_parent<T: Transactable>(
+    tx: &mut T,
+    parent: &ObjId,
+    segment: &PathSegment,
+    insert: bool,
+    value: ScalarValue,
+    text_as_object: bool,
+) -> Result<(), AutomergeError> {
+    if let ScalarValue::Str(s) = &value {
+        return match segment {
+            PathSegment::Index(idx) if insert => {
+                insert_text_to_list(tx, parent, *idx, s, text_as_object)
+            }
+            _ => put_text_to_parent(tx, parent, segment, s, text_as_object),
+        };
+    }
+    match segment {
+        PathSegment::Index(idx) if insert => tx.insert(parent, *idx, value),
+        _ => put_value_to_parent(tx, parent, segment, value),
+    }
+}
 
-    /// Retrieve and clear the buffered AOF commands which represent the
-    /// changes previously applied via [`Self::apply`].
-    fn commands(&mut self) -> Vec<Vec<u8>>;
+/// Split an RFC 6901 JSON Pointer (e.g., `"/users/0/name"`) into its
+/// unescaped reference tokens. The empty pointer refers to the whole document.
+fn parse_json_pointer(pointer: &str) -> Result<Vec<String>, AutomergeError> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    let rest = pointer.strip_prefix('/').ok_or(AutomergeError::Fail)?;
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
 }
 
-/// Client for managing an Automerge CRDT document with Redis-specific features.
-///
-/// This struct wraps an Automerge document and provides:
-/// - Path-based access to nested data structures (maps and lists)
-/// - Change tracking for AOF persistence
-/// - Type-safe operations for common data types
-///
-/// # Examples
-///
-/// ```rust,no_run
-/// use redis_automerge::ext::RedisAutomergeClient;
-///
-/// let mut client = RedisAutomergeClient::new();
+/// Convert a JSON Pointer reference token into a path segment of `parent`.
 ///
-/// // Work with nested maps
-/// client.put_text("config.host", "localhost").unwrap();
-/// client.put_int("config.port", 6379).unwrap();
-///
-/// // Work with lists
-/// client.create_list("tags").unwrap();
-/// client.append_text("tags", "redis").unwrap();
-/// client.append_text("tags", "crdt").unwrap();
-///
-/// // Retrieve values
-/// let host = client.get_text("config.host").unwrap();
-/// let tag = client.get_text("tags[0]").unwrap();
-/// ```
-pub struct RedisAutomergeClient {
-    doc: Automerge,
-    aof: Vec<Vec<u8>>,
+/// Tokens address list elements by index when `parent` is a list, and map keys
+/// otherwise. `-` refers to the end of a list when `allow_end` is set.
+fn json_pointer_segment<T: ReadDoc>(
+    doc: &T,
+    parent: &ObjId,
+    token: &str,
+    allow_end: bool,
+) -> Result<PathSegment, AutomergeError> {
+    match doc.object_type(parent)? {
+        ObjType::List => {
+            if token == "-" && allow_end {
+                return Ok(PathSegment::Index(doc.length(parent)));
+            }
+            // RFC 6901 array indices are decimal digits without leading zeros
+            if token.is_empty()
+                || !token.bytes().all(|b| b.is_ascii_digit())
+                || (token.len() > 1 && token.starts_with('0'))
+            {
+                return Err(AutomergeError::Fail);
+            }
+            let idx = token.parse::<usize>().map_err(|_| AutomergeError::Fail)?;
+            Ok(PathSegment::Index(idx))
+        }
+        ObjType::Map | ObjType::Table => Ok(PathSegment::Key(token.to_string())),
+        ObjType::Text => Err(AutomergeError::Fail),
+    }
 }
 
-impl RedisAutomergeClient {
-    /// Creates a new client with an empty Automerge document.
-    ///
-    /// # Examples
-    ///
-    /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
-    ///
-    /// let client = RedisAutomergeClient::new();
-    /// ```
-    pub fn new() -> Self {
-        Self {
-            doc: Automerge::new(),
-            aof: Vec::new(),
+/// Walk a sequence of JSON Pointer tokens to the object they refer to.
+fn navigate_json_pointer<T: ReadDoc>(doc: &T, tokens: &[String]) -> Result<ObjId, AutomergeError> {
+    let mut current = ROOT;
+    for token in tokens {
+        let segment = json_pointer_segment(doc, &current, token, false)?;
+        match get_value_from_parent(doc, &current, &segment)? {
+            Some((Value::Object(_), obj_id)) => current = obj_id,
+            _ => return Err(AutomergeError::Fail),
         }
     }
+    Ok(current)
+}
 
-    /// Inserts a text value at the specified path.
-    ///
-    /// Supports nested paths with automatic intermediate map creation.
-    /// Array indices in the path must already exist.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path to the field (e.g., "name", "user.profile.name", "users[0].name", "$.data.value")
-    /// * `value` - Text value to insert
-    ///
-    /// # Examples
-    ///
-    /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
-    ///
-    /// let mut client = RedisAutomergeClient::new();
-    /// client.put_text("user.name", "Alice").unwrap();
-    /// client.put_text("$.config.host", "localhost").unwrap();
-    /// ```
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The path is invalid or empty
-    /// - An array index is out of bounds
-    /// - A path segment exists but is not an object
-    pub fn put_text(&mut self, path: &str, value: &str) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+/// Convert a JSON scalar into an Automerge scalar, using the same mapping as
+/// `from_json` (integers stay integers, other numbers become doubles).
+/// Typed-JSON objects are converted to the scalar they describe.
+fn json_to_scalar(value: &JsonValue) -> Result<ScalarValue, AutomergeError> {
+    match value {
+        JsonValue::Object(_) => typed_json_scalar(value).ok_or(AutomergeError::Fail),
+        JsonValue::String(s) => Ok(ScalarValue::from(s.as_str())),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Ok(ScalarValue::Int(i)),
+            None => n.as_f64().map(ScalarValue::F64).ok_or(AutomergeError::Fail),
+        },
+        JsonValue::Bool(b) => Ok(ScalarValue::Boolean(*b)),
+        JsonValue::Null => Ok(ScalarValue::Null),
+        JsonValue::Array(_) => Err(AutomergeError::Fail),
+    }
+}
+
+/// Recognize the typed-JSON form produced by `typed_scalar_to_json`: an object
+/// with a single `"$counter"` or `"$timestamp"` key holding an integer.
+fn typed_json_scalar(value: &JsonValue) -> Option<ScalarValue> {
+    let JsonValue::Object(map) = value else {
+        return None;
+    };
+    if map.len() != 1 {
+        return None;
+    }
+    let (key, inner) = map.iter().next()?;
+    let n = inner.as_i64()?;
+    match key.as_str() {
+        "$counter" => Some(ScalarValue::counter(n)),
+        "$timestamp" => Some(ScalarValue::Timestamp(n)),
+        _ => None,
+    }
+}
+
+/// Write a JSON value at `segment` of `parent`, creating maps and lists for
+/// objects and arrays. Typed-JSON objects such as `{"$counter": 5}` become
+/// the corresponding scalar, and strings are written as with
+/// `put_scalar_to_parent`. List elements are inserted when `insert` is set and
+/// overwritten otherwise.
+fn put_json_value<T: Transactable>(
+    tx: &mut T,
+    parent: &ObjId,
+    segment: &PathSegment,
+    insert: bool,
+    value: &JsonValue,
+    text_as_object: bool,
+) -> Result<(), AutomergeError> {
+    let obj_type = match value {
+        JsonValue::Object(_) if typed_json_scalar(value).is_some() => None,
+        JsonValue::Object(_) => Some(ObjType::Map),
+        JsonValue::Array(_) => Some(ObjType::List),
+        _ => None,
+    };
+    let Some(obj_type) = obj_type else {
+        let scalar = json_to_scalar(value)?;
+        return put_scalar_to_parent(tx, parent, segment, insert, scalar, text_as_object);
+    };
+
+    let obj_id = match segment {
+        PathSegment::Key(key) => tx.put_object(parent, key.as_str(), obj_type)?,
+        PathSegment::Index(idx) if insert => tx.insert_object(parent, *idx, obj_type)?,
+        PathSegment::Index(idx) => tx.put_object(parent, *idx, obj_type)?,
+    };
+    match value {
+        JsonValue::Object(map) => {
+            for (k, v) in map {
+                let segment = PathSegment::Key(k.clone());
+                put_json_value(tx, &obj_id, &segment, false, v, text_as_object)?;
+            }
+        }
+        JsonValue::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                let segment = PathSegment::Index(i);
+                put_json_value(tx, &obj_id, &segment, true, v, text_as_object)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Write a typed value at `segment` of `parent`, inserting list elements when
+/// `insert` is set. The inverse of reading a value with `value_to_typed`, except
+/// that text is written as a string scalar.
+fn put_typed_value<T: Transactable>(
+    tx: &mut T,
+    parent: &ObjId,
+    segment: &PathSegment,
+    insert: bool,
+    value: &TypedValue,
+) -> Result<(), AutomergeError> {
+    let scalar = match value {
+        TypedValue::Text(s) => ScalarValue::Str(s.as_str().into()),
+        TypedValue::Int(i) => ScalarValue::Int(*i),
+        TypedValue::Double(f) => ScalarValue::F64(*f),
+        TypedValue::Bool(b) => ScalarValue::Boolean(*b),
+        TypedValue::Timestamp(ts) => ScalarValue::Timestamp(*ts),
+        TypedValue::Counter(c) => ScalarValue::counter(*c),
+        TypedValue::Bytes(b) => ScalarValue::Bytes(b.clone()),
+        TypedValue::Null => ScalarValue::Null,
+        TypedValue::Array(_) | TypedValue::Object(_) => {
+            let obj_type = if matches!(value, TypedValue::Array(_)) {
+                ObjType::List
+            } else {
+                ObjType::Map
+            };
+            let obj_id = match segment {
+                PathSegment::Key(key) => tx.put_object(parent, key.as_str(), obj_type)?,
+                PathSegment::Index(idx) if insert => tx.insert_object(parent, *idx, obj_type)?,
+                PathSegment::Index(idx) => tx.put_object(parent, *idx, obj_type)?,
+            };
+            match value {
+                TypedValue::Array(items) => {
+                    for (i, item) in items.iter().enumerate() {
+                        put_typed_value(tx, &obj_id, &PathSegment::Index(i), true, item)?;
+                    }
+                }
+                TypedValue::Object(map) => {
+                    for (key, child) in map {
+                        put_typed_value(tx, &obj_id, &PathSegment::Key(key.clone()), false, child)?;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+    };
+    match segment {
+        PathSegment::Index(idx) if insert => tx.insert(parent, *idx, scalar),
+        _ => put_value_to_parent(tx, parent, segment, scalar),
+    }
+}
+
+/// Recursively copy the current state of `src` in `doc` into `dst`, keeping
+/// object types, scalar types and text marks but none of the history.
+fn copy_object<T: Transactable>(
+    doc: &Automerge,
+    src: &ObjId,
+    tx: &mut T,
+    dst: &ObjId,
+) -> Result<(), AutomergeError> {
+    match doc.object_type(src)? {
+        ObjType::Map | ObjType::Table => {
+            for key in doc.keys(src) {
+                match doc.get(src, key.as_str())? {
+                    Some((Value::Scalar(s), _)) => tx.put(dst, key.as_str(), s.into_owned())?,
+                    Some((Value::Object(obj_type), id)) => {
+                        let child = tx.put_object(dst, key.as_str(), obj_type)?;
+                        copy_object(doc, &id, tx, &child)?;
+                    }
+                    None => {}
+                }
+            }
+        }
+        ObjType::List => {
+            for index in 0..doc.length(src) {
+                match doc.get(src, index)? {
+                    Some((Value::Scalar(s), _)) => tx.insert(dst, index, s.into_owned())?,
+                    Some((Value::Object(obj_type), id)) => {
+                        let child = tx.insert_object(dst, index, obj_type)?;
+                        copy_object(doc, &id, tx, &child)?;
+                    }
+                    None => {}
+                }
+            }
+        }
+        ObjType::Text => {
+            tx.splice_text(dst, 0, 0, &doc.text(src)?)?;
+            for mark in doc.marks(src)? {
+                let copy = Mark::new(
+                    mark.name().to_string(),
+                    mark.value().clone(),
+                    mark.start,
+                    mark.end,
+                );
+                tx.mark(dst, copy, ExpandMark::None)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Copy the object `src` of `doc` into a scratch document of its own, so it
+/// can still be read while `doc` is modified. Only the subtree is copied.
+fn detach_object(
+    doc: &Automerge,
+    src: &ObjId,
+    obj_type: ObjType,
+) -> Result<(Automerge, ObjId), AutomergeError> {
+    let mut scratch = Automerge::new();
+    let mut tx = scratch.transaction();
+    let copy = tx.put_object(ROOT, "value", obj_type)?;
+    copy_object(doc, src, &mut tx, &copy)?;
+    tx.commit();
+    Ok((scratch, copy))
+}
+
+/// Merge a JSON object into an existing map.
+///
+/// Nested objects are merged into existing maps key by key; any other value
+/// replaces what is at the key. Keys whose value already matches are skipped
+/// so no redundant operations are recorded.
+fn merge_json_object<T: Transactable>(
+    tx: &mut T,
+    obj: &ObjId,
+    map: &serde_json::Map<String, JsonValue>,
+    text_as_object: bool,
+) -> Result<(), AutomergeError> {
+    for (key, value) in map {
+        let existing = tx.get(obj, key.as_str())?;
+        if let Some((current, current_id)) = &existing {
+            if value_to_json(tx, current, current_id, false)? == *value {
+                continue;
+            }
+        }
+        match (existing, value) {
+            (Some((Value::Object(ObjType::Map), map_id)), JsonValue::Object(nested))
+                if typed_json_scalar(value).is_none() =>
+            {
+                merge_json_object(tx, &map_id, nested, text_as_object)?;
+            }
+            _ => {
+                let segment = PathSegment::Key(key.clone());
+                put_json_value(tx, obj, &segment, false, value, text_as_object)?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply a single RFC 6902 operation (`add`, `remove`, `replace`, or `test`).
+fn apply_json_patch_op<T: Transactable>(
+    tx: &mut T,
+    op: &JsonValue,
+    text_as_object: bool,
+) -> Result<(), JsonPatchError> {
+    let invalid = |reason: &str| JsonPatchError::InvalidPatch(reason.to_string());
+    let name = op
+        .get("op")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid("every operation needs an \"op\""))?;
+    let pointer = op
+        .get("path")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid("every operation needs a \"path\""))?;
+    if !matches!(name, "add" | "remove" | "replace" | "test") {
+        return Err(invalid(&format!("unsupported operation '{}'", name)));
+    }
+    let value = match (name, op.get("value")) {
+        ("remove", _) => None,
+        (_, Some(value)) => Some(value),
+        (_, None) => return Err(invalid(&format!("'{}' needs a \"value\"", name))),
+    };
+    let tokens = parse_json_pointer(pointer)
+        .map_err(|_| JsonPatchError::InvalidPointer(pointer.to_string()))?;
+    let not_found = || JsonPatchError::PathNotFound(pointer.to_string());
+
+    // Only `test` may target the whole document
+    let Some((last, parent_tokens)) = tokens.split_last() else {
+        return match (name, value) {
+            ("test", Some(expected)) if obj_to_json(tx, &ROOT, false)? == *expected => Ok(()),
+            ("test", _) => Err(JsonPatchError::TestFailed(pointer.to_string())),
+            _ => Err(invalid(&format!(
+                "'{}' can't target the whole document",
+                name
+            ))),
+        };
+    };
+    let parent = navigate_json_pointer(tx, parent_tokens).map_err(|_| not_found())?;
+    let allow_end = name == "add";
+    let segment = json_pointer_segment(tx, &parent, last, allow_end).map_err(|_| not_found())?;
+
+    match (name, value) {
+        ("add", Some(value)) => match segment {
+            PathSegment::Index(idx) if idx > tx.length(&parent) => Err(not_found()),
+            PathSegment::Index(_) => Ok(put_json_value(
+                tx,
+                &parent,
+                &segment,
+                true,
+                value,
+                text_as_object,
+            )?),
+            PathSegment::Key(_) => Ok(put_json_value(
+                tx,
+                &parent,
+                &segment,
+                false,
+                value,
+                text_as_object,
+            )?),
+        },
+        ("replace", Some(value)) => {
+            if get_value_from_parent(tx, &parent, &segment)?.is_none() {
+                return Err(not_found());
+            }
+            Ok(put_json_value(
+                tx,
+                &parent,
+                &segment,
+                false,
+                value,
+                text_as_object,
+            )?)
+        }
+        ("remove", _) => {
+            if get_value_from_parent(tx, &parent, &segment)?.is_none() {
+                return Err(not_found());
+            }
+            match segment {
+                PathSegment::Key(key) => tx.delete(&parent, key.as_str())?,
+                PathSegment::Index(idx) => tx.delete(&parent, idx)?,
+            }
+            Ok(())
+        }
+        (_, Some(expected)) => {
+            let actual = match get_value_from_parent(tx, &parent, &segment)? {
+                Some((value, obj_id)) => value_to_json(tx, &value, &obj_id, false)?,
+                None => return Err(not_found()),
+            };
+            if actual == *expected {
+                Ok(())
+            } else {
+                Err(JsonPatchError::TestFailed(pointer.to_string()))
+            }
+        }
+        _ => unreachable!("every operation but remove has a value"),
+    }
+}
+
+/// Convert a scalar value to its JSON representation.
+///
+/// Timestamps are rendered as ISO 8601 strings and bytes as base64 strings.
+fn scalar_to_json(scalar: &ScalarValue) -> JsonValue {
+    match scalar {
+        ScalarValue::Str(s) => JsonValue::String(s.to_string()),
+        ScalarValue::Int(i) => JsonValue::Number((*i).into()),
+        ScalarValue::F64(f) => serde_json::Number::from_f64(*f)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        ScalarValue::Counter(c) => JsonValue::Number(i64::from(c).into()),
+        ScalarValue::Timestamp(ts) => {
+            // Convert Unix timestamp (milliseconds) to ISO 8601 string
+            let dt =
+                DateTime::from_timestamp_millis(*ts).unwrap_or_else(|| DateTime::<Utc>::UNIX_EPOCH);
+            JsonValue::String(dt.to_rfc3339())
+        }
+        ScalarValue::Boolean(b) => JsonValue::Bool(*b),
+        ScalarValue::Bytes(b) => {
+            // Encode binary data as a base64 string
+            JsonValue::String(general_purpose::STANDARD.encode(b))
+        }
+        ScalarValue::Null => JsonValue::Null,
+        _ => JsonValue::Null,
+    }
+}
+
+/// Recursively convert an Automerge object to JSON.
+///
+/// Maps become objects, lists become arrays, and text objects become strings.
+/// When `typed` is set, scalars are rendered with `typed_scalar_to_json`.
+fn obj_to_json<T: ReadDoc>(
+    doc: &T,
+    obj_id: &ObjId,
+    typed: bool,
+) -> Result<JsonValue, AutomergeError> {
+    match doc.object_type(obj_id)? {
+        ObjType::Map => {
+            let mut map = serde_json::Map::new();
+            for key in doc.keys(obj_id) {
+                if let Some((value, value_obj_id)) = doc.get(obj_id, &key)? {
+                    map.insert(
+                        key.to_string(),
+                        value_to_json(doc, &value, &value_obj_id, typed)?,
+                    );
+                }
+            }
+            Ok(JsonValue::Object(map))
+        }
+        ObjType::List => {
+            let mut arr = Vec::new();
+            for i in 0..doc.length(obj_id) {
+                if let Some((value, value_obj_id)) = doc.get(obj_id, i)? {
+                    arr.push(value_to_json(doc, &value, &value_obj_id, typed)?);
+                }
+            }
+            Ok(JsonValue::Array(arr))
+        }
+        ObjType::Text => Ok(JsonValue::String(doc.text(obj_id)?)),
+        // Unknown object type, treat as null
+        _ => Ok(JsonValue::Null),
+    }
+}
+
+/// Convert an Automerge value to JSON, recursing into nested objects.
+fn value_to_json<T: ReadDoc>(
+    doc: &T,
+    value: &Value,
+    obj_id: &ObjId,
+    typed: bool,
+) -> Result<JsonValue, AutomergeError> {
+    match value {
+        Value::Object(_) => obj_to_json(doc, obj_id, typed),
+        Value::Scalar(scalar) if typed => Ok(typed_scalar_to_json(scalar.as_ref())),
+        Value::Scalar(scalar) => Ok(scalar_to_json(scalar.as_ref())),
+    }
+}
+
+/// Convert a scalar value to JSON, keeping types that plain JSON would lose.
+///
+/// Counters become `{"$counter": n}` and timestamps `{"$timestamp": millis}`,
+/// which `from_json` reads back as the original types. Other scalars render
+/// as in `scalar_to_json`.
+fn typed_scalar_to_json(scalar: &ScalarValue) -> JsonValue {
+    match scalar {
+        ScalarValue::Counter(c) => serde_json::json!({ "$counter": i64::from(c) }),
+        ScalarValue::Timestamp(ts) => serde_json::json!({ "$timestamp": ts }),
+        _ => scalar_to_json(scalar),
+    }
+}
+
+/// Convert a patch value to JSON. Newly created objects are rendered empty
+/// since their contents arrive in subsequent patches.
+fn patch_value_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::Object(ObjType::Map) | Value::Object(ObjType::Table) => {
+            JsonValue::Object(serde_json::Map::new())
+        }
+        Value::Object(ObjType::List) => JsonValue::Array(Vec::new()),
+        Value::Object(ObjType::Text) => JsonValue::String(String::new()),
+        Value::Scalar(s) => scalar_to_json(s.as_ref()),
+    }
+}
+
+/// Render a patch path in this crate's path syntax (e.g., `"user.tags[0]"`).
+///
+/// `prop` is the property within the patched object, if the action targets one.
+fn format_patch_path(path: &[(ObjId, Prop)], prop: Option<&Prop>) -> String {
+    let mut result = String::new();
+    for p in path.iter().map(|(_, p)| p).chain(prop) {
+        match p {
+            Prop::Map(key) => push_path_key(&mut result, key),
+            Prop::Seq(idx) => result.push_str(&format!("[{}]", idx)),
+        }
+    }
+    result
+}
+
+/// Convert an Automerge patch into a JSON object.
+///
+/// Every object has a `"path"` rendered in this crate's path syntax and an
+/// `"action"` of `"put"`, `"insert"`, `"delete"`, `"increment"`, `"splice"`,
+/// `"mark"`, or `"conflict"`. The remaining fields depend on the action:
+///
+/// - `put`: `value` and `conflict`
+/// - `insert`: `values` (inserted at `path`)
+/// - `delete`: `length` for list deletions
+/// - `increment`: `value` (the delta)
+/// - `splice`: `index` and `value` (the inserted text) relative to the text at `path`
+/// - `mark`: `marks`, each with `name`, `value`, `start`, and `end`
+pub fn patch_to_json(patch: &Patch) -> JsonValue {
+    let mut obj = serde_json::Map::new();
+    let (action, prop) = match &patch.action {
+        PatchAction::PutMap {
+            key,
+            value,
+            conflict,
+        } => {
+            obj.insert("value".to_string(), patch_value_to_json(&value.0));
+            obj.insert("conflict".to_string(), JsonValue::Bool(*conflict));
+            ("put", Some(Prop::Map(key.clone())))
+        }
+        PatchAction::PutSeq {
+            index,
+            value,
+            conflict,
+        } => {
+            obj.insert("value".to_string(), patch_value_to_json(&value.0));
+            obj.insert("conflict".to_string(), JsonValue::Bool(*conflict));
+            ("put", Some(Prop::Seq(*index)))
+        }
+        PatchAction::Insert { index, values } => {
+            let values = values
+                .iter()
+                .map(|(value, _, _)| patch_value_to_json(value))
+                .collect();
+            obj.insert("values".to_string(), JsonValue::Array(values));
+            ("insert", Some(Prop::Seq(*index)))
+        }
+        PatchAction::SpliceText { index, value, .. } => {
+            obj.insert("index".to_string(), JsonValue::Number((*index).into()));
+            obj.insert("value".to_string(), JsonValue::String(value.make_string()));
+            ("splice", None)
+        }
+        PatchAction::Increment { prop, value } => {
+            obj.insert("value".to_string(), JsonValue::Number((*value).into()));
+            ("increment", Some(prop.clone()))
+        }
+        PatchAction::Conflict { prop } => ("conflict", Some(prop.clone())),
+        PatchAction::DeleteMap { key } => ("delete", Some(Prop::Map(key.clone()))),
+        PatchAction::DeleteSeq { index, length } => {
+            obj.insert("length".to_string(), JsonValue::Number((*length).into()));
+            ("delete", Some(Prop::Seq(*index)))
+        }
+        PatchAction::Mark { marks } => {
+            let marks = marks
+                .iter()
+                .map(|mark| {
+                    let mut m = serde_json::Map::new();
+                    m.insert(
+                        "name".to_string(),
+                        JsonValue::String(mark.name().to_string()),
+                    );
+                    m.insert("value".to_string(), scalar_to_json(mark.value()));
+                    m.insert("start".to_string(), JsonValue::Number(mark.start.into()));
+                    m.insert("end".to_string(), JsonValue::Number(mark.end.into()));
+                    JsonValue::Object(m)
+                })
+                .collect();
+            obj.insert("marks".to_string(), JsonValue::Array(marks));
+            ("mark", None)
+        }
+    };
+    obj.insert(
+        "path".to_string(),
+        JsonValue::String(format_patch_path(&patch.path, prop.as_ref())),
+    );
+    obj.insert("action".to_string(), JsonValue::String(action.to_string()));
+    JsonValue::Object(obj)
+}
+
+/// Append a length-prefixed list of change hashes to `out`.
+fn encode_hash_list<'a>(out: &mut Vec<u8>, hashes: impl ExactSizeIterator<Item = &'a ChangeHash>) {
+    out.extend_from_slice(&(hashes.len() as u32).to_be_bytes());
+    for hash in hashes {
+        out.extend_from_slice(hash.as_ref());
+    }
+}
+
+/// Append an optional length-prefixed list of change hashes to `out`.
+fn encode_optional_hash_list(out: &mut Vec<u8>, hashes: &Option<Vec<ChangeHash>>) {
+    match hashes {
+        Some(hashes) => {
+            out.push(1);
+            encode_hash_list(out, hashes.iter());
+        }
+        None => out.push(0),
+    }
+}
+
+/// Cursor over the session portion of an encoded sync state.
+struct SyncStateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SyncStateReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AutomergeError> {
+        if self.bytes.len() < len {
+            return Err(AutomergeError::Fail);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, AutomergeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn flag(&mut self) -> Result<bool, AutomergeError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(AutomergeError::Fail),
+        }
+    }
+
+    fn len(&mut self) -> Result<usize, AutomergeError> {
+        let bytes: [u8; 4] = self.take(4)?.try_into().map_err(|_| AutomergeError::Fail)?;
+        Ok(u32::from_be_bytes(bytes) as usize)
+    }
+
+    fn hashes(&mut self) -> Result<Vec<ChangeHash>, AutomergeError> {
+        let count = self.len()?;
+        (0..count)
+            .map(|_| ChangeHash::try_from(self.take(32)?).map_err(|_| AutomergeError::Fail))
+            .collect()
+    }
+
+    fn optional_hashes(&mut self) -> Result<Option<Vec<ChangeHash>>, AutomergeError> {
+        if self.flag()? {
+            Ok(Some(self.hashes()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Encode a sync state so it can be handed to a peer as an opaque token.
+///
+/// The output starts with [`sync::State::encode`], which only covers the
+/// heads shared with the peer, followed by the in-session fields (what the
+/// peer has, needs and was already sent). Carrying the session fields is what
+/// lets a stateless caller continue a sync session across separate commands.
+pub fn encode_sync_state(state: &sync::State) -> Vec<u8> {
+    let mut out = state.encode();
+
+    encode_hash_list(&mut out, state.last_sent_heads.iter());
+    encode_optional_hash_list(&mut out, &state.their_heads);
+    encode_optional_hash_list(&mut out, &state.their_need);
+    match &state.their_have {
+        Some(haves) => {
+            out.push(1);
+            out.extend_from_slice(&(haves.len() as u32).to_be_bytes());
+            for have in haves {
+                encode_hash_list(&mut out, have.last_sync.iter());
+                let bloom = have.bloom.to_bytes();
+                out.extend_from_slice(&(bloom.len() as u32).to_be_bytes());
+                out.extend_from_slice(&bloom);
+            }
+        }
+        None => out.push(0),
+    }
+    encode_hash_list(&mut out, state.sent_hashes.iter());
+    out.push(state.in_flight as u8);
+    out.push(state.have_responded as u8);
+    match &state.their_capabilities {
+        Some(caps) => {
+            out.push(1);
+            out.extend_from_slice(&(caps.len() as u32).to_be_bytes());
+            for cap in caps {
+                out.push(match cap {
+                    sync::Capability::MessageV1 => 0x01,
+                    sync::Capability::MessageV2 => 0x02,
+                    sync::Capability::Unknown(v) => *v,
+                });
+            }
+        }
+        None => out.push(0),
+    }
+    out
+}
+
+/// Decode a sync state produced by [`encode_sync_state`].
+///
+/// Plain [`sync::State::encode`] output is also accepted, in which case a new
+/// session is started from the persisted shared heads.
+pub fn decode_sync_state(bytes: &[u8]) -> Result<sync::State, AutomergeError> {
+    let mut state = sync::State::decode(bytes).map_err(|_| AutomergeError::Fail)?;
+    let persisted_len = state.encode().len();
+    let mut reader = SyncStateReader {
+        bytes: &bytes[persisted_len..],
+    };
+    if reader.bytes.is_empty() {
+        return Ok(state);
+    }
+
+    state.last_sent_heads = reader.hashes()?;
+    state.their_heads = reader.optional_hashes()?;
+    state.their_need = reader.optional_hashes()?;
+    state.their_have = if reader.flag()? {
+        let count = reader.len()?;
+        // The count comes from the client; each entry takes at least two
+        // length prefixes, so don't reserve more than the bytes left can hold
+        let mut haves = Vec::with_capacity(count.min(reader.bytes.len() / 8));
+        for _ in 0..count {
+            let last_sync = reader.hashes()?;
+            let bloom_len = reader.len()?;
+            let bloom = sync::BloomFilter::try_from(reader.take(bloom_len)?)
+                .map_err(|_| AutomergeError::Fail)?;
+            haves.push(sync::Have { last_sync, bloom });
+        }
+        Some(haves)
+    } else {
+        None
+    };
+    state.sent_hashes = reader.hashes()?.into_iter().collect();
+    state.in_flight = reader.flag()?;
+    state.have_responded = reader.flag()?;
+    state.their_capabilities = if reader.flag()? {
+        let count = reader.len()?;
+        let caps = reader.take(count)?;
+        Some(
+            caps.iter()
+                .map(|cap| match cap {
+                    0x01 => sync::Capability::MessageV1,
+                    0x02 => sync::Capability::MessageV2,
+                    v => sync::Capability::Unknown(*v),
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    if !reader.bytes.is_empty() {
+        return Err(AutomergeError::Fail);
+    }
+    Ok(state)
+}
+
+/// Convenience methods for integrating Automerge with Redis persistence layers.
+pub trait RedisAutomergeExt {
+    /// Load an Automerge document from its persisted binary form.
+    ///
+    /// This is typically used when restoring a document from Redis' RDB
+    /// persistence format.
+    fn load(bytes: &[u8]) -> Result<Self, AutomergeError>
+    where
+        Self: Sized;
+
+    /// Save the current state of the document to a compact binary
+    /// representation suitable for RDB persistence.
+    fn save(&self) -> Vec<u8>;
+
+    /// Apply a list of changes to the document.
+    ///
+    /// The raw bytes of the applied changes are recorded internally so that
+    /// they can later be emitted as commands for Redis' AOF persistence.
+    ///
+    /// Returns the hashes of the changes that were integrated into the document,
+    /// found by comparing the heads before and after, in topological order.
+    /// Changes the document already had are skipped by Automerge and left out,
+    /// as are changes queued because their dependencies are missing; a queued
+    /// change is reported by the call that supplies its last missing dependency.
+    fn apply(&mut self, changes: Vec<Change>) -> Result<Vec<ChangeHash>, AutomergeError>;
+
+    /// Retrieve and clear the buffered AOF commands which represent the
+    /// changes previously applied via [`Self::apply`].
+    fn commands(&mut self) -> Vec<Vec<u8>>;
+}
+
+/// Client for managing an Automerge CRDT document with Redis-specific features.
+///
+/// This struct wraps an Automerge document and provides:
+/// - Path-based access to nested data structures (maps and lists)
+/// - Change tracking for AOF persistence
+/// - Type-safe operations for common data types
+///
+/// Each write operation has a `*_with_change` variant that returns the hash
+/// and raw bytes of the change ([`CommittedChange`]) and accepts a
+/// [`CommitOptions`] carrying an optional commit message and timestamp for
+/// the change.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use redis_automerge::ext::RedisAutomergeClient;
+///
+/// let mut client = RedisAutomergeClient::new();
+///
+/// // Work with nested maps
+/// client.put_text("config.host", "localhost").unwrap();
+/// client.put_int("config.port", 6379).unwrap();
+///
+/// // Work with lists
+/// client.create_list("tags").unwrap();
+/// client.append_text("tags", "redis").unwrap();
+/// client.append_text("tags", "crdt").unwrap();
+///
+/// // Retrieve values
+/// let host = client.get_text("config.host").unwrap();
+/// let tag = client.get_text("tags[0]").unwrap();
+/// ```
+pub struct RedisAutomergeClient {
+    doc: Automerge,
+    aof: Vec<Vec<u8>>,
+    /// Whether strings are written as `Text` objects instead of string scalars
+    text_as_object: bool,
+    /// Whether the actor was chosen with `set_actor`, and so is persisted
+    actor_pinned: bool,
+    /// Output of the last full `save()` and the heads it was taken at. Dropped
+    /// by `record_change` whenever the document changes.
+    cached_save: Mutex<Option<(Vec<ChangeHash>, Vec<u8>)>>,
+}
+
+impl RedisAutomergeClient {
+    /// Creates a new client with an empty Automerge document.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let client = RedisAutomergeClient::new();
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            doc: Automerge::new(),
+            aof: Vec::new(),
+            text_as_object: false,
+            actor_pinned: false,
+            cached_save: Mutex::new(None),
+        }
+    }
+
+    /// Creates an independent copy of this document.
+    ///
+    /// The copy shares the full change history, so both documents can still
+    /// be synchronized with each other afterward. It gets a new actor ID and
+    /// starts with an empty AOF buffer, since the buffered changes belong to
+    /// the original document.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("name", "Alice").unwrap();
+    ///
+    /// let copy = client.fork();
+    /// assert_eq!(copy.get_heads(), client.get_heads());
+    /// ```
+    pub fn fork(&self) -> Self {
+        Self {
+            doc: self.doc.fork(),
+            aof: Vec::new(),
+            text_as_object: self.text_as_object,
+            actor_pinned: false,
+            cached_save: Mutex::new(None),
+        }
+    }
+
+    /// Choose how strings written to the document are stored.
+    ///
+    /// By default they are stored as string scalars, which must be converted
+    /// into a `Text` object before they can be spliced or marked. When enabled,
+    /// every method that writes a string creates a `Text` object instead, so
+    /// it can be edited collaboratively: `put_text`, `append_text`, the list
+    /// writers such as `insert_at` and `splice_list`, `put_many` and the JSON
+    /// writers `apply_json_merge` and `apply_json_patch`. Strings already in
+    /// the document are unaffected, and `from_json` always stores scalars.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.set_text_as_object(true);
+    /// client.put_text("title", "Draft").unwrap();
+    ///
+    /// assert_eq!(client.get_type("title").unwrap(), Some("text"));
+    /// ```
+    pub fn set_text_as_object(&mut self, enabled: bool) {
+        self.text_as_object = enabled;
+    }
+
+    /// Whether strings are written as `Text` objects.
+    pub fn text_as_object(&self) -> bool {
+        self.text_as_object
+    }
+
+    /// Collect summary statistics about the document.
+    ///
+    /// The size is taken from the kept full save (see
+    /// [`with_saved`](Self::with_saved)), so the document is only serialized
+    /// if it changed since it was last saved; everything else is read from
+    /// the document's metadata.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("name", "Alice").unwrap();
+    ///
+    /// let info = client.info();
+    /// assert_eq!(info.num_changes, 1);
+    /// assert_eq!(info.num_heads, 1);
+    /// ```
+    pub fn info(&self) -> DocumentInfo {
+        let stats = self.doc.stats();
+        DocumentInfo {
+            num_changes: stats.num_changes as usize,
+            num_heads: self.doc.get_heads().len(),
+            num_actors: stats.num_actors as usize,
+            size_bytes: self.with_saved(|bytes| bytes.len()),
+            pending_aof: self.aof.len(),
+        }
+    }
+
+    /// Count the changes made by each actor.
+    ///
+    /// Returns `(actor id bytes, change count)` pairs, with actors in the order
+    /// their first change appears in the history.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("name", "Alice").unwrap();
+    /// client.put_int("age", 30).unwrap();
+    ///
+    /// let stats = client.actor_stats();
+    /// assert_eq!(stats.len(), 1);
+    /// assert_eq!(stats[0].1, 2);
+    /// ```
+    pub fn actor_stats(&self) -> Vec<(Vec<u8>, usize)> {
+        let mut stats: Vec<(Vec<u8>, usize)> = Vec::new();
+        for change in self.doc.get_changes(&[]) {
+            let actor = change.actor_id().to_bytes();
+            match stats.iter_mut().find(|(id, _)| id.as_slice() == actor) {
+                Some((_, count)) => *count += 1,
+                None => stats.push((actor.to_vec(), 1)),
+            }
+        }
+        stats
+    }
+
+    /// Squash the document's history into a single baseline change.
+    ///
+    /// The current state (including object types, counters, timestamps and
+    /// text marks) is rebuilt in a fresh document, and the old change history
+    /// is discarded. Returns the full save of the compacted document, which is
+    /// also kept for [`with_saved`](Self::with_saved), together with the number
+    /// of bytes it is smaller than the save of the old history.
+    ///
+    /// The baseline is written by a new random actor, since changes of the old
+    /// actor that peers still hold would clash with a new history under the
+    /// same actor and sequence numbers. An actor pinned with
+    /// [`set_actor`](Self::set_actor) is kept; peers holding its old changes
+    /// then can't merge with the compacted document at all.
+    ///
+    /// **This breaks incremental sync.** Peers holding heads from the old
+    /// history can no longer exchange changes with the compacted document and
+    /// must replace their copy with a full one (for example via
+    /// `save()`/`load()`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// for i in 0..100 {
+    ///     client.put_int("counter", i).unwrap();
+    /// }
+    ///
+    /// let (bytes, saved) = client.compact().unwrap();
+    /// assert!(saved > 0);
+    /// assert_eq!(bytes, client.save());
+    /// assert_eq!(client.get_changes(&[]).len(), 1);
+    /// ```
+    pub fn compact(&mut self) -> Result<(Vec<u8>, usize), AutomergeError> {
+        let before = self.with_saved(|bytes| bytes.len());
+
+        let mut fresh = Automerge::new();
+        if self.actor_pinned {
+            fresh.set_actor(self.doc.get_actor().clone());
+        }
+        let mut tx = fresh.transaction();
+        copy_object(&self.doc, &ROOT, &mut tx, &ROOT)?;
+        tx.commit();
+        let bytes = fresh.save();
+
+        self.doc = fresh;
+        // Buffered changes belong to the discarded history
+        self.aof.clear();
+        let changes: Vec<Vec<u8>> = self
+            .doc
+            .get_changes(&[])
+            .iter()
+            .map(|change| change.raw_bytes().to_vec())
+            .collect();
+        for change in changes {
+            self.record_change(change);
+        }
+        *self
+            .cached_save
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some((self.doc.get_heads(), bytes.clone()));
+
+        let saved = before.saturating_sub(bytes.len());
+        Ok((bytes, saved))
+    }
+
+    /// Save only the changes that `heads` don't cover.
+    ///
+    /// Pass the heads the document had at an earlier save: appending the
+    /// result to that save yields bytes that `load()` reconstructs into the
+    /// current document. With no heads every change is returned. Nothing is
+    /// recorded, so callers sharing a document can each track their own base.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::{RedisAutomergeClient, RedisAutomergeExt};
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_int("a", 1).unwrap();
+    /// let mut bytes = client.save();
+    /// let saved_at = client.get_heads();
+    ///
+    /// client.put_int("b", 2).unwrap();
+    /// bytes.extend(client.save_after(&saved_at));
+    ///
+    /// let loaded = RedisAutomergeClient::load(&bytes).unwrap();
+    /// assert_eq!(loaded.get_int("b").unwrap(), Some(2));
+    /// ```
+    pub fn save_after(&self, heads: &[ChangeHash]) -> Vec<u8> {
+        self.doc.save_after(heads)
+    }
+
+    /// Set the actor ID used to attribute subsequent changes.
+    ///
+    /// Changes already in the document keep the actor that made them. The
+    /// actor ID isn't part of `save()`, but an actor set here is reported by
+    /// `pinned_actor()` so RDB and AOF persistence can restore it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.set_actor(&[0xab, 0xcd]);
+    /// assert_eq!(client.actor_id(), vec![0xab, 0xcd]);
+    /// ```
+    pub fn set_actor(&mut self, id: &[u8]) {
+        self.doc.set_actor(automerge::ActorId::from(id));
+        self.actor_pinned = true;
+    }
+
+    /// The actor ID set with `set_actor`, if any.
+    ///
+    /// A random actor is left out, so copies restored from the same snapshot
+    /// don't end up writing changes under one actor.
+    pub fn pinned_actor(&self) -> Option<Vec<u8>> {
+        self.actor_pinned.then(|| self.actor_id())
+    }
+
+    /// Get the actor ID used to attribute subsequent changes.
+    pub fn actor_id(&self) -> Vec<u8> {
+        self.doc.get_actor().to_bytes().to_vec()
+    }
+
+    /// Approximate memory footprint of this document in bytes.
+    ///
+    /// The document itself is estimated from its operation and change counts,
+    /// which Automerge keeps up to date, so answering `MEMORY USAGE` or sampling
+    /// keys for eviction doesn't serialize it. Buffered AOF changes and the kept
+    /// output of the last full save are counted exactly.
+    pub fn heap_size(&self) -> usize {
+        // Rough in-memory cost of one operation and of one change graph entry
+        const OP_BYTES: usize = 16;
+        const CHANGE_BYTES: usize = 64;
+
+        let stats = self.doc.stats();
+        std::mem::size_of::<Self>()
+            + stats.num_ops as usize * OP_BYTES
+            + stats.num_changes as usize * CHANGE_BYTES
+            + self.cached_save_len()
+            + self.aof.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Call `f` with the full save of the document, without copying it.
+    ///
+    /// Serializing a large document is costly, so the output is kept and
+    /// reused until the document changes, which drops it. Saves on the main thread, such as
+    /// `AM.SAVE`, `AM.DUMP` or a foreground `SAVE`, fill it, and a `BGSAVE` or
+    /// AOF rewrite forked afterwards reuses it. Saves made in the forked child
+    /// can't be kept, so a document written since its last foreground save is
+    /// serialized again by every background save.
+    pub fn with_saved<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        let heads = self.doc.get_heads();
+        let mut cached = self
+            .cached_save
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match &*cached {
+            Some((saved_at, bytes)) if *saved_at == heads => f(bytes),
+            _ => {
+                let bytes = self.doc.save();
+                let result = f(&bytes);
+                *cached = Some((heads, bytes));
+                result
+            }
+        }
+    }
+
+    /// Buffer a committed change for the AOF and drop the kept full save,
+    /// which no longer matches the document.
+    ///
+    /// Every change to the document, whether committed here or applied from a
+    /// peer, is recorded through this method.
+    fn record_change(&mut self, change_bytes: Vec<u8>) {
+        self.aof.push(change_bytes);
+        *self
+            .cached_save
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// Size of the kept full save, which is a second copy of the document.
+    pub(crate) fn cached_save_len(&self) -> usize {
+        self.cached_save
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .map_or(0, |(_, bytes)| bytes.len())
+    }
+
+    /// Inserts a text value at the specified path.
+    ///
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the field (e.g., "name", "user.profile.name", "users[0].name", "$.data.value")
+    /// * `value` - Text value to insert
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("user.name", "Alice").unwrap();
+    /// client.put_text("$.config.host", "localhost").unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The path is invalid or empty
+    /// - An array index is out of bounds
+    /// - A path segment exists but is not an object
+    pub fn put_text(&mut self, path: &str, value: &str) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_text_to_parent(
+            &mut tx,
+            &parent_obj,
+            &field_name[0],
+            value,
+            self.text_as_object,
+        )?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieves a text value from the specified path.
+    ///
+    /// Returns `None` if the path doesn't exist or the value is not text.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the field
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("user.name", "Alice").unwrap();
+    ///
+    /// let name = client.get_text("user.name").unwrap();
+    /// assert_eq!(name, Some("Alice".to_string()));
+    ///
+    /// let missing = client.get_text("user.email").unwrap();
+    /// assert_eq!(missing, None);
+    /// ```
+    pub fn get_text(&self, path: &str) -> Result<Option<String>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
+            // Handle scalar string values
+            Some((Value::Scalar(s), _)) => {
+                if let ScalarValue::Str(t) = s.as_ref() {
+                    return Ok(Some(t.to_string()));
+                }
+            }
+            // Handle Text objects
+            Some((Value::Object(automerge::ObjType::Text), obj_id)) => {
+                return Ok(Some(self.doc.text(&obj_id)?));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Check whether the value at `path` already equals `value`.
+    ///
+    /// Strings compare against text objects as well as string scalars, so a
+    /// repeated `put_text` on either is recognised as a no-op. Missing paths
+    /// and values of a different type never match.
+    pub fn value_matches(&self, path: &str, value: &ScalarValue) -> Result<bool, AutomergeError> {
+        if let ScalarValue::Str(expected) = value {
+            return Ok(self.get_text(path)?.as_deref() == Some(expected.as_str()));
+        }
+
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(false);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(false),
+            }
+        };
+
+        match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
+            Some((Value::Scalar(s), _)) => Ok(s.as_ref() == value),
+            _ => Ok(false),
+        }
+    }
+
+    /// Apply raw Automerge change bytes to this document.
+    ///
+    /// This allows applying changes generated by one document to another,
+    /// enabling real-time synchronization between clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `change_bytes` - Raw bytes from an Automerge change
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::transaction::CommitOptions;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client1 = RedisAutomergeClient::new();
+    /// let change = client1
+    ///     .put_text_with_change("field", "value", CommitOptions::default())
+    ///     .unwrap()
+    ///     .unwrap();
+    ///
+    /// let mut client2 = RedisAutomergeClient::new();
+    /// client2.apply_change_bytes(&change).unwrap();
+    ///
+    /// assert_eq!(client2.get_text("field").unwrap(), Some("value".to_string()));
+    /// ```
+    pub fn apply_change_bytes(&mut self, change_bytes: &[u8]) -> Result<(), AutomergeError> {
+        let change = Change::from_bytes(change_bytes.to_vec())?;
+        self.apply(vec![change])?;
+        Ok(())
+    }
+
+    /// Insert a text value and return the raw change bytes.
+    ///
+    /// Like `put_text()` but returns Automerge change bytes that can
+    /// be published to other clients for real-time synchronization.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the field
+    /// * `value` - Text value to insert
+    /// * `options` - Optional commit message and timestamp to attach to the change
+    ///
+    /// # Returns
+    ///
+    /// - `Some((hash, bytes))` - The hash and raw bytes of the change, if one was generated
+    /// - `None` - If no change was needed
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::transaction::CommitOptions;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// let options = CommitOptions::default().with_message("Set greeting");
+    /// let change = client.put_text_with_change("field", "hello", options).unwrap();
+    ///
+    /// if let Some(change_bytes) = change {
+    ///     // Publish to other clients
+    /// }
+    /// ```
+    pub fn put_text_with_change(
+        &mut self,
+        path: &str,
+        value: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_text_to_parent(
+            &mut tx,
+            &parent_obj,
+            &field_name[0],
+            value,
+            self.text_as_object,
+        )?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Delete a value at the specified path.
+    ///
+    /// Removes the field or array element at the given path. For maps, this removes
+    /// the key entirely. For arrays, this removes the element at the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the field to delete (e.g., "name", "user.profile.age", "items[0]")
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("user.name", "Alice").unwrap();
+    /// client.put_int("user.age", 30).unwrap();
+    ///
+    /// // Delete the age field
+    /// client.delete("user.age").unwrap();
+    ///
+    /// // age is now gone
+    /// assert_eq!(client.get_int("user.age").unwrap(), None);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The path is invalid or empty
+    /// - The parent path doesn't exist
+    pub fn delete(&mut self, path: &str) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+
+        // First check if the parent path exists (read-only check)
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Err(AutomergeError::Fail),
+            }
+        };
+
+        // Now create a transaction and delete
+        let mut tx = self.doc.transaction();
+
+        // Delete the field from the parent
+        match &field_name[0] {
+            PathSegment::Key(key) => {
+                tx.delete(&parent_obj, key.as_str())?;
+            }
+            PathSegment::Index(idx) => {
+                tx.delete(&parent_obj, *idx)?;
+            }
+        }
+
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a value and return the raw change bytes.
+    ///
+    /// Like `delete()` but returns Automerge change bytes that can
+    /// be published to other clients for real-time synchronization.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the field to delete
+    /// * `options` - Optional commit message and timestamp to attach to the change
+    ///
+    /// # Returns
+    ///
+    /// - `Some((hash, bytes))` - The hash and raw bytes of the change, if one was generated
+    /// - `None` - If no change was needed
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::transaction::CommitOptions;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("field", "value").unwrap();
+    /// let change = client
+    ///     .delete_with_change("field", CommitOptions::default())
+    ///     .unwrap();
+    ///
+    /// if let Some(change_bytes) = change {
+    ///     // Publish to other clients
+    /// }
+    /// ```
+    pub fn delete_with_change(
+        &mut self,
+        path: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+
+        // First check if the parent path exists (read-only check)
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Err(AutomergeError::Fail),
+            }
+        };
+
+        // Now create a transaction and delete
+        let mut tx = self.doc.transaction();
+
+        // Delete the field from the parent
+        match &field_name[0] {
+            PathSegment::Key(key) => {
+                tx.delete(&parent_obj, key.as_str())?;
+            }
+            PathSegment::Index(idx) => {
+                tx.delete(&parent_obj, *idx)?;
+            }
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Move the value at `src` to `dst` in a single change.
+    ///
+    /// Objects are moved with their whole subtree, keeping text marks. Automerge
+    /// can't reparent an object, so the subtree is recreated at `dst`: concurrent
+    /// edits made under `src` by other peers stay with the deleted original.
+    /// Intermediate maps of `dst` are created as needed, and an existing value at
+    /// `dst` is overwritten.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("user.name", "Alice").unwrap();
+    ///
+    /// client.rename("user", "profile.owner").unwrap();
+    /// assert_eq!(client.get_text("profile.owner.name").unwrap(), Some("Alice".to_string()));
+    /// assert_eq!(client.get_type("user").unwrap(), None);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - `src` doesn't exist
+    /// - Either path is empty, or `dst` is `src` or lies inside it
+    pub fn rename(&mut self, src: &str, dst: &str) -> Result<(), AutomergeError> {
+        self.rename_with_change(src, dst, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Move the value at `src` to `dst` and return the raw change bytes.
+    pub fn rename_with_change(
+        &mut self,
+        src: &str,
+        dst: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        self.transfer_with_change(src, dst, true, options)
+    }
+
+    /// Copy the value at `src` to `dst` in a single change, leaving `src` as is.
+    ///
+    /// Objects are copied with their whole subtree, keeping text marks, and the
+    /// copy is independent of the original afterward. Intermediate maps of
+    /// `dst` are created as needed, and an existing value at `dst` is replaced.
+    /// `dst` may lie inside `src`, since the source is read before anything is
+    /// written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("templates.task.status", "todo").unwrap();
+    ///
+    /// client.copy_path("templates.task", "tasks.first").unwrap();
+    /// assert_eq!(client.get_text("tasks.first.status").unwrap(), Some("todo".to_string()));
+    /// assert_eq!(client.get_text("templates.task.status").unwrap(), Some("todo".to_string()));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `src` doesn't exist or either path is empty.
+    pub fn copy_path(&mut self, src: &str, dst: &str) -> Result<(), AutomergeError> {
+        self.copy_path_with_change(src, dst, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Copy the value at `src` to `dst` and return the raw change bytes.
+    pub fn copy_path_with_change(
+        &mut self,
+        src: &str,
+        dst: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        self.transfer_with_change(src, dst, false, options)
+    }
+
+    /// Write a copy of `src` at `dst`, deleting `src` afterward when
+    /// `remove_src` is set.
+    ///
+    /// An object at `src` is first copied into a scratch document holding only
+    /// that subtree (see [`detach_object`]), which leaves `self.doc` free for the
+    /// transaction and lets `dst` lie inside `src`.
+    fn transfer_with_change(
+        &mut self,
+        src: &str,
+        dst: &str,
+        remove_src: bool,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let src_segments = parse_path(&self.doc, src)?;
+        let dst_segments = parse_path(&self.doc, dst)?;
+
+        if src_segments.is_empty() || dst_segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+        // Moving a value into itself would delete the copy along with the original
+        if remove_src && dst_segments.starts_with(&src_segments) {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (src_parent_path, src_field) = src_segments.split_at(src_segments.len() - 1);
+        let src_parent = if src_parent_path.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, src_parent_path)?.ok_or(AutomergeError::Fail)?
+        };
+        let (value, src_obj) = get_value_from_parent(&self.doc, &src_parent, &src_field[0])?
+            .ok_or(AutomergeError::Fail)?;
+        let value = value.into_owned();
+        // The transaction borrows the document, so an object is read out first
+        let source = match value {
+            Value::Object(obj_type) => Some(detach_object(&self.doc, &src_obj, obj_type)?),
+            Value::Scalar(_) => None,
+        };
+
+        let mut tx = self.doc.transaction();
+        let (dst_parent_path, dst_field) = dst_segments.split_at(dst_segments.len() - 1);
+        let dst_parent = navigate_or_create_path(&mut tx, dst_parent_path)?;
+        match (value, source) {
+            (Value::Object(obj_type), Some((scratch, detached))) => {
+                let copy = match &dst_field[0] {
+                    PathSegment::Key(key) => tx.put_object(&dst_parent, key.as_str(), obj_type)?,
+                    PathSegment::Index(idx) => tx.put_object(&dst_parent, *idx, obj_type)?,
+                };
+                copy_object(&scratch, &detached, &mut tx, &copy)?;
+            }
+            (Value::Scalar(s), _) => {
+                put_value_to_parent(&mut tx, &dst_parent, &dst_field[0], s.into_owned())?
+            }
+            (Value::Object(_), None) => unreachable!("objects are detached above"),
+        }
+
+        // Overwriting an ancestor of `src` has already removed it
+        if remove_src && !src_segments.starts_with(&dst_segments) {
+            match &src_field[0] {
+                PathSegment::Key(key) => tx.delete(&src_parent, key.as_str())?,
+                PathSegment::Index(idx) => tx.delete(&src_parent, *idx)?,
+            }
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Remove every key of a map or every element of a list, keeping the
+    /// container itself.
+    ///
+    /// The container keeps its object id, so peers editing it concurrently keep
+    /// merging into the same object. The empty path refers to the root map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("tags").unwrap();
+    /// client.append_text("tags", "redis").unwrap();
+    ///
+    /// client.clear_container("tags").unwrap();
+    /// assert_eq!(client.list_len("tags").unwrap(), Some(0));
+    /// assert_eq!(client.get_type("tags").unwrap(), Some("list"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path doesn't exist or doesn't point to a map or list.
+    pub fn clear_container(&mut self, path: &str) -> Result<(), AutomergeError> {
+        self.clear_container_with_change(path, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Empty a map or list and return the raw change bytes, or None if it
+    /// was already empty.
+    pub fn clear_container_with_change(
+        &mut self,
+        path: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        let obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let mut tx = self.doc.transaction();
+        match tx.object_type(&obj)? {
+            ObjType::Map | ObjType::Table => {
+                let keys: Vec<String> = tx.keys(&obj).collect();
+                for key in keys {
+                    tx.delete(&obj, key.as_str())?;
+                }
+            }
+            ObjType::List => {
+                for index in (0..tx.length(&obj)).rev() {
+                    tx.delete(&obj, index)?;
+                }
+            }
+            ObjType::Text => return Err(AutomergeError::Fail),
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Insert an integer value using a path (e.g., "user.age", "users[0].age", or "$.user.age").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    pub fn put_int(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieve an integer value using a path (e.g., "user.age", "users[0].age", or "$.user.age").
+    pub fn get_int(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        if let Some((Value::Scalar(s), _)) =
+            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
+        {
+            if let ScalarValue::Int(i) = s.as_ref() {
+                return Ok(Some(*i));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Insert an integer value and return the raw change bytes.
+    pub fn put_int_with_change(
+        &mut self,
+        path: &str,
+        value: i64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Insert a double value using a path (e.g., "metrics.temperature", "temps[0]", or "$.metrics.temperature").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    pub fn put_double(&mut self, path: &str, value: f64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert a double value and return the raw change bytes.
+    pub fn put_double_with_change(
+        &mut self,
+        path: &str,
+        value: f64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Retrieve a double value using a path (e.g., "metrics.temperature", "temps[0]", or "$.metrics.temperature").
+    pub fn get_double(&self, path: &str) -> Result<Option<f64>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        if let Some((Value::Scalar(s), _)) =
+            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
+        {
+            if let ScalarValue::F64(f) = s.as_ref() {
+                return Ok(Some(*f));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Retrieve a number as an integer, truncating a double toward zero.
+    ///
+    /// Unlike `get_int`, a value stored as a double (for example by an earlier
+    /// `put_double` at the same path) is converted instead of reading as `None`.
+    /// NaN reads as `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AutomergeError::InvalidValueType` for a double outside the `i64`
+    /// range, including the infinities.
+    pub fn get_int_coerced(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
+        if let Some(value) = self.get_int(path)? {
+            return Ok(Some(value));
+        }
+        let Some(value) = self.get_double(path)?.filter(|value| !value.is_nan()) else {
+            return Ok(None);
+        };
+        // The cast to i128 only saturates past the i64 range, which try_from rejects
+        i64::try_from(value.trunc() as i128).map(Some).map_err(|_| {
+            AutomergeError::InvalidValueType {
+                expected: "a double within the integer range".to_string(),
+                unexpected: value.to_string(),
+            }
+        })
+    }
+
+    /// Retrieve a number as a double, widening an integer.
+    ///
+    /// Unlike `get_double`, a value stored as an integer is converted instead of
+    /// reading as `None`.
+    pub fn get_double_coerced(&self, path: &str) -> Result<Option<f64>, AutomergeError> {
+        if let Some(value) = self.get_double(path)? {
+            return Ok(Some(value));
+        }
+        Ok(self.get_int(path)?.map(|value| value as f64))
+    }
+
+    /// Insert a boolean value using a path (e.g., "flags.active", "flags\[0\]", or "$.flags.active").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    pub fn put_bool(&mut self, path: &str, value: bool) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieve a boolean value using a path (e.g., "flags.active", "flags\[0\]", or "$.flags.active").
+    pub fn get_bool(&self, path: &str) -> Result<Option<bool>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        if let Some((Value::Scalar(s), _)) =
+            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
+        {
+            if let ScalarValue::Boolean(b) = s.as_ref() {
+                return Ok(Some(*b));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Insert a boolean value and return the raw change bytes.
+    pub fn put_bool_with_change(
+        &mut self,
+        path: &str,
+        value: bool,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Insert a null value using a path (e.g., "user.nickname", "items[0]", or "$.user.nickname").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    ///
+    /// This is equivalent to a JSON `null` and can be used to reset a field.
+    pub fn put_null(&mut self, path: &str) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], ScalarValue::Null)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert a null value and return the raw change bytes.
+    pub fn put_null_with_change(
+        &mut self,
+        path: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], ScalarValue::Null)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Insert a binary value using a path (e.g., "files.avatar", "blobs\[0\]", or "$.files.avatar").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    pub fn put_bytes(&mut self, path: &str, value: &[u8]) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(
+            &mut tx,
+            &parent_obj,
+            &field_name[0],
+            ScalarValue::Bytes(value.to_vec()),
+        )?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Retrieve a binary value using a path (e.g., "files.avatar", "blobs\[0\]", or "$.files.avatar").
+    pub fn get_bytes(&self, path: &str) -> Result<Option<Vec<u8>>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        if let Some((Value::Scalar(s), _)) =
+            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
+        {
+            if let ScalarValue::Bytes(b) = s.as_ref() {
+                return Ok(Some(b.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Insert a binary value and return the raw change bytes.
+    pub fn put_bytes_with_change(
+        &mut self,
+        path: &str,
+        value: &[u8],
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        put_value_to_parent(
+            &mut tx,
+            &parent_obj,
+            &field_name[0],
+            ScalarValue::Bytes(value.to_vec()),
+        )?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Set several scalar values in a single transaction and return the raw change bytes.
+    ///
+    /// Each `(path, value)` pair is written like the individual `put_*` methods,
+    /// creating intermediate maps and lists as needed. All writes are committed as one
+    /// change; if any path is invalid, none of the writes are applied.
+    ///
+    /// # Returns
+    ///
+    /// - `Some((hash, bytes))` - The hash and raw bytes of the change, if one was generated
+    /// - `None` - If `ops` is empty
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::ScalarValue;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// let change = client
+    ///     .put_many(vec![
+    ///         ("user.name".to_string(), ScalarValue::from("Alice")),
+    ///         ("user.age".to_string(), ScalarValue::Int(30)),
+    ///     ])
+    ///     .unwrap();
+    /// ```
+    pub fn put_many(
+        &mut self,
+        ops: Vec<(String, ScalarValue)>,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        self.put_many_with_change(ops, CommitOptions::default())
+    }
+
+    /// Set several scalar values in a single change carrying `options`.
+    pub fn put_many_with_change(
+        &mut self,
+        ops: Vec<(String, ScalarValue)>,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        fn put_one<T: Transactable>(
+            tx: &mut T,
+            path: &str,
+            value: ScalarValue,
+            text_as_object: bool,
+        ) -> Result<(), AutomergeError> {
+            let segments = parse_path(tx, path)?;
+            if segments.is_empty() {
+                return Err(AutomergeError::Fail);
+            }
+            let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+            let parent_obj = navigate_or_create_path(tx, parent_path)?;
+            put_scalar_to_parent(
+                tx,
+                &parent_obj,
+                &field_name[0],
+                false,
+                value,
+                text_as_object,
+            )
+        }
+
+        let text_as_object = self.text_as_object;
+        let mut tx = self.doc.transaction();
+        for (path, value) in ops {
+            if let Err(e) = put_one(&mut tx, &path, value, text_as_object) {
+                tx.rollback();
+                return Err(e);
+            }
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Insert a counter value using a path (e.g., "stats.views", "counters[0]", or "$.stats.views").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    ///
+    /// Counters are CRDT values that support increment operations with proper
+    /// conflict resolution across distributed systems.
+    pub fn put_counter(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
         let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
@@ -420,163 +3185,542 @@ impl RedisAutomergeClient {
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
 
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        // Put counter value
+        match &field_name[0] {
+            PathSegment::Key(key) => {
+                tx.put(&parent_obj, key.as_str(), ScalarValue::Counter(value.into()))?;
+            }
+            PathSegment::Index(idx) => {
+                tx.put(&parent_obj, *idx, ScalarValue::Counter(value.into()))?;
+            }
+        }
+
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Retrieves a text value from the specified path.
+    /// Insert a counter value and return the raw change bytes.
+    pub fn put_counter_with_change(
+        &mut self,
+        path: &str,
+        value: i64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+
+        // Put counter value
+        match &field_name[0] {
+            PathSegment::Key(key) => {
+                tx.put(&parent_obj, key.as_str(), ScalarValue::Counter(value.into()))?;
+            }
+            PathSegment::Index(idx) => {
+                tx.put(&parent_obj, *idx, ScalarValue::Counter(value.into()))?;
+            }
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Retrieve a counter value using a path (e.g., "stats.views", "counters[0]", or "$.stats.views").
+    /// Returns the current counter value as an i64.
+    pub fn get_counter(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Ok(None);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        if let Some((Value::Scalar(s), _)) =
+            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
+        {
+            if let ScalarValue::Counter(c) = s.as_ref() {
+                return Ok(Some(i64::from(c)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get a value with type information from the specified path.
     ///
-    /// Returns `None` if the path doesn't exist or the value is not text.
+    /// This method extracts values preserving their Automerge types, which is
+    /// useful for building JSON index documents with proper type representation.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the field
+    /// * `path` - Path to the value
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(TypedValue)` if the path exists, `None` otherwise. The empty
+    /// path returns the root map as a `TypedValue::Object`.
+    pub fn get_typed_value(&self, path: &str) -> Result<Option<TypedValue>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return self.value_to_typed(&Value::Object(ObjType::Map), &ROOT);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
+            Some((Value::Scalar(s), _)) => {
+                let typed_val = match s.as_ref() {
+                    ScalarValue::Str(text) => TypedValue::Text(text.to_string()),
+                    ScalarValue::Int(i) => TypedValue::Int(*i),
+                    ScalarValue::F64(f) => TypedValue::Double(*f),
+                    ScalarValue::Boolean(b) => TypedValue::Bool(*b),
+                    ScalarValue::Timestamp(ts) => TypedValue::Timestamp(*ts),
+                    ScalarValue::Counter(c) => TypedValue::Counter(i64::from(c)),
+                    ScalarValue::Bytes(b) => TypedValue::Bytes(b.clone()),
+                    ScalarValue::Null => TypedValue::Null,
+                    _ => TypedValue::Null,
+                };
+                Ok(Some(typed_val))
+            }
+            Some((Value::Object(obj_type), obj_id)) => {
+                // Handle Text objects
+                if obj_type == automerge::ObjType::Text {
+                    let text = self.doc.text(&obj_id)?;
+                    return Ok(Some(TypedValue::Text(text)));
+                }
+
+                // Handle List objects - return as Array
+                if obj_type == automerge::ObjType::List {
+                    let mut arr = Vec::new();
+                    let len = self.doc.length(&obj_id);
+                    for i in 0..len {
+                        if let Some((value, value_obj_id)) = self.doc.get(&obj_id, i)? {
+                            if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
+                                arr.push(typed_val);
+                            }
+                        }
+                    }
+                    return Ok(Some(TypedValue::Array(arr)));
+                }
+
+                // Handle Map objects
+                if obj_type == automerge::ObjType::Map {
+                    let mut map = std::collections::HashMap::new();
+                    for key in self.doc.keys(&obj_id) {
+                        if let Some((value, value_obj_id)) = self.doc.get(&obj_id, &key)? {
+                            if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
+                                map.insert(key, typed_val);
+                            }
+                        }
+                    }
+                    return Ok(Some(TypedValue::Object(map)));
+                }
+
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get a value with type information as of the document state at `heads`.
+    ///
+    /// Returns what `get_typed_value` returned when the document's heads were
+    /// `heads`, for example to read a field as it was right before a change.
+    /// Only the addressed value is read, through Automerge's `*_at` lookups, so
+    /// nothing is copied.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
+    /// use redis_automerge::ext::{RedisAutomergeClient, TypedValue};
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.put_text("user.name", "Alice").unwrap();
+    /// client.put_text("status", "draft").unwrap();
+    /// let heads = client.get_heads();
+    /// client.put_text("status", "published").unwrap();
+    ///
+    /// assert_eq!(
+    ///     client.get_typed_value_at("status", &heads).unwrap(),
+    ///     Some(TypedValue::Text("draft".to_string()))
+    /// );
+    /// ```
     ///
-    /// let name = client.get_text("user.name").unwrap();
-    /// assert_eq!(name, Some("Alice".to_string()));
+    /// # Errors
     ///
-    /// let missing = client.get_text("user.email").unwrap();
-    /// assert_eq!(missing, None);
-    /// ```
-    pub fn get_text(&self, path: &str) -> Result<Option<String>, AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Returns `AutomergeError::InvalidHash` if a head isn't part of the document.
+    pub fn get_typed_value_at(
+        &self,
+        path: &str,
+        heads: &[ChangeHash],
+    ) -> Result<Option<TypedValue>, AutomergeError> {
+        // Unknown heads would otherwise be silently ignored
+        if let Some(hash) = heads
+            .iter()
+            .find(|hash| self.doc.get_change_by_hash(hash).is_none())
+        {
+            return Err(AutomergeError::InvalidHash(*hash));
+        }
 
-        if segments.is_empty() {
-            return Ok(None);
+        // Resolved like `parse_path`, but against the document at `heads`
+        let mut current = Some((Value::Object(ObjType::Map), ROOT));
+        for parsed in lex_path(path)? {
+            let parent = match &current {
+                Some((Value::Object(obj_type), obj)) => Some((*obj_type, obj.clone())),
+                _ => None,
+            };
+            current = match (parsed, parent) {
+                (ParsedSegment::FromEnd(n), None) => return Err(AutomergeError::InvalidIndex(n)),
+                (ParsedSegment::FromEnd(n), Some((ObjType::List | ObjType::Text, obj))) => {
+                    let len = self.doc.length_at(&obj, heads);
+                    if n > len {
+                        return Err(AutomergeError::InvalidIndex(n));
+                    }
+                    self.doc.get_at(&obj, len - n, heads)?
+                }
+                (ParsedSegment::FromEnd(_), Some((obj_type, _))) => {
+                    return Err(AutomergeError::InvalidOp(obj_type))
+                }
+                (ParsedSegment::Segment(segment), Some((obj_type, obj))) => {
+                    match (segment, obj_type) {
+                        (PathSegment::Key(key), ObjType::Map | ObjType::Table) => {
+                            self.doc.get_at(&obj, key.as_str(), heads)?
+                        }
+                        (PathSegment::Index(idx), ObjType::List | ObjType::Text) => {
+                            self.doc.get_at(&obj, idx, heads)?
+                        }
+                        _ => None,
+                    }
+                }
+                (ParsedSegment::Segment(_), None) => None,
+            };
+        }
+
+        match current {
+            Some((value, obj)) => self.value_to_typed_in(&value, &obj, Some(heads)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read-only copy of the document as it was at `heads`.
+    fn fork_at(&self, heads: &[ChangeHash]) -> Result<Self, AutomergeError> {
+        Ok(Self {
+            doc: self.doc.fork_at(heads)?,
+            aof: Vec::new(),
+            text_as_object: false,
+            actor_pinned: false,
+            cached_save: Mutex::new(None),
+        })
+    }
+
+    /// Helper method to convert Automerge Value to TypedValue
+    fn value_to_typed(
+        &self,
+        value: &Value,
+        obj_id: &ObjId,
+    ) -> Result<Option<TypedValue>, AutomergeError> {
+        self.value_to_typed_in(value, obj_id, None)
+    }
+
+    /// Like `value_to_typed`, reading objects as of `heads` when given.
+    fn value_to_typed_in(
+        &self,
+        value: &Value,
+        obj_id: &ObjId,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Option<TypedValue>, AutomergeError> {
+        match value {
+            Value::Scalar(s) => {
+                let typed_val = match s.as_ref() {
+                    ScalarValue::Str(text) => TypedValue::Text(text.to_string()),
+                    ScalarValue::Int(i) => TypedValue::Int(*i),
+                    ScalarValue::F64(f) => TypedValue::Double(*f),
+                    ScalarValue::Boolean(b) => TypedValue::Bool(*b),
+                    ScalarValue::Timestamp(ts) => TypedValue::Timestamp(*ts),
+                    ScalarValue::Counter(c) => TypedValue::Counter(i64::from(c)),
+                    ScalarValue::Bytes(b) => TypedValue::Bytes(b.clone()),
+                    ScalarValue::Null => TypedValue::Null,
+                    _ => TypedValue::Null,
+                };
+                Ok(Some(typed_val))
+            }
+            Value::Object(obj_type) => {
+                // Handle Text objects
+                if *obj_type == automerge::ObjType::Text {
+                    let text = match heads {
+                        Some(heads) => self.doc.text_at(obj_id, heads)?,
+                        None => self.doc.text(obj_id)?,
+                    };
+                    return Ok(Some(TypedValue::Text(text)));
+                }
+
+                // Handle List objects
+                if *obj_type == automerge::ObjType::List {
+                    let mut arr = Vec::new();
+                    let len = match heads {
+                        Some(heads) => self.doc.length_at(obj_id, heads),
+                        None => self.doc.length(obj_id),
+                    };
+                    for i in 0..len {
+                        let item = match heads {
+                            Some(heads) => self.doc.get_at(obj_id, i, heads)?,
+                            None => self.doc.get(obj_id, i)?,
+                        };
+                        if let Some((val, val_obj_id)) = item {
+                            if let Some(typed_val) =
+                                self.value_to_typed_in(&val, &val_obj_id, heads)?
+                            {
+                                arr.push(typed_val);
+                            }
+                        }
+                    }
+                    return Ok(Some(TypedValue::Array(arr)));
+                }
+
+                // Handle Map objects
+                if *obj_type == automerge::ObjType::Map {
+                    let mut map = std::collections::HashMap::new();
+                    let keys: Vec<String> = match heads {
+                        Some(heads) => self.doc.keys_at(obj_id, heads).collect(),
+                        None => self.doc.keys(obj_id).collect(),
+                    };
+                    for key in keys {
+                        let entry = match heads {
+                            Some(heads) => self.doc.get_at(obj_id, &key, heads)?,
+                            None => self.doc.get(obj_id, &key)?,
+                        };
+                        if let Some((val, val_obj_id)) = entry {
+                            if let Some(typed_val) =
+                                self.value_to_typed_in(&val, &val_obj_id, heads)?
+                            {
+                                map.insert(key, typed_val);
+                            }
+                        }
+                    }
+                    return Ok(Some(TypedValue::Object(map)));
+                }
+
+                Ok(None)
+            }
         }
+    }
+
+    /// Get all values from a list at the specified path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the list
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(Vec<TypedValue>)` if the path points to a list, `None` otherwise.
+    pub fn get_list_values(&self, path: &str) -> Result<Option<Vec<TypedValue>>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = if parent_path.is_empty() {
+        let list_obj = if segments.is_empty() {
             ROOT
         } else {
-            match navigate_path_read(&self.doc, parent_path)? {
+            match navigate_path_read(&self.doc, &segments)? {
                 Some(obj) => obj,
                 None => return Ok(None),
             }
         };
 
-        match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
-            // Handle scalar string values
-            Some((Value::Scalar(s), _)) => {
-                if let ScalarValue::Str(t) = s.as_ref() {
-                    return Ok(Some(t.to_string()));
+        // Check if it's a list
+        let obj_type = self.doc.object_type(&list_obj)?;
+        if obj_type == automerge::ObjType::List {
+            let mut values = Vec::new();
+            let len = self.doc.length(&list_obj);
+
+            for i in 0..len {
+                if let Some((value, value_obj_id)) = self.doc.get(&list_obj, i)? {
+                    if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
+                        values.push(typed_val);
+                    }
                 }
             }
-            // Handle Text objects
-            Some((Value::Object(automerge::ObjType::Text), obj_id)) => {
-                return Ok(Some(self.doc.text(&obj_id)?));
-            }
-            _ => {}
+
+            return Ok(Some(values));
         }
+
         Ok(None)
     }
 
-    /// Apply raw Automerge change bytes to this document.
+    /// Get a slice of the values in a list at the specified path.
     ///
-    /// This allows applying changes generated by one document to another,
-    /// enabling real-time synchronization between clients.
+    /// Modeled on Redis `LRANGE`: `stop` is inclusive and negative indices count
+    /// from the end of the list (`-1` is the last element). Out-of-range bounds
+    /// are clamped, so a range that selects nothing returns an empty vector.
     ///
     /// # Arguments
     ///
-    /// * `change_bytes` - Raw bytes from an Automerge change
-    ///
-    /// # Examples
-    ///
-    /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
-    ///
-    /// let mut client1 = RedisAutomergeClient::new();
-    /// let change = client1.put_text_with_change("field", "value").unwrap().unwrap();
+    /// * `path` - Path to the list
+    /// * `start` - First index to include
+    /// * `stop` - Last index to include
     ///
-    /// let mut client2 = RedisAutomergeClient::new();
-    /// client2.apply_change_bytes(&change).unwrap();
+    /// # Returns
     ///
-    /// assert_eq!(client2.get_text("field").unwrap(), Some("value".to_string()));
-    /// ```
-    pub fn apply_change_bytes(&mut self, change_bytes: &[u8]) -> Result<(), AutomergeError> {
-        let change = Change::from_bytes(change_bytes.to_vec())?;
-        self.doc.apply_changes(vec![change])?;
-        Ok(())
+    /// Returns `Some(Vec<TypedValue>)` if the path points to a list, `None` otherwise.
+    pub fn get_list_range(
+        &self,
+        path: &str,
+        start: i64,
+        stop: i64,
+    ) -> Result<Option<Vec<TypedValue>>, AutomergeError> {
+        let values = match self.get_list_values(path)? {
+            Some(values) => values,
+            None => return Ok(None),
+        };
+
+        let len = values.len() as i64;
+        let start = if start < 0 {
+            (len + start).max(0)
+        } else {
+            start
+        };
+        let stop = if stop < 0 {
+            len + stop
+        } else {
+            stop.min(len - 1)
+        };
+
+        if start > stop || start >= len {
+            return Ok(Some(Vec::new()));
+        }
+
+        Ok(Some(
+            values
+                .into_iter()
+                .skip(start as usize)
+                .take((stop - start + 1) as usize)
+                .collect(),
+        ))
     }
 
-    /// Insert a text value and return the raw change bytes.
+    /// Get all keys from a map at the specified path.
     ///
-    /// Like `put_text()` but returns Automerge change bytes that can
-    /// be published to other clients for real-time synchronization.
+    /// Keys are sorted lexicographically so the output is deterministic
+    /// regardless of Automerge's internal iteration order.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the field
-    /// * `value` - Text value to insert
+    /// * `path` - Path to the map
     ///
     /// # Returns
     ///
-    /// - `Some(Vec<u8>)` - Raw change bytes if a change was generated
-    /// - `None` - If no change was needed
+    /// Returns `Some(Vec<String>)` if the path points to a map, `None` otherwise.
+    pub fn get_map_keys(&self, path: &str) -> Result<Option<Vec<String>>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        let map_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, &segments)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
+
+        // Check if it's a map
+        let obj_type = self.doc.object_type(&map_obj)?;
+        if obj_type == automerge::ObjType::Map {
+            let mut keys: Vec<String> = self.doc.keys(&map_obj).collect();
+            keys.sort();
+            return Ok(Some(keys));
+        }
+
+        Ok(None)
+    }
+
+    /// Get the values of all immediate children of a map at the specified path.
     ///
-    /// # Examples
+    /// Values are returned in the same (sorted) order as [`Self::get_map_keys`].
     ///
-    /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
+    /// # Arguments
     ///
-    /// let mut client = RedisAutomergeClient::new();
-    /// let change = client.put_text_with_change("field", "hello").unwrap();
+    /// * `path` - Path to the map
     ///
-    /// if let Some(change_bytes) = change {
-    ///     // Publish to other clients
-    /// }
-    /// ```
-    pub fn put_text_with_change(
-        &mut self,
-        path: &str,
-        value: &str,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
-        let mut tx = self.doc.transaction();
-
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
-        }
+    /// # Returns
+    ///
+    /// Returns `Some(Vec<TypedValue>)` if the path points to a map, `None` otherwise.
+    pub fn get_map_values(&self, path: &str) -> Result<Option<Vec<TypedValue>>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+        let map_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, &segments)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
+        };
 
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
-        let (hash, _patch) = tx.commit();
+        // Check if it's a map
+        let obj_type = self.doc.object_type(&map_obj)?;
+        if obj_type == automerge::ObjType::Map {
+            let mut keys: Vec<String> = self.doc.keys(&map_obj).collect();
+            keys.sort();
 
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+            let mut values = Vec::new();
+            for key in keys {
+                if let Some((value, value_obj_id)) = self.doc.get(&map_obj, &key)? {
+                    if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
+                        values.push(typed_val);
+                    }
+                }
             }
+
+            return Ok(Some(values));
         }
 
         Ok(None)
     }
 
-    /// Delete a value at the specified path.
+    /// Increment a counter at the specified path by the given delta.
     ///
-    /// Removes the field or array element at the given path. For maps, this removes
-    /// the key entirely. For arrays, this removes the element at the index.
+    /// This uses Automerge's CRDT counter increment operation, which properly
+    /// merges concurrent increments from different replicas.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the field to delete (e.g., "name", "user.profile.age", "items[0]")
+    /// * `path` - Path to the counter field
+    /// * `delta` - Amount to increment (can be negative to decrement)
     ///
     /// # Examples
     ///
@@ -584,23 +3728,14 @@ impl RedisAutomergeClient {
     /// use redis_automerge::ext::RedisAutomergeClient;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.put_text("user.name", "Alice").unwrap();
-    /// client.put_int("user.age", 30).unwrap();
-    ///
-    /// // Delete the age field
-    /// client.delete("user.age").unwrap();
+    /// client.put_counter("views", 0).unwrap();
+    /// client.inc_counter("views", 1).unwrap();
+    /// client.inc_counter("views", 5).unwrap();
     ///
-    /// // age is now gone
-    /// assert_eq!(client.get_int("user.age").unwrap(), None);
+    /// assert_eq!(client.get_counter("views").unwrap(), Some(6));
     /// ```
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The path is invalid or empty
-    /// - The parent path doesn't exist
-    pub fn delete(&mut self, path: &str) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    pub fn inc_counter(&mut self, path: &str, delta: i64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
@@ -608,7 +3743,7 @@ impl RedisAutomergeClient {
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
 
-        // First check if the parent path exists (read-only check)
+        // Get parent object (don't create it if it doesn't exist for increment)
         let parent_obj = if parent_path.is_empty() {
             ROOT
         } else {
@@ -618,57 +3753,36 @@ impl RedisAutomergeClient {
             }
         };
 
-        // Now create a transaction and delete
         let mut tx = self.doc.transaction();
 
-        // Delete the field from the parent
+        // Increment the counter
         match &field_name[0] {
             PathSegment::Key(key) => {
-                tx.delete(&parent_obj, key.as_str())?;
+                tx.increment(&parent_obj, key.as_str(), delta)?;
             }
             PathSegment::Index(idx) => {
-                tx.delete(&parent_obj, *idx)?;
+                tx.increment(&parent_obj, *idx, delta)?;
             }
         }
 
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Delete a value and return the raw change bytes.
-    ///
-    /// Like `delete()` but returns Automerge change bytes that can
-    /// be published to other clients for real-time synchronization.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path to the field to delete
-    ///
-    /// # Returns
-    ///
-    /// - `Some(Vec<u8>)` - Raw change bytes if a change was generated
-    /// - `None` - If no change was needed
-    ///
-    /// # Examples
-    ///
-    /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
-    ///
-    /// let mut client = RedisAutomergeClient::new();
-    /// client.put_text("field", "value").unwrap();
-    /// let change = client.delete_with_change("field").unwrap();
-    ///
-    /// if let Some(change_bytes) = change {
-    ///     // Publish to other clients
-    /// }
-    /// ```
-    pub fn delete_with_change(&mut self, path: &str) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Increment a counter and return the raw change bytes.
+    pub fn inc_counter_with_change(
+        &mut self,
+        path: &str,
+        delta: i64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
@@ -676,7 +3790,7 @@ impl RedisAutomergeClient {
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
 
-        // First check if the parent path exists (read-only check)
+        // Get parent object (don't create it if it doesn't exist for increment)
         let parent_obj = if parent_path.is_empty() {
             ROOT
         } else {
@@ -686,117 +3800,211 @@ impl RedisAutomergeClient {
             }
         };
 
-        // Now create a transaction and delete
         let mut tx = self.doc.transaction();
 
-        // Delete the field from the parent
+        // Increment the counter
         match &field_name[0] {
             PathSegment::Key(key) => {
-                tx.delete(&parent_obj, key.as_str())?;
+                tx.increment(&parent_obj, key.as_str(), delta)?;
             }
             PathSegment::Index(idx) => {
-                tx.delete(&parent_obj, *idx)?;
+                tx.increment(&parent_obj, *idx, delta)?;
             }
         }
 
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Insert an integer value using a path (e.g., "user.age", "users[0].age", or "$.user.age").
-    /// Creates intermediate maps as needed. Array indices must already exist.
-    pub fn put_int(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-        let mut tx = self.doc.transaction();
-
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
-        }
-
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
-
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
-        let (hash, _patch) = tx.commit();
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
-            }
-        }
-        Ok(())
+    /// Add `delta` to an integer value, treating a missing field as `0`.
+    ///
+    /// Unlike `inc_counter`, this works on plain `Int` fields written with
+    /// `put_int`: the current value is read and the sum written back in one
+    /// change. Concurrent increments from different peers are therefore
+    /// last-writer-wins rather than added up; use a counter when increments
+    /// must be additive. Intermediate maps are created as needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_int("stock", 10).unwrap();
+    /// let stock = client.inc_int("stock", -3).unwrap();
+    ///
+    /// assert_eq!(stock, 7);
+    /// assert_eq!(client.get_int("stock").unwrap(), Some(7));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty, the field holds something other
+    /// than an integer (including a counter), or the result would overflow.
+    /// Nothing is written then.
+    pub fn inc_int(&mut self, path: &str, delta: i64) -> Result<i64, IncrementError> {
+        self.inc_int_with_change(path, delta, CommitOptions::default())
+            .map(|(value, _)| value)
     }
 
-    /// Retrieve an integer value using a path (e.g., "user.age", "users[0].age", or "$.user.age").
-    pub fn get_int(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
-        let segments = parse_path(path)?;
-
+    /// Add `delta` to an integer value and return the new value with the raw
+    /// change bytes.
+    pub fn inc_int_with_change(
+        &mut self,
+        path: &str,
+        delta: i64,
+        options: CommitOptions,
+    ) -> Result<(i64, Option<CommittedChange>), IncrementError> {
+        let segments = parse_path(&self.doc, path)?;
+
         if segments.is_empty() {
-            return Ok(None);
+            return Err(AutomergeError::Fail.into());
         }
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+
+        // Read the current value before creating the transaction
         let parent_obj = if parent_path.is_empty() {
-            ROOT
+            Some(ROOT)
         } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
+            navigate_path_read(&self.doc, parent_path)?
+        };
+        let existing = match &parent_obj {
+            Some(obj) => get_value_from_parent(&self.doc, obj, &field_name[0])?,
+            None => None,
         };
+        let current = match existing {
+            None => 0,
+            Some((Value::Scalar(s), _)) => match s.as_ref() {
+                ScalarValue::Int(value) => *value,
+                ScalarValue::Counter(_) => return Err(IncrementError::Counter),
+                _ => return Err(IncrementError::NotAnInteger),
+            },
+            Some(_) => return Err(IncrementError::NotAnInteger),
+        };
+        let value = current.checked_add(delta).ok_or(IncrementError::Overflow)?;
 
-        if let Some((Value::Scalar(s), _)) =
-            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
-        {
-            if let ScalarValue::Int(i) = s.as_ref() {
-                return Ok(Some(*i));
+        let mut tx = self.doc.transaction();
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok((value, Some((h, change_bytes))));
             }
         }
-        Ok(None)
+
+        Ok((value, None))
     }
 
-    /// Insert an integer value and return the raw change bytes.
-    pub fn put_int_with_change(
+    /// Add `delta` to a double value, treating a missing field as `0.0`.
+    ///
+    /// The current value is read and the sum written back in one change.
+    /// Doubles are not CRDT counters: concurrent increments from different
+    /// peers are resolved last-writer-wins rather than added up. Use
+    /// `inc_counter` when increments must be additive. Intermediate maps are
+    /// created as needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.inc_double("stats.average", 1.5).unwrap();
+    /// let average = client.inc_double("stats.average", 0.25).unwrap();
+    ///
+    /// assert_eq!(average, 1.75);
+    /// assert_eq!(client.get_double("stats.average").unwrap(), Some(1.75));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty, the field holds something other
+    /// than a double, or the sum is NaN or infinite. Nothing is written then.
+    pub fn inc_double(&mut self, path: &str, delta: f64) -> Result<f64, IncrementError> {
+        self.inc_double_with_change(path, delta, CommitOptions::default())
+            .map(|(value, _)| value)
+    }
+
+    /// Add `delta` to a double value and return the new value with the raw
+    /// change bytes.
+    pub fn inc_double_with_change(
         &mut self,
         path: &str,
-        value: i64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        delta: f64,
+        options: CommitOptions,
+    ) -> Result<(f64, Option<CommittedChange>), IncrementError> {
+        let segments = parse_path(&self.doc, path)?;
         let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
-            return Err(AutomergeError::Fail);
+            return Err(AutomergeError::Fail.into());
         }
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
 
+        let current = match get_value_from_parent(&tx, &parent_obj, &field_name[0])? {
+            None => 0.0,
+            Some((Value::Scalar(s), _)) => match s.as_ref() {
+                ScalarValue::F64(value) => *value,
+                _ => return Err(IncrementError::NotADouble),
+            },
+            Some(_) => return Err(IncrementError::NotADouble),
+        };
+        let value = current + delta;
+        if !value.is_finite() {
+            return Err(IncrementError::NotFinite);
+        }
         put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok((value, Some((h, change_bytes))));
             }
         }
 
-        Ok(None)
+        Ok((value, None))
     }
 
-    /// Insert a double value using a path (e.g., "metrics.temperature", "temps[0]", or "$.metrics.temperature").
-    /// Creates intermediate maps as needed. Array indices must already exist.
-    pub fn put_double(&mut self, path: &str, value: f64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Insert a timestamp value using a path (e.g., "event.created_at", "timestamps[0]", or "$.event.timestamp").
+    /// Missing parents are created as described under [path syntax](crate::ext#path-syntax).
+    ///
+    /// Timestamps are stored as i64 values representing milliseconds since Unix epoch (UTC).
+    /// They will be rendered as ISO 8601 UTC datetime strings when exported to JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the field
+    /// * `value` - Unix timestamp in milliseconds
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// // Set timestamp to 2024-01-01 00:00:00 UTC (1704067200000 milliseconds)
+    /// client.put_timestamp("created_at", 1704067200000).unwrap();
+    /// ```
+    pub fn put_timestamp(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
         let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
@@ -806,23 +4014,34 @@ impl RedisAutomergeClient {
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
 
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
+        // Put timestamp value
+        match &field_name[0] {
+            PathSegment::Key(key) => {
+                tx.put(&parent_obj, key.as_str(), ScalarValue::Timestamp(value))?;
+            }
+            PathSegment::Index(idx) => {
+                tx.put(&parent_obj, *idx, ScalarValue::Timestamp(value))?;
+            }
+        }
+
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Insert a double value and return the raw change bytes.
-    pub fn put_double_with_change(
+    /// Insert a timestamp value and return the raw change bytes.
+    pub fn put_timestamp_with_change(
         &mut self,
         path: &str,
-        value: f64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        value: i64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
         let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
@@ -832,23 +4051,33 @@ impl RedisAutomergeClient {
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
 
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
-        let (hash, _patch) = tx.commit();
+        // Put timestamp value
+        match &field_name[0] {
+            PathSegment::Key(key) => {
+                tx.put(&parent_obj, key.as_str(), ScalarValue::Timestamp(value))?;
+            }
+            PathSegment::Index(idx) => {
+                tx.put(&parent_obj, *idx, ScalarValue::Timestamp(value))?;
+            }
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Retrieve a double value using a path (e.g., "metrics.temperature", "temps[0]", or "$.metrics.temperature").
-    pub fn get_double(&self, path: &str) -> Result<Option<f64>, AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Retrieve a timestamp value using a path (e.g., "event.created_at", "timestamps[0]", or "$.event.timestamp").
+    /// Returns the timestamp as an i64 (milliseconds since Unix epoch).
+    pub fn get_timestamp(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Ok(None);
@@ -867,101 +4096,197 @@ impl RedisAutomergeClient {
         if let Some((Value::Scalar(s), _)) =
             get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
         {
-            if let ScalarValue::F64(f) = s.as_ref() {
-                return Ok(Some(*f));
+            if let ScalarValue::Timestamp(ts) = s.as_ref() {
+                return Ok(Some(*ts));
             }
         }
         Ok(None)
     }
 
-    /// Insert a boolean value using a path (e.g., "flags.active", "flags\[0\]", or "$.flags.active").
-    /// Creates intermediate maps as needed. Array indices must already exist.
-    pub fn put_bool(&mut self, path: &str, value: bool) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-        let mut tx = self.doc.transaction();
+    /// Apply a unified diff to update text value at the specified path.
+    ///
+    /// This is more efficient than replacing entire text values when only small
+    /// portions change. Each changed region of the diff becomes a `splice_text`
+    /// at its character offset, so untouched lines keep their identity and
+    /// concurrent edits to them merge cleanly. A plain string value is
+    /// converted to a Text object in the same change.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the text field
+    /// * `diff` - Unified diff in git format
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("doc", "Hello World").unwrap();
+    ///
+    /// let diff = r#"--- a/doc
+    /// +++ b/doc
+    /// @@ -1 +1 @@
+    /// -Hello World
+    /// +Hello Rust
+    /// "#;
+    /// client.put_diff("doc", diff).unwrap();
+    ///
+    /// assert_eq!(client.get_text("doc").unwrap(), Some("Hello Rust".to_string()));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The path is invalid or doesn't exist
+    /// - The value at path is not text
+    /// - The diff cannot be parsed
+    /// - The diff cannot be applied to the current text
+    pub fn put_diff(&mut self, path: &str, diff: &str) -> Result<(), AutomergeError> {
+        self.put_diff_with_change(path, diff, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Apply a unified diff and return the raw change bytes.
+    pub fn put_diff_with_change(
+        &mut self,
+        path: &str,
+        diff: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
         }
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+        // Get current text
+        let current_text = self.get_text(path)?.ok_or(AutomergeError::Fail)?;
+        let current_lines: Vec<&str> = current_text.lines().collect();
 
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
-        let (hash, _patch) = tx.commit();
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
-            }
+        // Parse the diff and work out which line ranges change
+        let ops = parse_unified_diff(diff)?;
+        let (edits, _mismatch) = diff_line_edits(&current_lines, &ops)?;
+        if edits.is_empty() {
+            return Ok(None);
         }
-        Ok(())
-    }
-
-    /// Retrieve a boolean value using a path (e.g., "flags.active", "flags\[0\]", or "$.flags.active").
-    pub fn get_bool(&self, path: &str) -> Result<Option<bool>, AutomergeError> {
-        let segments = parse_path(path)?;
 
-        if segments.is_empty() {
-            return Ok(None);
+        // Char offset of every line, plus the total length
+        let mut line_starts = vec![0];
+        for line in current_text.split_inclusive('\n') {
+            let last = line_starts[line_starts.len() - 1];
+            line_starts.push(last + line.chars().count());
         }
+        let trailing_newline = current_text.ends_with('\n');
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = if parent_path.is_empty() {
             ROOT
         } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
+            navigate_path_read(&self.doc, parent_path)?.ok_or(AutomergeError::Fail)?
+        };
+        let existing_obj = match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
+            Some((Value::Object(automerge::ObjType::Text), obj_id)) => Some(obj_id),
+            _ => None,
         };
 
-        if let Some((Value::Scalar(s), _)) =
-            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
-        {
-            if let ScalarValue::Boolean(b) = s.as_ref() {
-                return Ok(Some(*b));
-            }
-        }
-        Ok(None)
-    }
-
-    /// Insert a boolean value and return the raw change bytes.
-    pub fn put_bool_with_change(
-        &mut self,
-        path: &str,
-        value: bool,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
         let mut tx = self.doc.transaction();
+        let text_obj = match existing_obj {
+            Some(obj_id) => obj_id,
+            None => {
+                // Convert scalar string to Text object so the edits can be splices
+                let text_obj = match &field_name[0] {
+                    PathSegment::Key(key) => {
+                        tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::Text)?
+                    }
+                    PathSegment::Index(idx) => {
+                        tx.put_object(&parent_obj, *idx, automerge::ObjType::Text)?
+                    }
+                };
+                tx.splice_text(&text_obj, 0, 0, &current_text)?;
+                text_obj
+            }
+        };
 
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
+        // Splice only the changed regions, last first so earlier offsets stay valid
+        for edit in edits.iter().rev() {
+            let (pos, del, insert) = line_edit_splice(&line_starts, trailing_newline, edit);
+            tx.splice_text(&text_obj, pos, del as isize, &insert)?;
         }
-
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
-
-        put_value_to_parent(&mut tx, &parent_obj, &field_name[0], value)?;
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Insert a counter value using a path (e.g., "stats.views", "counters[0]", or "$.stats.views").
-    /// Creates intermediate maps as needed. Array indices must already exist.
+    /// Check a unified diff against the current text without applying it.
     ///
-    /// Counters are CRDT values that support increment operations with proper
-    /// conflict resolution across distributed systems.
-    pub fn put_counter(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    /// `put_diff()` is lenient: context lines that don't match are kept and
+    /// deletions that don't match are skipped. Call this first to detect a
+    /// stale diff instead; it returns the first context or deletion line that
+    /// doesn't match the text, or `None` if the diff applies cleanly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("doc", "Hello World\n").unwrap();
+    ///
+    /// let diff = "@@ -1 +1 @@\n-Hello Rust\n+Hello Valkey\n";
+    /// let mismatch = client.diff_mismatch("doc", diff).unwrap().unwrap();
+    /// assert_eq!(mismatch.line, 1);
+    /// assert_eq!(mismatch.found.as_deref(), Some("Hello World"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path doesn't hold text or the diff can't be parsed.
+    pub fn diff_mismatch(
+        &self,
+        path: &str,
+        diff: &str,
+    ) -> Result<Option<DiffMismatch>, AutomergeError> {
+        let current_text = self.get_text(path)?.ok_or(AutomergeError::Fail)?;
+        let current_lines: Vec<&str> = current_text.lines().collect();
+        let ops = parse_unified_diff(diff)?;
+        let (_edits, mismatch) = diff_line_edits(&current_lines, &ops)?;
+        Ok(mismatch)
+    }
+
+    /// Creates a new empty list at the specified path.
+    ///
+    /// Creates intermediate maps and lists as needed. The final segment must be a map key.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path where the list should be created
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("users").unwrap();
+    /// client.create_list("data.items").unwrap();
+    ///
+    /// assert_eq!(client.list_len("users").unwrap(), Some(0));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or the final segment is an array index.
+    pub fn create_list(&mut self, path: &str) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
         let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
@@ -971,32 +4296,32 @@ impl RedisAutomergeClient {
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
 
-        // Put counter value
         match &field_name[0] {
             PathSegment::Key(key) => {
-                tx.put(&parent_obj, key.as_str(), ScalarValue::Counter(value.into()))?;
+                tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::List)?;
             }
-            PathSegment::Index(idx) => {
-                tx.put(&parent_obj, *idx, ScalarValue::Counter(value.into()))?;
+            PathSegment::Index(_) => {
+                return Err(AutomergeError::Fail); // Cannot create list at index
             }
         }
 
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Insert a counter value and return the raw change bytes.
-    pub fn put_counter_with_change(
+    /// Create a new empty list and return the raw change bytes.
+    pub fn create_list_with_change(
         &mut self,
         path: &str,
-        value: i64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
         let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
@@ -1006,281 +4331,37 @@ impl RedisAutomergeClient {
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
         let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
 
-        // Put counter value
         match &field_name[0] {
             PathSegment::Key(key) => {
-                tx.put(&parent_obj, key.as_str(), ScalarValue::Counter(value.into()))?;
+                tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::List)?;
             }
-            PathSegment::Index(idx) => {
-                tx.put(&parent_obj, *idx, ScalarValue::Counter(value.into()))?;
+            PathSegment::Index(_) => {
+                return Err(AutomergeError::Fail); // Cannot create list at index
             }
         }
 
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
-            }
-        }
-
-        Ok(None)
-    }
-
-    /// Retrieve a counter value using a path (e.g., "stats.views", "counters[0]", or "$.stats.views").
-    /// Returns the current counter value as an i64.
-    pub fn get_counter(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Ok(None);
-        }
-
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = if parent_path.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
-        };
-
-        if let Some((Value::Scalar(s), _)) =
-            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
-        {
-            if let ScalarValue::Counter(c) = s.as_ref() {
-                return Ok(Some(i64::from(c)));
-            }
-        }
-        Ok(None)
-    }
-
-    /// Get a value with type information from the specified path.
-    ///
-    /// This method extracts values preserving their Automerge types, which is
-    /// useful for building JSON index documents with proper type representation.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path to the value
-    ///
-    /// # Returns
-    ///
-    /// Returns `Some(TypedValue)` if the path exists, `None` otherwise.
-    pub fn get_typed_value(&self, path: &str) -> Result<Option<TypedValue>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Ok(None);
-        }
-
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = if parent_path.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
-        };
-
-        match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
-            Some((Value::Scalar(s), _)) => {
-                let typed_val = match s.as_ref() {
-                    ScalarValue::Str(text) => TypedValue::Text(text.to_string()),
-                    ScalarValue::Int(i) => TypedValue::Int(*i),
-                    ScalarValue::F64(f) => TypedValue::Double(*f),
-                    ScalarValue::Boolean(b) => TypedValue::Bool(*b),
-                    ScalarValue::Timestamp(ts) => TypedValue::Timestamp(*ts),
-                    ScalarValue::Counter(c) => TypedValue::Counter(i64::from(c)),
-                    ScalarValue::Null => TypedValue::Null,
-                    _ => TypedValue::Null,
-                };
-                Ok(Some(typed_val))
-            }
-            Some((Value::Object(obj_type), obj_id)) => {
-                // Handle Text objects
-                if obj_type == automerge::ObjType::Text {
-                    let text = self.doc.text(&obj_id)?;
-                    return Ok(Some(TypedValue::Text(text)));
-                }
-
-                // Handle List objects - return as Array
-                if obj_type == automerge::ObjType::List {
-                    let mut arr = Vec::new();
-                    let len = self.doc.length(&obj_id);
-                    for i in 0..len {
-                        if let Some((value, value_obj_id)) = self.doc.get(&obj_id, i)? {
-                            if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
-                                arr.push(typed_val);
-                            }
-                        }
-                    }
-                    return Ok(Some(TypedValue::Array(arr)));
-                }
-
-                // Handle Map objects
-                if obj_type == automerge::ObjType::Map {
-                    let mut map = std::collections::HashMap::new();
-                    for key in self.doc.keys(&obj_id) {
-                        if let Some((value, value_obj_id)) = self.doc.get(&obj_id, &key)? {
-                            if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
-                                map.insert(key, typed_val);
-                            }
-                        }
-                    }
-                    return Ok(Some(TypedValue::Object(map)));
-                }
-
-                Ok(None)
-            }
-            None => Ok(None),
-        }
-    }
-
-    /// Helper method to convert Automerge Value to TypedValue
-    fn value_to_typed(
-        &self,
-        value: &Value,
-        obj_id: &ObjId,
-    ) -> Result<Option<TypedValue>, AutomergeError> {
-        match value {
-            Value::Scalar(s) => {
-                let typed_val = match s.as_ref() {
-                    ScalarValue::Str(text) => TypedValue::Text(text.to_string()),
-                    ScalarValue::Int(i) => TypedValue::Int(*i),
-                    ScalarValue::F64(f) => TypedValue::Double(*f),
-                    ScalarValue::Boolean(b) => TypedValue::Bool(*b),
-                    ScalarValue::Timestamp(ts) => TypedValue::Timestamp(*ts),
-                    ScalarValue::Counter(c) => TypedValue::Counter(i64::from(c)),
-                    ScalarValue::Null => TypedValue::Null,
-                    _ => TypedValue::Null,
-                };
-                Ok(Some(typed_val))
-            }
-            Value::Object(obj_type) => {
-                // Handle Text objects
-                if *obj_type == automerge::ObjType::Text {
-                    let text = self.doc.text(obj_id)?;
-                    return Ok(Some(TypedValue::Text(text)));
-                }
-
-                // Handle List objects
-                if *obj_type == automerge::ObjType::List {
-                    let mut arr = Vec::new();
-                    let len = self.doc.length(obj_id);
-                    for i in 0..len {
-                        if let Some((val, val_obj_id)) = self.doc.get(obj_id, i)? {
-                            if let Some(typed_val) = self.value_to_typed(&val, &val_obj_id)? {
-                                arr.push(typed_val);
-                            }
-                        }
-                    }
-                    return Ok(Some(TypedValue::Array(arr)));
-                }
-
-                // Handle Map objects
-                if *obj_type == automerge::ObjType::Map {
-                    let mut map = std::collections::HashMap::new();
-                    for key in self.doc.keys(obj_id) {
-                        if let Some((val, val_obj_id)) = self.doc.get(obj_id, &key)? {
-                            if let Some(typed_val) = self.value_to_typed(&val, &val_obj_id)? {
-                                map.insert(key, typed_val);
-                            }
-                        }
-                    }
-                    return Ok(Some(TypedValue::Object(map)));
-                }
-
-                Ok(None)
-            }
-        }
-    }
-
-    /// Get all values from a list at the specified path.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path to the list
-    ///
-    /// # Returns
-    ///
-    /// Returns `Some(Vec<TypedValue>)` if the path points to a list, `None` otherwise.
-    pub fn get_list_values(&self, path: &str) -> Result<Option<Vec<TypedValue>>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        let list_obj = if segments.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, &segments)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
-        };
-
-        // Check if it's a list
-        let obj_type = self.doc.object_type(&list_obj)?;
-        if obj_type == automerge::ObjType::List {
-            let mut values = Vec::new();
-            let len = self.doc.length(&list_obj);
-
-            for i in 0..len {
-                if let Some((value, value_obj_id)) = self.doc.get(&list_obj, i)? {
-                    if let Some(typed_val) = self.value_to_typed(&value, &value_obj_id)? {
-                        values.push(typed_val);
-                    }
-                }
-            }
-
-            return Ok(Some(values));
-        }
-
-        Ok(None)
-    }
-
-    /// Get all keys from a map at the specified path.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path to the map
-    ///
-    /// # Returns
-    ///
-    /// Returns `Some(Vec<String>)` if the path points to a map, `None` otherwise.
-    pub fn get_map_keys(&self, path: &str) -> Result<Option<Vec<String>>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        let map_obj = if segments.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, &segments)? {
-                Some(obj) => obj,
-                None => return Ok(None),
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
-        };
-
-        // Check if it's a map
-        let obj_type = self.doc.object_type(&map_obj)?;
-        if obj_type == automerge::ObjType::Map {
-            let keys: Vec<String> = self.doc.keys(&map_obj).collect();
-            return Ok(Some(keys));
         }
 
         Ok(None)
     }
 
-    /// Increment a counter at the specified path by the given delta.
+    /// Creates a new empty map at the specified path.
     ///
-    /// This uses Automerge's CRDT counter increment operation, which properly
-    /// merges concurrent increments from different replicas.
+    /// Creates intermediate maps and lists as needed. The final segment must be a map key, and
+    /// an existing value there is replaced. Useful to tell an intentionally empty map
+    /// apart from a missing one.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the counter field
-    /// * `delta` - Amount to increment (can be negative to decrement)
+    /// * `path` - Path where the map should be created
     ///
     /// # Examples
     ///
@@ -1288,111 +4369,147 @@ impl RedisAutomergeClient {
     /// use redis_automerge::ext::RedisAutomergeClient;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.put_counter("views", 0).unwrap();
-    /// client.inc_counter("views", 1).unwrap();
-    /// client.inc_counter("views", 5).unwrap();
+    /// client.create_map("settings").unwrap();
     ///
-    /// assert_eq!(client.get_counter("views").unwrap(), Some(6));
+    /// assert_eq!(client.map_len("settings").unwrap(), Some(0));
+    /// assert_eq!(client.get_type("settings").unwrap(), Some("map"));
     /// ```
-    pub fn inc_counter(&mut self, path: &str, delta: i64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty, the final segment is an array index, or
+    /// the parent of the final segment is not a map.
+    pub fn create_map(&mut self, path: &str) -> Result<(), AutomergeError> {
+        self.create_map_with_change(path, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Create a new empty map and return the raw change bytes.
+    pub fn create_map_with_change(
+        &mut self,
+        path: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
         }
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+        if tx.object_type(&parent_obj)? != automerge::ObjType::Map {
+            return Err(AutomergeError::Fail); // Map keys only exist in maps
+        }
 
-        // Get parent object (don't create it if it doesn't exist for increment)
-        let parent_obj = if parent_path.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Err(AutomergeError::Fail),
-            }
-        };
-
-        let mut tx = self.doc.transaction();
-
-        // Increment the counter
         match &field_name[0] {
             PathSegment::Key(key) => {
-                tx.increment(&parent_obj, key.as_str(), delta)?;
+                tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::Map)?;
             }
-            PathSegment::Index(idx) => {
-                tx.increment(&parent_obj, *idx, delta)?;
+            PathSegment::Index(_) => {
+                return Err(AutomergeError::Fail); // Cannot create map at index
             }
         }
 
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
+
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
-        Ok(())
+
+        Ok(None)
     }
 
-    /// Increment a counter and return the raw change bytes.
-    pub fn inc_counter_with_change(
+    /// Creates a new `Text` object at the specified path, optionally with initial content.
+    ///
+    /// Unlike [`put_text`](Self::put_text), which stores a plain string scalar, a `Text`
+    /// object supports splicing and marks from the start. Creates intermediate maps and
+    /// lists as needed, and replaces any existing value at the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path where the text should be created
+    /// * `initial` - Initial content, may be empty
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_text("body", "Hello").unwrap();
+    ///
+    /// assert_eq!(client.get_type("body").unwrap(), Some("text"));
+    /// assert_eq!(client.get_text("body").unwrap(), Some("Hello".to_string()));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty, the final segment is an array index, or
+    /// the parent of the final segment is not a map.
+    pub fn create_text(&mut self, path: &str, initial: &str) -> Result<(), AutomergeError> {
+        self.create_text_with_change(path, initial, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Create a new `Text` object and return the raw change bytes.
+    pub fn create_text_with_change(
         &mut self,
         path: &str,
-        delta: i64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        initial: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        let mut tx = self.doc.transaction();
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
         }
 
         let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+        if tx.object_type(&parent_obj)? != automerge::ObjType::Map {
+            return Err(AutomergeError::Fail); // Map keys only exist in maps
+        }
 
-        // Get parent object (don't create it if it doesn't exist for increment)
-        let parent_obj = if parent_path.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Err(AutomergeError::Fail),
-            }
-        };
-
-        let mut tx = self.doc.transaction();
-
-        // Increment the counter
-        match &field_name[0] {
+        let text_obj = match &field_name[0] {
             PathSegment::Key(key) => {
-                tx.increment(&parent_obj, key.as_str(), delta)?;
+                tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::Text)?
             }
-            PathSegment::Index(idx) => {
-                tx.increment(&parent_obj, *idx, delta)?;
+            PathSegment::Index(_) => {
+                return Err(AutomergeError::Fail); // Cannot create text at index
             }
+        };
+        if !initial.is_empty() {
+            tx.splice_text(&text_obj, 0, 0, initial)?;
         }
 
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Insert a timestamp value using a path (e.g., "event.created_at", "timestamps[0]", or "$.event.timestamp").
-    /// Creates intermediate maps as needed. Array indices must already exist.
+    /// Appends a text value to the end of a list at the specified path.
     ///
-    /// Timestamps are stored as i64 values representing milliseconds since Unix epoch (UTC).
-    /// They will be rendered as ISO 8601 UTC datetime strings when exported to JSON.
+    /// The list must already exist at the given path.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the field
-    /// * `value` - Unix timestamp in milliseconds
+    /// * `path` - Path to the list
+    /// * `value` - Text value to append
     ///
     /// # Examples
     ///
@@ -1400,454 +4517,576 @@ impl RedisAutomergeClient {
     /// use redis_automerge::ext::RedisAutomergeClient;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// // Set timestamp to 2024-01-01 00:00:00 UTC (1704067200000 milliseconds)
-    /// client.put_timestamp("created_at", 1704067200000).unwrap();
+    /// client.create_list("users").unwrap();
+    /// client.append_text("users", "Alice").unwrap();
+    /// client.append_text("users", "Bob").unwrap();
+    ///
+    /// assert_eq!(client.get_text("users[0]").unwrap(), Some("Alice".to_string()));
+    /// assert_eq!(client.list_len("users").unwrap(), Some(2));
     /// ```
-    pub fn put_timestamp(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-        let mut tx = self.doc.transaction();
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path doesn't exist or doesn't point to a list.
+    pub fn append_text(&mut self, path: &str, value: &str) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        insert_text_to_list(&mut tx, &list_obj, list_len, value, self.text_as_object)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
         }
+        Ok(())
+    }
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+    /// Append a text value to a list and return the raw change bytes.
+    pub fn append_text_with_change(
+        &mut self,
+        path: &str,
+        value: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        // Put timestamp value
-        match &field_name[0] {
-            PathSegment::Key(key) => {
-                tx.put(&parent_obj, key.as_str(), ScalarValue::Timestamp(value))?;
-            }
-            PathSegment::Index(idx) => {
-                tx.put(&parent_obj, *idx, ScalarValue::Timestamp(value))?;
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        insert_text_to_list(&mut tx, &list_obj, list_len, value, self.text_as_object)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
+        Ok(None)
+    }
+
+    /// Append an integer value to a list at the specified path.
+    pub fn append_int(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, value)?;
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Insert a timestamp value and return the raw change bytes.
-    pub fn put_timestamp_with_change(
+    /// Append an integer value to a list and return the raw change bytes.
+    pub fn append_int_with_change(
         &mut self,
         path: &str,
         value: i64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
         let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, value)?;
+        let (hash, _patch) = tx.commit_with(options);
 
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
         }
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
+        Ok(None)
+    }
 
-        // Put timestamp value
-        match &field_name[0] {
-            PathSegment::Key(key) => {
-                tx.put(&parent_obj, key.as_str(), ScalarValue::Timestamp(value))?;
+    /// Append a double value to a list at the specified path.
+    pub fn append_double(&mut self, path: &str, value: f64) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, value)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
-            PathSegment::Index(idx) => {
-                tx.put(&parent_obj, *idx, ScalarValue::Timestamp(value))?;
+        }
+        Ok(())
+    }
+
+    /// Append a double value to a list and return the raw change bytes.
+    pub fn append_double_with_change(
+        &mut self,
+        path: &str,
+        value: f64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, value)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
+        Ok(None)
+    }
+
+    /// Append a boolean value to a list at the specified path.
+    pub fn append_bool(&mut self, path: &str, value: bool) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, value)?;
         let (hash, _patch) = tx.commit();
-
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes);
             }
         }
-
-        Ok(None)
+        Ok(())
     }
 
-    /// Retrieve a timestamp value using a path (e.g., "event.created_at", "timestamps[0]", or "$.event.timestamp").
-    /// Returns the timestamp as an i64 (milliseconds since Unix epoch).
-    pub fn get_timestamp(&self, path: &str) -> Result<Option<i64>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Ok(None);
-        }
+    /// Append a boolean value to a list and return the raw change bytes.
+    pub fn append_bool_with_change(
+        &mut self,
+        path: &str,
+        value: bool,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = if parent_path.is_empty() {
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
             ROOT
         } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
         };
 
-        if let Some((Value::Scalar(s), _)) =
-            get_value_from_parent(&self.doc, &parent_obj, &field_name[0])?
-        {
-            if let ScalarValue::Timestamp(ts) = s.as_ref() {
-                return Ok(Some(*ts));
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, value)?;
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
+
         Ok(None)
     }
 
-    /// Apply a unified diff to update text value at the specified path.
-    ///
-    /// This is more efficient than replacing entire text values when only small
-    /// portions change. The diff is parsed and applied using Automerge's text
-    /// operations (splice_text) to preserve CRDT properties.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path to the text field
-    /// * `diff` - Unified diff in git format
-    ///
-    /// # Examples
-    ///
-    /// ```rust,no_run
-    /// use redis_automerge::ext::RedisAutomergeClient;
-    ///
-    /// let mut client = RedisAutomergeClient::new();
-    /// client.put_text("doc", "Hello World").unwrap();
-    ///
-    /// let diff = r#"--- a/doc
-    /// +++ b/doc
-    /// @@ -1 +1 @@
-    /// -Hello World
-    /// +Hello Rust
-    /// "#;
-    /// client.put_diff("doc", diff).unwrap();
-    ///
-    /// assert_eq!(client.get_text("doc").unwrap(), Some("Hello Rust".to_string()));
-    /// ```
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The path is invalid or doesn't exist
-    /// - The value at path is not text
-    /// - The diff cannot be parsed
-    /// - The diff cannot be applied to the current text
-    pub fn put_diff(&mut self, path: &str, diff: &str) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
-        }
-
-        // Get current text
-        let current_text = self.get_text(path)?.ok_or(AutomergeError::Fail)?;
-        let current_lines: Vec<&str> = current_text.lines().collect();
-
-        // Parse the diff
-        let ops = parse_unified_diff(diff)?;
-
-        // Build the new text by applying diff operations
-        let mut new_lines = Vec::new();
-        let mut current_line_idx = 0;
-
-        let mut i = 0;
-        while i < ops.len() {
-            match &ops[i] {
-                DiffOp::Context(line) => {
-                    // Verify context matches (for safety)
-                    if current_line_idx < current_lines.len() {
-                        let current = current_lines[current_line_idx];
-                        if current != line.as_str() {
-                            // Context mismatch - try to be lenient
-                        }
-                        new_lines.push(current.to_string());
-                        current_line_idx += 1;
-                    }
-                }
-                DiffOp::Delete(line) => {
-                    // Skip the deleted line in current text
-                    if current_line_idx < current_lines.len() {
-                        let current = current_lines[current_line_idx];
-                        if current == line.as_str() {
-                            current_line_idx += 1;
-                        }
-                    }
-                }
-                DiffOp::Add(line) => {
-                    // Add the new line
-                    new_lines.push(line.clone());
-                }
-            }
-            i += 1;
-        }
-
-        // Add any remaining lines
-        while current_line_idx < current_lines.len() {
-            new_lines.push(current_lines[current_line_idx].to_string());
-            current_line_idx += 1;
-        }
+    /// Append a null value to a list at the specified path.
+    pub fn append_null(&mut self, path: &str) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        // Reconstruct text with newlines
-        let new_text = if current_text.ends_with('\n') {
-            new_lines.join("\n") + "\n"
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
         } else {
-            new_lines.join("\n")
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
         };
 
-        // Apply the change using put_text
-        self.put_text(path, &new_text)?;
-
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, ScalarValue::Null)?;
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
+            }
+        }
         Ok(())
     }
 
-    /// Apply a unified diff and return the raw change bytes.
-    pub fn put_diff_with_change(
+    /// Append a null value to a list and return the raw change bytes.
+    pub fn append_null_with_change(
         &mut self,
         path: &str,
-        diff: &str,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
-        }
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        // Get current text
-        let current_text = self.get_text(path)?.ok_or(AutomergeError::Fail)?;
-        let current_lines: Vec<&str> = current_text.lines().collect();
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
 
-        // Parse the diff
-        let ops = parse_unified_diff(diff)?;
+        let list_len = self.doc.length(&list_obj);
+        let mut tx = self.doc.transaction();
+        tx.insert(&list_obj, list_len, ScalarValue::Null)?;
+        let (hash, _patch) = tx.commit_with(options);
 
-        // Build the new text by applying diff operations
-        let mut new_lines = Vec::new();
-        let mut current_line_idx = 0;
-
-        let mut i = 0;
-        while i < ops.len() {
-            match &ops[i] {
-                DiffOp::Context(line) => {
-                    // Verify context matches (for safety)
-                    if current_line_idx < current_lines.len() {
-                        let current = current_lines[current_line_idx];
-                        if current != line.as_str() {
-                            // Context mismatch - try to be lenient
-                        }
-                        new_lines.push(current.to_string());
-                        current_line_idx += 1;
-                    }
-                }
-                DiffOp::Delete(line) => {
-                    // Skip the deleted line in current text
-                    if current_line_idx < current_lines.len() {
-                        let current = current_lines[current_line_idx];
-                        if current == line.as_str() {
-                            current_line_idx += 1;
-                        }
-                    }
-                }
-                DiffOp::Add(line) => {
-                    // Add the new line
-                    new_lines.push(line.clone());
-                }
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
-            i += 1;
-        }
-
-        // Add any remaining lines
-        while current_line_idx < current_lines.len() {
-            new_lines.push(current_lines[current_line_idx].to_string());
-            current_line_idx += 1;
         }
 
-        // Reconstruct text with newlines
-        let new_text = if current_text.ends_with('\n') {
-            new_lines.join("\n") + "\n"
-        } else {
-            new_lines.join("\n")
-        };
-
-        // Apply the change using put_text_with_change
-        self.put_text_with_change(path, &new_text)
+        Ok(None)
     }
 
-    /// Creates a new empty list at the specified path.
+    /// Insert a scalar value into a list at the specified index.
     ///
-    /// Creates intermediate maps as needed. The final segment must be a map key.
+    /// Elements at or after `index` are shifted one position to the right.
+    /// An index equal to the current list length behaves like an append.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path where the list should be created
+    /// * `path` - Path to the list
+    /// * `index` - Position to insert at (0 = head)
+    /// * `value` - Scalar value to insert
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// use redis_automerge::ext::RedisAutomergeClient;
+    /// use automerge::ScalarValue;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.create_list("users").unwrap();
-    /// client.create_list("data.items").unwrap();
+    /// client.create_list("items").unwrap();
+    /// client.append_text("items", "second").unwrap();
+    /// client.insert_at("items", 0, ScalarValue::Str("first".into())).unwrap();
     ///
-    /// assert_eq!(client.list_len("users").unwrap(), Some(0));
+    /// assert_eq!(client.get_text("items[0]").unwrap(), Some("first".to_string()));
     /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if the path is empty or the final segment is an array index.
-    pub fn create_list(&mut self, path: &str) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-        let mut tx = self.doc.transaction();
+    /// Returns an error if:
+    /// - The path doesn't exist or doesn't point to a list
+    /// - The index is greater than the list length
+    pub fn insert_at(
+        &mut self,
+        path: &str,
+        index: usize,
+        value: ScalarValue,
+    ) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        if segments.is_empty() {
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        if self.doc.object_type(&list_obj)? != automerge::ObjType::List {
             return Err(AutomergeError::Fail);
         }
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
-
-        match &field_name[0] {
-            PathSegment::Key(key) => {
-                tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::List)?;
-            }
-            PathSegment::Index(_) => {
-                return Err(AutomergeError::Fail); // Cannot create list at index
-            }
+        let list_len = self.doc.length(&list_obj);
+        if index > list_len {
+            return Err(AutomergeError::InvalidIndex(index));
         }
 
+        let mut tx = self.doc.transaction();
+        let segment = PathSegment::Index(index);
+        put_scalar_to_parent(
+            &mut tx,
+            &list_obj,
+            &segment,
+            true,
+            value,
+            self.text_as_object,
+        )?;
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Create a new empty list and return the raw change bytes.
-    pub fn create_list_with_change(
+    /// Insert a scalar value into a list at the specified index and return the raw change bytes.
+    pub fn insert_at_with_change(
         &mut self,
         path: &str,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
-        let mut tx = self.doc.transaction();
+        index: usize,
+        value: ScalarValue,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        if segments.is_empty() {
+        // Navigate before creating transaction
+        let list_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+        };
+
+        if self.doc.object_type(&list_obj)? != automerge::ObjType::List {
             return Err(AutomergeError::Fail);
         }
 
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-        let parent_obj = navigate_or_create_path(&mut tx, parent_path)?;
-
-        match &field_name[0] {
-            PathSegment::Key(key) => {
-                tx.put_object(&parent_obj, key.as_str(), automerge::ObjType::List)?;
-            }
-            PathSegment::Index(_) => {
-                return Err(AutomergeError::Fail); // Cannot create list at index
-            }
+        let list_len = self.doc.length(&list_obj);
+        if index > list_len {
+            return Err(AutomergeError::InvalidIndex(index));
         }
 
-        let (hash, _patch) = tx.commit();
+        let mut tx = self.doc.transaction();
+        let segment = PathSegment::Index(index);
+        put_scalar_to_parent(
+            &mut tx,
+            &list_obj,
+            &segment,
+            true,
+            value,
+            self.text_as_object,
+        )?;
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Appends a text value to the end of a list at the specified path.
+    /// Insert a scalar value at the head of a list.
+    pub fn prepend(&mut self, path: &str, value: ScalarValue) -> Result<(), AutomergeError> {
+        self.prepend_with_change(path, value, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Insert a scalar value at the head of a list and return the raw change bytes.
     ///
-    /// The list must already exist at the given path.
+    /// The list must already exist; an empty list gets `value` as its only element.
+    pub fn prepend_with_change(
+        &mut self,
+        path: &str,
+        value: ScalarValue,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        self.insert_at_with_change(path, 0, value, options)
+    }
+
+    /// Replace a range of a list with new scalar values in a single change.
+    ///
+    /// Removes `del` elements starting at `pos` and inserts `values` in their
+    /// place. Because the whole edit is one transaction, peers receive a
+    /// single change instead of one per element.
     ///
     /// # Arguments
     ///
     /// * `path` - Path to the list
-    /// * `value` - Text value to append
+    /// * `pos` - Position where the splice starts
+    /// * `del` - Number of elements to remove
+    /// * `values` - Scalar values to insert at `pos`
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// use redis_automerge::ext::RedisAutomergeClient;
+    /// use automerge::ScalarValue;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.create_list("users").unwrap();
-    /// client.append_text("users", "Alice").unwrap();
-    /// client.append_text("users", "Bob").unwrap();
+    /// client.create_list("items").unwrap();
+    /// client.append_text("items", "a").unwrap();
+    /// client.append_text("items", "d").unwrap();
+    /// client
+    ///     .splice_list("items", 1, 0, vec!["b".into(), "c".into()])
+    ///     .unwrap();
     ///
-    /// assert_eq!(client.get_text("users[0]").unwrap(), Some("Alice".to_string()));
-    /// assert_eq!(client.list_len("users").unwrap(), Some(2));
+    /// assert_eq!(client.list_len("items").unwrap(), Some(4));
     /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if the path doesn't exist or doesn't point to a list.
-    pub fn append_text(&mut self, path: &str, value: &str) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-
-        // Navigate before creating transaction
-        let list_obj = if segments.is_empty() {
-            ROOT
-        } else {
-            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
-        };
-
-        let list_len = self.doc.length(&list_obj);
-        let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
-            }
-        }
-        Ok(())
+    /// Returns an error if:
+    /// - The path doesn't exist or doesn't point to a list
+    /// - `pos` is greater than the list length
+    /// - `pos + del` runs past the end of the list
+    pub fn splice_list(
+        &mut self,
+        path: &str,
+        pos: usize,
+        del: usize,
+        values: Vec<ScalarValue>,
+    ) -> Result<(), AutomergeError> {
+        self.splice_list_with_change(path, pos, del, values, CommitOptions::default())
+            .map(|_| ())
     }
 
-    /// Append a text value to a list and return the raw change bytes.
-    pub fn append_text_with_change(
+    /// Replace a range of a list and return the raw change bytes.
+    pub fn splice_list_with_change(
         &mut self,
         path: &str,
-        value: &str,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        pos: usize,
+        del: usize,
+        values: Vec<ScalarValue>,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        // Navigate before creating transaction
         let list_obj = if segments.is_empty() {
             ROOT
         } else {
             navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
         };
 
+        if self.doc.object_type(&list_obj)? != automerge::ObjType::List {
+            return Err(AutomergeError::Fail);
+        }
+
         let list_len = self.doc.length(&list_obj);
+        if pos > list_len {
+            return Err(AutomergeError::InvalidIndex(pos));
+        }
+        if del > list_len - pos {
+            return Err(AutomergeError::InvalidIndex(pos.saturating_add(del)));
+        }
+
+        let del = isize::try_from(del).map_err(|_| AutomergeError::InvalidIndex(pos))?;
         let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
+        tx.splice(&list_obj, pos, del, std::iter::empty::<ScalarValue>())?;
+        for (offset, value) in values.into_iter().enumerate() {
+            let segment = PathSegment::Index(pos + offset);
+            put_scalar_to_parent(
+                &mut tx,
+                &list_obj,
+                &segment,
+                true,
+                value,
+                self.text_as_object,
+            )?;
+        }
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Append an integer value to a list at the specified path.
-    pub fn append_int(&mut self, path: &str, value: i64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Overwrite the list element at the specified index with a scalar value.
+    ///
+    /// Unlike deleting and reinserting, this keeps the element's position in the
+    /// list and leaves the other elements and the list length unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the list
+    /// * `index` - Index of the element to overwrite
+    /// * `value` - New scalar value
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    /// use automerge::ScalarValue;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("items").unwrap();
+    /// client.append_text("items", "old").unwrap();
+    /// client.set_list_element("items", 0, ScalarValue::Str("new".into())).unwrap();
+    ///
+    /// assert_eq!(client.get_text("items[0]").unwrap(), Some("new".to_string()));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The path doesn't exist or doesn't point to a list
+    /// - The index is out of bounds
+    pub fn set_list_element(
+        &mut self,
+        path: &str,
+        index: usize,
+        value: ScalarValue,
+    ) -> Result<(), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         // Navigate before creating transaction
         let list_obj = if segments.is_empty() {
@@ -1856,25 +5095,43 @@ impl RedisAutomergeClient {
             navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
         };
 
-        let list_len = self.doc.length(&list_obj);
+        if self.doc.object_type(&list_obj)? != automerge::ObjType::List {
+            return Err(AutomergeError::Fail);
+        }
+
+        if index >= self.doc.length(&list_obj) {
+            return Err(AutomergeError::InvalidIndex(index));
+        }
+
         let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
+        let segment = PathSegment::Index(index);
+        put_scalar_to_parent(
+            &mut tx,
+            &list_obj,
+            &segment,
+            false,
+            value,
+            self.text_as_object,
+        )?;
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
-    /// Append an integer value to a list and return the raw change bytes.
-    pub fn append_int_with_change(
+    /// Overwrite a list element and return the raw change bytes.
+    pub fn set_list_element_with_change(
         &mut self,
         path: &str,
-        value: i64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        index: usize,
+        value: ScalarValue,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         // Navigate before creating transaction
         let list_obj = if segments.is_empty() {
@@ -1883,137 +5140,378 @@ impl RedisAutomergeClient {
             navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
         };
 
-        let list_len = self.doc.length(&list_obj);
+        if self.doc.object_type(&list_obj)? != automerge::ObjType::List {
+            return Err(AutomergeError::Fail);
+        }
+
+        if index >= self.doc.length(&list_obj) {
+            return Err(AutomergeError::InvalidIndex(index));
+        }
+
         let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
+        let segment = PathSegment::Index(index);
+        put_scalar_to_parent(
+            &mut tx,
+            &list_obj,
+            &segment,
+            false,
+            value,
+            self.text_as_object,
+        )?;
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Append a double value to a list at the specified path.
-    pub fn append_double(&mut self, path: &str, value: f64) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Remove the list element at the specified index.
+    ///
+    /// Negative indices count from the end of the list (`-1` is the last element).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the list
+    /// * `index` - Index of the element to remove
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("items").unwrap();
+    /// client.append_text("items", "first").unwrap();
+    /// client.append_text("items", "last").unwrap();
+    /// client.remove_list_element("items", -1).unwrap();
+    ///
+    /// assert_eq!(client.list_len("items").unwrap(), Some(1));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The path doesn't exist or doesn't point to a list
+    /// - The index is out of bounds
+    pub fn remove_list_element(&mut self, path: &str, index: i64) -> Result<(), AutomergeError> {
+        let element_path = self.list_element_path(path, index)?;
+        self.delete(&element_path)
+    }
 
-        // Navigate before creating transaction
-        let list_obj = if segments.is_empty() {
-            ROOT
-        } else {
-            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
-        };
+    /// Remove a list element and return the raw change bytes.
+    pub fn remove_list_element_with_change(
+        &mut self,
+        path: &str,
+        index: i64,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let element_path = self.list_element_path(path, index)?;
+        self.delete_with_change(&element_path, options)
+    }
 
-        let list_len = self.doc.length(&list_obj);
-        let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
-            }
-        }
-        Ok(())
+    /// Remove the first element of a list and return it.
+    ///
+    /// The element is read and deleted in a single change. Returns `None` if the
+    /// list is empty or the path doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::{RedisAutomergeClient, TypedValue};
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("queue").unwrap();
+    /// client.append_text("queue", "first").unwrap();
+    /// client.append_text("queue", "second").unwrap();
+    ///
+    /// assert_eq!(
+    ///     client.pop_front("queue").unwrap(),
+    ///     Some(TypedValue::Text("first".to_string()))
+    /// );
+    /// assert_eq!(client.list_len("queue").unwrap(), Some(1));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path holds something other than a list.
+    pub fn pop_front(&mut self, path: &str) -> Result<Option<TypedValue>, AutomergeError> {
+        self.pop_front_with_change(path, CommitOptions::default())
+            .map(|(value, _)| value)
     }
 
-    /// Append a double value to a list and return the raw change bytes.
-    pub fn append_double_with_change(
+    /// Remove the first element of a list, returning it and the raw change bytes.
+    pub fn pop_front_with_change(
         &mut self,
         path: &str,
-        value: f64,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        options: CommitOptions,
+    ) -> Result<(Option<TypedValue>, Option<CommittedChange>), AutomergeError> {
+        self.pop_with_change(path, false, options)
+    }
 
-        // Navigate before creating transaction
+    /// Remove the last element of a list and return it.
+    ///
+    /// Like `pop_front()`, but from the other end of the list.
+    pub fn pop_back(&mut self, path: &str) -> Result<Option<TypedValue>, AutomergeError> {
+        self.pop_back_with_change(path, CommitOptions::default())
+            .map(|(value, _)| value)
+    }
+
+    /// Remove the last element of a list, returning it and the raw change bytes.
+    pub fn pop_back_with_change(
+        &mut self,
+        path: &str,
+        options: CommitOptions,
+    ) -> Result<(Option<TypedValue>, Option<CommittedChange>), AutomergeError> {
+        self.pop_with_change(path, true, options)
+    }
+
+    fn pop_with_change(
+        &mut self,
+        path: &str,
+        from_back: bool,
+        options: CommitOptions,
+    ) -> Result<(Option<TypedValue>, Option<CommittedChange>), AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
         let list_obj = if segments.is_empty() {
             ROOT
         } else {
-            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+            match navigate_path_read(&self.doc, &segments)? {
+                Some(obj) => obj,
+                None if self.path_exists(path)? => return Err(AutomergeError::Fail),
+                None => return Ok((None, None)),
+            }
+        };
+        if self.doc.object_type(&list_obj)? != ObjType::List {
+            return Err(AutomergeError::Fail);
+        }
+
+        let len = self.doc.length(&list_obj);
+        if len == 0 {
+            return Ok((None, None));
+        }
+        let index = if from_back { len - 1 } else { 0 };
+        let value = match self.doc.get(&list_obj, index)? {
+            Some((value, obj_id)) => self.value_to_typed(&value, &obj_id)?,
+            None => None,
         };
 
-        let list_len = self.doc.length(&list_obj);
         let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
+        tx.delete(&list_obj, index)?;
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok((value, Some((h, change_bytes))));
             }
         }
 
-        Ok(None)
+        Ok((value, None))
     }
 
-    /// Append a boolean value to a list at the specified path.
-    pub fn append_bool(&mut self, path: &str, value: bool) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Helper to resolve a (possibly negative) list index into an element path,
+    /// validating that the path points to a list and the index is in bounds.
+    fn list_element_path(&self, path: &str, index: i64) -> Result<String, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        // Navigate before creating transaction
         let list_obj = if segments.is_empty() {
             ROOT
         } else {
             navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
         };
 
-        let list_len = self.doc.length(&list_obj);
-        let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
-            }
+        if self.doc.object_type(&list_obj)? != automerge::ObjType::List {
+            return Err(AutomergeError::Fail);
         }
-        Ok(())
+
+        let list_len = self.doc.length(&list_obj) as i64;
+        let resolved = if index < 0 { list_len + index } else { index };
+        if resolved < 0 || resolved >= list_len {
+            return Err(AutomergeError::InvalidIndex(index.unsigned_abs() as usize));
+        }
+
+        Ok(format!("{}[{}]", path, resolved))
     }
 
-    /// Append a boolean value to a list and return the raw change bytes.
-    pub fn append_bool_with_change(
-        &mut self,
-        path: &str,
-        value: bool,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+    /// Returns the length of a list at the specified path.
+    ///
+    /// Returns `None` if the path doesn't exist or doesn't point to a list.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the list
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("items").unwrap();
+    /// client.append_text("items", "first").unwrap();
+    /// client.append_text("items", "second").unwrap();
+    ///
+    /// assert_eq!(client.list_len("items").unwrap(), Some(2));
+    /// assert_eq!(client.list_len("missing").unwrap(), None);
+    /// assert_eq!(client.list_len("").unwrap(), None); // the root is a map
+    /// ```
+    pub fn list_len(&self, path: &str) -> Result<Option<usize>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        // Navigate before creating transaction
         let list_obj = if segments.is_empty() {
             ROOT
         } else {
-            navigate_path_read(&self.doc, &segments)?.ok_or(AutomergeError::Fail)?
+            match navigate_path_read(&self.doc, &segments)? {
+                Some(obj) => obj,
+                None => return Ok(None),
+            }
         };
 
-        let list_len = self.doc.length(&list_obj);
-        let mut tx = self.doc.transaction();
-        tx.insert(&list_obj, list_len, value)?;
-        let (hash, _patch) = tx.commit();
+        // Maps and text have a length too, but it isn't a list length
+        if self.doc.object_type(&list_obj)? != ObjType::List {
+            return Ok(None);
+        }
+        Ok(Some(self.doc.length(&list_obj)))
+    }
 
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+    /// Returns the index of the first element of a list equal to `value`.
+    ///
+    /// Matching is type-sensitive: the integer `1` doesn't match the boolean `true`
+    /// or the double `1.0`. A `Str` value also matches a `Text` object with the same
+    /// content. Returns `None` if nothing matches or the path isn't a list.
+    pub fn index_of(
+        &self,
+        path: &str,
+        value: ScalarValue,
+    ) -> Result<Option<usize>, AutomergeError> {
+        let target = match value {
+            ScalarValue::Str(text) => TypedValue::Text(text.to_string()),
+            ScalarValue::Int(i) => TypedValue::Int(i),
+            ScalarValue::F64(f) => TypedValue::Double(f),
+            ScalarValue::Boolean(b) => TypedValue::Bool(b),
+            ScalarValue::Timestamp(ts) => TypedValue::Timestamp(ts),
+            ScalarValue::Bytes(b) => TypedValue::Bytes(b),
+            ScalarValue::Null => TypedValue::Null,
+            _ => return Ok(None),
+        };
+        Ok(self
+            .get_list_values(path)?
+            .and_then(|values| values.iter().position(|v| *v == target)))
+    }
+
+    /// Returns the length of a text value at the specified path, in characters.
+    ///
+    /// Both `Text` objects and plain string scalars are supported. The length
+    /// counts Unicode scalar values, matching the positions accepted by
+    /// `splice_text()`, so it can differ from the UTF-8 byte length.
+    ///
+    /// Returns `None` if the path doesn't exist or doesn't point to text.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the text field
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("greeting", "héllo").unwrap();
+    ///
+    /// assert_eq!(client.text_len("greeting").unwrap(), Some(5));
+    /// assert_eq!(client.text_len("missing").unwrap(), None);
+    /// ```
+    pub fn text_len(&self, path: &str) -> Result<Option<usize>, AutomergeError> {
+        Ok(self.get_text(path)?.map(|text| text.chars().count()))
+    }
+
+    /// Returns up to `len` characters of a text value, starting at character `start`.
+    ///
+    /// Offsets count Unicode scalar values like `text_len()`, so slices never split
+    /// a multibyte character. A range running past the end is clamped, and a
+    /// `start` at or beyond the end gives an empty string.
+    ///
+    /// Returns `None` if the path doesn't exist or doesn't point to text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("greeting", "héllo world").unwrap();
+    ///
+    /// assert_eq!(client.get_text_range("greeting", 1, 4).unwrap(), Some("éllo".to_string()));
+    /// assert_eq!(client.get_text_range("greeting", 6, 100).unwrap(), Some("world".to_string()));
+    /// ```
+    pub fn get_text_range(
+        &self,
+        path: &str,
+        start: usize,
+        len: usize,
+    ) -> Result<Option<String>, AutomergeError> {
+        Ok(self
+            .get_text(path)?
+            .map(|text| text.chars().skip(start).take(len).collect()))
+    }
+
+    /// Returns the number of keys in a map at the specified path.
+    ///
+    /// Returns `None` if the path doesn't exist or doesn't point to a map.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the map
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("name", "Alice").unwrap();
+    /// client.put_text("age", "30").unwrap();
+    ///
+    /// assert_eq!(client.map_len("").unwrap(), Some(2));
+    /// assert_eq!(client.map_len("missing").unwrap(), None);
+    /// ```
+    pub fn map_len(&self, path: &str) -> Result<Option<usize>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        let map_obj = if segments.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, &segments)? {
+                Some(obj) => obj,
+                None => return Ok(None),
             }
-        }
+        };
 
-        Ok(None)
+        if !matches!(
+            self.doc.object_type(&map_obj)?,
+            ObjType::Map | ObjType::Table
+        ) {
+            return Ok(None);
+        }
+        Ok(Some(self.doc.keys(&map_obj).count()))
     }
 
-    /// Returns the length of a list at the specified path.
+    /// Get the Automerge type name of the value at the specified path.
     ///
-    /// Returns `None` if the path doesn't exist or doesn't point to a list.
-    ///
-    /// # Arguments
+    /// Returns one of `"map"`, `"list"`, `"text"`, `"str"`, `"int"`, `"double"`,
+    /// `"bool"`, `"counter"`, `"timestamp"`, `"bytes"`, or `"null"`. A `Text` object is
+    /// reported as `"text"` while a plain string scalar is reported as `"str"`.
+    /// The empty path refers to the root map.
     ///
-    /// * `path` - Path to the list
+    /// Returns `None` if the path doesn't exist.
     ///
     /// # Examples
     ///
@@ -2021,35 +5519,120 @@ impl RedisAutomergeClient {
     /// use redis_automerge::ext::RedisAutomergeClient;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.create_list("items").unwrap();
-    /// client.append_text("items", "first").unwrap();
-    /// client.append_text("items", "second").unwrap();
+    /// client.put_int("user.age", 30).unwrap();
     ///
-    /// assert_eq!(client.list_len("items").unwrap(), Some(2));
-    /// assert_eq!(client.list_len("missing").unwrap(), None);
+    /// assert_eq!(client.get_type("user").unwrap(), Some("map"));
+    /// assert_eq!(client.get_type("user.age").unwrap(), Some("int"));
+    /// assert_eq!(client.get_type("user.email").unwrap(), None);
     /// ```
-    pub fn list_len(&self, path: &str) -> Result<Option<usize>, AutomergeError> {
-        let segments = parse_path(path)?;
+    pub fn get_type(&self, path: &str) -> Result<Option<&'static str>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        let list_obj = if segments.is_empty() {
+        if segments.is_empty() {
+            return Ok(Some("map"));
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = if parent_path.is_empty() {
             ROOT
         } else {
-            match navigate_path_read(&self.doc, &segments)? {
+            match navigate_path_read(&self.doc, parent_path)? {
                 Some(obj) => obj,
                 None => return Ok(None),
             }
         };
 
-        Ok(Some(self.doc.length(&list_obj)))
+        let type_name = match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
+            Some((Value::Object(obj_type), _)) => match obj_type {
+                automerge::ObjType::Map | automerge::ObjType::Table => "map",
+                automerge::ObjType::List => "list",
+                automerge::ObjType::Text => "text",
+            },
+            Some((Value::Scalar(s), _)) => match s.as_ref() {
+                ScalarValue::Str(_) => "str",
+                ScalarValue::Int(_) | ScalarValue::Uint(_) => "int",
+                ScalarValue::F64(_) => "double",
+                ScalarValue::Boolean(_) => "bool",
+                ScalarValue::Counter(_) => "counter",
+                ScalarValue::Timestamp(_) => "timestamp",
+                ScalarValue::Bytes(_) => "bytes",
+                _ => "null",
+            },
+            None => return Ok(None),
+        };
+
+        Ok(Some(type_name))
     }
 
-    /// Returns the number of keys in a map at the specified path.
+    /// Check whether the value at the specified path is a `Text` object.
     ///
-    /// Returns `None` if the path doesn't exist or doesn't point to a map.
+    /// `get_text` reads both `Text` objects and plain string scalars, but only a
+    /// `Text` object supports character-level edits that merge concurrently. This
+    /// tells the two apart: `Some(true)` for a `Text` object, `Some(false)` for a
+    /// string scalar.
+    ///
+    /// Returns `None` if the path doesn't exist or holds any other type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("title", "Draft").unwrap();
+    /// client.create_text("body", "Hello").unwrap();
+    ///
+    /// assert_eq!(client.is_text_object("title").unwrap(), Some(false));
+    /// assert_eq!(client.is_text_object("body").unwrap(), Some(true));
+    /// assert_eq!(client.is_text_object("missing").unwrap(), None);
+    /// ```
+    pub fn is_text_object(&self, path: &str) -> Result<Option<bool>, AutomergeError> {
+        Ok(match self.get_type(path)? {
+            Some("text") => Some(true),
+            Some("str") => Some(false),
+            _ => None,
+        })
+    }
+
+    /// Get the Automerge object ID of the map, list or text object at the specified path.
+    ///
+    /// The ID is in Automerge's string form, `<counter>@<actor-hex>`, or `_root`
+    /// for the root map. It identifies the object itself rather than its
+    /// location, so it stays the same across unrelated writes.
+    ///
+    /// Returns `None` if the path doesn't exist or holds a scalar.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.create_list("tags").unwrap();
+    /// client.put_text("title", "Notes").unwrap();
+    ///
+    /// assert!(client.object_id_at("tags").unwrap().is_some());
+    /// assert_eq!(client.object_id_at("").unwrap(), Some("_root".to_string()));
+    /// assert_eq!(client.object_id_at("title").unwrap(), None);
+    /// ```
+    pub fn object_id_at(&self, path: &str) -> Result<Option<String>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+        if segments.is_empty() {
+            return Ok(Some(ROOT.to_string()));
+        }
+        Ok(navigate_path_read(&self.doc, &segments)?.map(|obj| obj.to_string()))
+    }
+
+    /// Check whether a value exists at the specified path.
+    ///
+    /// Returns `false` (rather than an error) when an intermediate segment is
+    /// missing, when a segment does not match the container type (e.g. indexing
+    /// into a map), or when an array index is out of bounds. The empty path
+    /// refers to the root map and always exists.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the map
+    /// * `path` - Path to check
     ///
     /// # Examples
     ///
@@ -2057,25 +5640,29 @@ impl RedisAutomergeClient {
     /// use redis_automerge::ext::RedisAutomergeClient;
     ///
     /// let mut client = RedisAutomergeClient::new();
-    /// client.put_text("name", "Alice").unwrap();
-    /// client.put_text("age", "30").unwrap();
+    /// client.put_text("user.name", "Alice").unwrap();
     ///
-    /// assert_eq!(client.map_len("").unwrap(), Some(2));
-    /// assert_eq!(client.map_len("missing").unwrap(), None);
+    /// assert!(client.path_exists("user.name").unwrap());
+    /// assert!(!client.path_exists("user.email").unwrap());
+    /// assert!(client.path_exists("").unwrap());
     /// ```
-    pub fn map_len(&self, path: &str) -> Result<Option<usize>, AutomergeError> {
-        let segments = parse_path(path)?;
+    pub fn path_exists(&self, path: &str) -> Result<bool, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
-        let map_obj = if segments.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, &segments)? {
-                Some(obj) => obj,
-                None => return Ok(None),
-            }
+        if segments.is_empty() {
+            return Ok(true);
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+        let parent_obj = match navigate_path_read(&self.doc, parent_path) {
+            Ok(Some(obj)) => obj,
+            Ok(None) | Err(_) => return Ok(false),
         };
 
-        Ok(Some(self.doc.keys(&map_obj).count()))
+        match get_value_from_parent(&self.doc, &parent_obj, &field_name[0]) {
+            Ok(Some(_)) => Ok(true),
+            Ok(None) | Err(_) => Ok(false),
+        }
     }
 
     /// Get changes from the document that are not in the provided have_deps list.
@@ -2113,6 +5700,168 @@ impl RedisAutomergeClient {
         self.doc.get_changes(have_deps)
     }
 
+    /// Find the dependencies of `changes` that neither the document nor the
+    /// batch itself contains.
+    ///
+    /// `AM.APPLY` uses this to reject an incomplete batch up front rather than
+    /// let `apply()` queue the changes it can't integrate yet, so only the sync
+    /// protocol leaves changes queued. The order within the batch doesn't
+    /// matter. Each missing hash is listed once, in the order it is first
+    /// depended on.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut peer = RedisAutomergeClient::new();
+    /// peer.put_text("title", "Draft").unwrap();
+    /// peer.put_int("revision", 1).unwrap();
+    /// let changes = peer.get_changes(&[]);
+    ///
+    /// let client = RedisAutomergeClient::new();
+    /// assert_eq!(client.missing_deps(&changes[1..]), vec![changes[0].hash()]);
+    /// assert!(client.missing_deps(&changes).is_empty());
+    /// ```
+    pub fn missing_deps(&self, changes: &[Change]) -> Vec<ChangeHash> {
+        let batch: std::collections::HashSet<ChangeHash> =
+            changes.iter().map(|change| change.hash()).collect();
+        let mut missing = Vec::new();
+        for dep in changes.iter().flat_map(|change| change.deps()) {
+            if !batch.contains(dep)
+                && self.doc.get_change_by_hash(dep).is_none()
+                && !missing.contains(dep)
+            {
+                missing.push(*dep);
+            }
+        }
+        missing
+    }
+
+    /// Get at most `limit` changes not covered by `have_deps`.
+    ///
+    /// Changes come back in the same topological order as `get_changes()`, so
+    /// every page only depends on earlier pages. To fetch the next page, apply
+    /// the returned changes and pass the resulting heads as `have_deps`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_int("a", 1).unwrap();
+    /// client.put_int("b", 2).unwrap();
+    ///
+    /// let mut replica = RedisAutomergeClient::new();
+    /// let page = client.get_changes_limit(&[], 1);
+    /// assert_eq!(page.len(), 1);
+    /// replica.apply_change_bytes(page[0].raw_bytes()).unwrap();
+    ///
+    /// let rest = client.get_changes_limit(&replica.get_heads(), 1);
+    /// assert_eq!(rest.len(), 1);
+    /// ```
+    pub fn get_changes_limit(&self, have_deps: &[ChangeHash], limit: usize) -> Vec<Change> {
+        let mut changes = self.doc.get_changes(have_deps);
+        changes.truncate(limit);
+        changes
+    }
+
+    /// Get the current heads of the document.
+    ///
+    /// The heads are the hashes of the changes that no other change depends on. A
+    /// document with a linear history has a single head; concurrent changes that have
+    /// not yet been merged by a later change produce multiple heads.
+    ///
+    /// The returned hashes can be passed as `have_deps` to [`get_changes`](Self::get_changes)
+    /// or as `before_heads`/`after_heads` to [`get_diff`](Self::get_diff).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("field", "value").unwrap();
+    ///
+    /// let heads = client.get_heads();
+    /// assert_eq!(heads.len(), 1);
+    /// assert_eq!(client.get_changes(&heads).len(), 0);
+    /// ```
+    pub fn get_heads(&self) -> Vec<ChangeHash> {
+        self.doc.get_heads()
+    }
+
+    /// Get a single change by its hash.
+    ///
+    /// Returns `None` if the document doesn't contain a change with the given hash.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("field", "value").unwrap();
+    ///
+    /// let hash = client.get_changes(&[])[0].hash();
+    /// let change = client.get_change_by_hash(&hash).unwrap();
+    /// assert_eq!(change.hash(), hash);
+    /// ```
+    pub fn get_change_by_hash(&self, hash: &ChangeHash) -> Option<Change> {
+        self.doc.get_change_by_hash(hash)
+    }
+
+    /// Generate the next sync protocol message for a peer.
+    ///
+    /// `state` tracks what is known about the peer and is updated in place.
+    /// Returns the encoded message, or `None` if there is nothing to send.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::sync;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut server = RedisAutomergeClient::new();
+    /// server.put_text("field", "value").unwrap();
+    ///
+    /// let mut state = sync::State::new();
+    /// let message = server.generate_sync_message(&mut state);
+    /// assert!(message.is_some());
+    /// ```
+    pub fn generate_sync_message(&self, state: &mut sync::State) -> Option<Vec<u8>> {
+        self.doc
+            .generate_sync_message(state)
+            .map(|message| message.encode())
+    }
+
+    /// Receive a sync protocol message from a peer and apply any changes it contains.
+    ///
+    /// `state` tracks what is known about the peer and is updated in place.
+    /// Returns the raw bytes of every change that was new to this document.
+    pub fn receive_sync_message_with_changes(
+        &mut self,
+        state: &mut sync::State,
+        message: &[u8],
+    ) -> Result<Vec<Vec<u8>>, AutomergeError> {
+        let message = sync::Message::decode(message).map_err(|_| AutomergeError::Fail)?;
+        let before_heads = self.doc.get_heads();
+        self.doc.receive_sync_message(state, message)?;
+
+        let new_changes: Vec<Vec<u8>> = self
+            .doc
+            .get_changes(&before_heads)
+            .iter()
+            .map(|change| change.raw_bytes().to_vec())
+            .collect();
+        for change in &new_changes {
+            self.record_change(change.clone());
+        }
+
+        Ok(new_changes)
+    }
+
     /// Get the diff between two document states.
     ///
     /// This uses Automerge's `diff` function to compare two document states identified by
@@ -2158,6 +5907,9 @@ impl RedisAutomergeClient {
     ///
     /// If the field contains a string scalar, it will be converted to a Text object first.
     ///
+    /// `pos` and `del` count Unicode scalar values (Rust `char`s), not UTF-8
+    /// bytes: in `"héllo"` the `l` after the accented `é` is at position 2.
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the text field
@@ -2184,71 +5936,17 @@ impl RedisAutomergeClient {
     /// Returns an error if:
     /// - The path is invalid or doesn't exist
     /// - The value at path is not text
-    /// - The position or deletion count is invalid
+    /// - `pos` is past the end of the text, `pos + del` runs past the end, or a
+    ///   negative `del` runs back past the start ([`SpliceError::OutOfBounds`])
     pub fn splice_text(
         &mut self,
         path: &str,
         pos: usize,
         del: isize,
         text: &str,
-    ) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
-        }
-
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-
-        // Get parent object
-        let parent_obj = if parent_path.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Err(AutomergeError::Fail),
-            }
-        };
-
-        // Check what exists at the path
-        let text_obj = match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
-            Some((Value::Object(automerge::ObjType::Text), obj_id)) => obj_id,
-            Some((Value::Scalar(s), _)) => {
-                // Convert scalar string to Text object
-                if let ScalarValue::Str(existing_text) = s.as_ref() {
-                    // Clone the text to avoid borrow checker issues
-                    let existing_text_owned = existing_text.to_string();
-                    let mut tx = self.doc.transaction();
-                    let parent_for_put = navigate_or_create_path(&mut tx, parent_path)?;
-                    let text_obj = match &field_name[0] {
-                        PathSegment::Key(key) => {
-                            tx.put_object(&parent_for_put, key.as_str(), automerge::ObjType::Text)?
-                        }
-                        PathSegment::Index(idx) => {
-                            tx.put_object(&parent_for_put, *idx, automerge::ObjType::Text)?
-                        }
-                    };
-                    // Insert existing text
-                    tx.splice_text(&text_obj, 0, 0, &existing_text_owned)?;
-                    let (_hash, _patch) = tx.commit();
-                    text_obj
-                } else {
-                    return Err(AutomergeError::Fail);
-                }
-            }
-            _ => return Err(AutomergeError::Fail),
-        };
-
-        let mut tx = self.doc.transaction();
-        tx.splice_text(&text_obj, pos, del, text)?;
-        let (hash, _patch) = tx.commit();
-
-        if let Some(h) = hash {
-            if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
-            }
-        }
-        Ok(())
+    ) -> Result<(), SpliceError> {
+        self.splice_text_with_change(path, pos, del, text, CommitOptions::default())
+            .map(|_| ())
     }
 
     /// Splice text and return the raw change bytes.
@@ -2267,18 +5965,21 @@ impl RedisAutomergeClient {
     ///
     /// # Returns
     ///
-    /// - `Some(Vec<u8>)` - Raw change bytes if a change was generated
+    /// - `Some((hash, bytes))` - The hash and raw bytes of the change, if one was generated
     /// - `None` - If no change was needed
     ///
     /// # Examples
     ///
     /// ```rust,no_run
+    /// use automerge::transaction::CommitOptions;
     /// use redis_automerge::ext::RedisAutomergeClient;
     ///
     /// let mut client = RedisAutomergeClient::new();
     /// client.put_text("doc", "Hello World").unwrap();
     ///
-    /// let change = client.splice_text_with_change("doc", 6, 5, "Rust").unwrap();
+    /// let change = client
+    ///     .splice_text_with_change("doc", 6, 5, "Rust", CommitOptions::default())
+    ///     .unwrap();
     ///
     /// if let Some(change_bytes) = change {
     ///     // Publish to other clients
@@ -2287,66 +5988,84 @@ impl RedisAutomergeClient {
     pub fn splice_text_with_change(
         &mut self,
         path: &str,
-        pos: usize,
-        del: isize,
-        text: &str,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
-
-        if segments.is_empty() {
-            return Err(AutomergeError::Fail);
-        }
-
-        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
-
-        // Get parent object
-        let parent_obj = if parent_path.is_empty() {
-            ROOT
-        } else {
-            match navigate_path_read(&self.doc, parent_path)? {
-                Some(obj) => obj,
-                None => return Err(AutomergeError::Fail),
-            }
-        };
-
-        // Check what exists at the path
-        let text_obj = match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
-            Some((Value::Object(automerge::ObjType::Text), obj_id)) => obj_id,
-            Some((Value::Scalar(s), _)) => {
-                // Convert scalar string to Text object
-                if let ScalarValue::Str(existing_text) = s.as_ref() {
-                    // Clone the text to avoid borrow checker issues
-                    let existing_text_owned = existing_text.to_string();
-                    let mut tx = self.doc.transaction();
-                    let parent_for_put = navigate_or_create_path(&mut tx, parent_path)?;
-                    let text_obj = match &field_name[0] {
-                        PathSegment::Key(key) => {
-                            tx.put_object(&parent_for_put, key.as_str(), automerge::ObjType::Text)?
-                        }
-                        PathSegment::Index(idx) => {
-                            tx.put_object(&parent_for_put, *idx, automerge::ObjType::Text)?
-                        }
-                    };
-                    // Insert existing text
-                    tx.splice_text(&text_obj, 0, 0, &existing_text_owned)?;
-                    let (_hash, _patch) = tx.commit();
-                    text_obj
-                } else {
-                    return Err(AutomergeError::Fail);
+        pos: usize,
+        del: isize,
+        text: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, SpliceError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        if segments.is_empty() {
+            return Err(AutomergeError::Fail.into());
+        }
+
+        let (parent_path, field_name) = segments.split_at(segments.len() - 1);
+
+        // Get parent object
+        let parent_obj = if parent_path.is_empty() {
+            ROOT
+        } else {
+            match navigate_path_read(&self.doc, parent_path)? {
+                Some(obj) => obj,
+                None => return Err(AutomergeError::Fail.into()),
+            }
+        };
+
+        // A Text object is spliced directly; a scalar string is converted first
+        let (existing_obj, existing_text) =
+            match get_value_from_parent(&self.doc, &parent_obj, &field_name[0])? {
+                Some((Value::Object(automerge::ObjType::Text), obj_id)) => {
+                    let current = self.doc.text(&obj_id)?;
+                    (Some(obj_id), current)
                 }
+                Some((Value::Scalar(s), _)) => match s.as_ref() {
+                    ScalarValue::Str(existing_text) => (None, existing_text.to_string()),
+                    _ => return Err(AutomergeError::Fail.into()),
+                },
+                _ => return Err(AutomergeError::Fail.into()),
+            };
+
+        // Positions are Unicode scalar (char) offsets. Reject out-of-range
+        // splices up front, before a scalar string is converted to Text. A
+        // negative `del` deletes backwards from `pos`.
+        let len = existing_text.chars().count();
+        let out_of_bounds = pos > len
+            || (del > 0 && del.unsigned_abs() > len - pos)
+            || (del < 0 && del.unsigned_abs() > pos);
+        if out_of_bounds {
+            return Err(SpliceError::OutOfBounds { pos, del, len });
+        }
+
+        let text_obj = match existing_obj {
+            Some(obj_id) => obj_id,
+            None => {
+                // Convert scalar string to Text object
+                let mut tx = self.doc.transaction();
+                let parent_for_put = navigate_or_create_path(&mut tx, parent_path)?;
+                let text_obj = match &field_name[0] {
+                    PathSegment::Key(key) => {
+                        tx.put_object(&parent_for_put, key.as_str(), automerge::ObjType::Text)?
+                    }
+                    PathSegment::Index(idx) => {
+                        tx.put_object(&parent_for_put, *idx, automerge::ObjType::Text)?
+                    }
+                };
+                // Insert existing text
+                tx.splice_text(&text_obj, 0, 0, &existing_text)?;
+                let (_hash, _patch) = tx.commit();
+                text_obj
             }
-            _ => return Err(AutomergeError::Fail),
         };
 
         let mut tx = self.doc.transaction();
         tx.splice_text(&text_obj, pos, del, text)?;
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
@@ -2388,95 +6107,124 @@ impl RedisAutomergeClient {
     /// // }
     /// ```
     pub fn to_json(&self, pretty: bool) -> Result<String, AutomergeError> {
-        use serde_json::{Map, Value as JsonValue};
-
-        // Helper function to recursively convert an Automerge object to JSON
-        fn obj_to_json(doc: &Automerge, obj_id: &ObjId) -> Result<JsonValue, AutomergeError> {
-            // Check the object type
-            let obj_type = doc.object_type(obj_id)?;
-
-            match obj_type {
-                automerge::ObjType::Map => {
-                    let mut map = Map::new();
-                    // Iterate over all keys in the map
-                    for key in doc.keys(obj_id) {
-                        if let Some((value, value_obj_id)) = doc.get(obj_id, &key)? {
-                            let json_value = value_to_json(doc, &value, &value_obj_id)?;
-                            map.insert(key.to_string(), json_value);
-                        }
-                    }
-                    Ok(JsonValue::Object(map))
-                }
-                automerge::ObjType::List => {
-                    let mut arr = Vec::new();
-                    let len = doc.length(obj_id);
-                    for i in 0..len {
-                        if let Some((value, value_obj_id)) = doc.get(obj_id, i)? {
-                            let json_value = value_to_json(doc, &value, &value_obj_id)?;
-                            arr.push(json_value);
-                        }
-                    }
-                    Ok(JsonValue::Array(arr))
-                }
-                automerge::ObjType::Text => {
-                    // Text objects are converted to strings
-                    let text = doc.text(obj_id)?;
-                    Ok(JsonValue::String(text))
-                }
-                _ => {
-                    // Unknown object type, treat as null
-                    Ok(JsonValue::Null)
-                }
-            }
-        }
+        // The empty path always resolves to ROOT
+        self.to_json_at("", pretty, false)
+            .map(|json| json.unwrap_or_default())
+    }
 
-        // Helper function to convert an Automerge value to JSON
-        fn value_to_json(
-            doc: &Automerge,
-            value: &Value,
-            obj_id: &ObjId,
-        ) -> Result<JsonValue, AutomergeError> {
-            match value {
-                Value::Object(_) => {
-                    // Recursively convert nested objects
-                    obj_to_json(doc, obj_id)
-                }
-                Value::Scalar(scalar) => {
-                    let s = scalar.as_ref();
-                    match s {
-                        ScalarValue::Str(s) => Ok(JsonValue::String(s.to_string())),
-                        ScalarValue::Int(i) => Ok(JsonValue::Number((*i).into())),
-                        ScalarValue::F64(f) => {
-                            if let Some(num) = serde_json::Number::from_f64(*f) {
-                                Ok(JsonValue::Number(num))
-                            } else {
-                                Ok(JsonValue::Null)
-                            }
-                        }
-                        ScalarValue::Counter(c) => Ok(JsonValue::Number(i64::from(c).into())),
-                        ScalarValue::Timestamp(ts) => {
-                            // Convert Unix timestamp (milliseconds) to ISO 8601 string
-                            let dt = DateTime::from_timestamp_millis(*ts)
-                                .unwrap_or_else(|| DateTime::<Utc>::UNIX_EPOCH);
-                            Ok(JsonValue::String(dt.to_rfc3339()))
-                        }
-                        ScalarValue::Boolean(b) => Ok(JsonValue::Bool(*b)),
-                        ScalarValue::Null => Ok(JsonValue::Null),
-                        _ => Ok(JsonValue::Null),
-                    }
+    /// Convert the entire document, as it was at `heads`, to JSON.
+    ///
+    /// Produces what `to_json()` returned when the document's heads were
+    /// `heads`, for point-in-time exports. The document is forked at `heads`,
+    /// so the cost grows with the history.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("name", "Alice").unwrap();
+    /// let heads = client.get_heads();
+    /// client.put_int("age", 30).unwrap();
+    ///
+    /// assert_eq!(client.to_json_at_heads(false, &heads).unwrap(), r#"{"name":"Alice"}"#);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `AutomergeError::InvalidHash` if a head isn't part of the document.
+    pub fn to_json_at_heads(
+        &self,
+        pretty: bool,
+        heads: &[ChangeHash],
+    ) -> Result<String, AutomergeError> {
+        self.fork_at(heads)?.to_json(pretty)
+    }
+
+    /// Convert the entire document to typed JSON.
+    ///
+    /// Like `to_json()`, but counters are written as `{"$counter": n}` and
+    /// timestamps as `{"$timestamp": millis}`, so that `from_json()` restores
+    /// them as counters and timestamps instead of plain numbers and strings.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_counter("views", 5).unwrap();
+    ///
+    /// let json = client.to_json_typed(false).unwrap();
+    /// // Returns: {"views":{"$counter":5}}
+    ///
+    /// let mut copy = RedisAutomergeClient::from_json(&json).unwrap();
+    /// copy.inc_counter("views", 1).unwrap();
+    /// ```
+    pub fn to_json_typed(&self, pretty: bool) -> Result<String, AutomergeError> {
+        self.to_json_at("", pretty, true)
+            .map(|json| json.unwrap_or_default())
+    }
+
+    /// Convert the value at a path to JSON.
+    ///
+    /// Maps and lists are serialized recursively like `to_json()`, while leaf
+    /// values become JSON scalars (e.g., `"Alice"` or `30`). An empty path
+    /// exports the whole document. When `typed` is set, counters and timestamps
+    /// use the typed-JSON form of `to_json_typed()`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(String)` - JSON representation of the value at the path
+    /// - `None` - If the path doesn't exist
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let client =
+    ///     RedisAutomergeClient::from_json(r#"{"user":{"name":"Alice","age":30}}"#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     client.to_json_at("user", false, false).unwrap(),
+    ///     Some(r#"{"age":30,"name":"Alice"}"#.to_string())
+    /// );
+    /// assert_eq!(
+    ///     client.to_json_at("user.name", false, false).unwrap(),
+    ///     Some(r#""Alice""#.to_string())
+    /// );
+    /// ```
+    pub fn to_json_at(
+        &self,
+        path: &str,
+        pretty: bool,
+        typed: bool,
+    ) -> Result<Option<String>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
+
+        let json_value = match segments.split_last() {
+            None => obj_to_json(&self.doc, &ROOT, typed)?,
+            Some((field, parent_path)) => {
+                let parent_obj = match navigate_path_read(&self.doc, parent_path)? {
+                    Some(obj) => obj,
+                    None => return Ok(None),
+                };
+                match get_value_from_parent(&self.doc, &parent_obj, field)? {
+                    Some((value, obj_id)) => value_to_json(&self.doc, &value, &obj_id, typed)?,
+                    None => return Ok(None),
                 }
             }
-        }
-
-        // Start conversion from ROOT
-        let json_value = obj_to_json(&self.doc, &ROOT)?;
+        };
 
         // Serialize to string
-        if pretty {
-            serde_json::to_string_pretty(&json_value).map_err(|_| AutomergeError::Fail)
+        let json = if pretty {
+            serde_json::to_string_pretty(&json_value)
         } else {
-            serde_json::to_string(&json_value).map_err(|_| AutomergeError::Fail)
-        }
+            serde_json::to_string(&json_value)
+        };
+        json.map(Some).map_err(|_| AutomergeError::Fail)
     }
 
     /// Create a new Automerge document from a JSON string.
@@ -2488,6 +6236,8 @@ impl RedisAutomergeClient {
     /// - JSON numbers become integers (if no decimal) or doubles
     /// - JSON booleans become boolean values
     /// - JSON null becomes null
+    /// - `{"$counter": n}` and `{"$timestamp": millis}` (as written by
+    ///   `to_json_typed()`) become counters and timestamps
     ///
     /// This replaces the entire document with the structure from the JSON.
     ///
@@ -2516,8 +6266,6 @@ impl RedisAutomergeClient {
     ///
     /// Returns an error if the JSON string cannot be parsed or converted to Automerge format.
     pub fn from_json(json: &str) -> Result<Self, AutomergeError> {
-        use serde_json::Value as JsonValue;
-
         // Parse JSON string
         let json_value: JsonValue = serde_json::from_str(json).map_err(|_| AutomergeError::Fail)?;
 
@@ -2525,110 +6273,11 @@ impl RedisAutomergeClient {
         let mut client = Self::new();
         let mut tx = client.doc.transaction();
 
-        // Helper function to recursively populate an Automerge object from JSON
-        fn populate_from_json<T: Transactable>(
-            tx: &mut T,
-            parent: &ObjId,
-            key_or_index: KeyOrIndex,
-            value: &JsonValue,
-        ) -> Result<(), AutomergeError> {
-            match value {
-                JsonValue::Object(map) => {
-                    // Create a Map object
-                    let obj_id = match key_or_index {
-                        KeyOrIndex::Key(key) => {
-                            tx.put_object(parent, key.as_str(), automerge::ObjType::Map)?
-                        }
-                        KeyOrIndex::Index(idx) => {
-                            tx.put_object(parent, idx, automerge::ObjType::Map)?
-                        }
-                    };
-                    // Recursively populate the map
-                    for (k, v) in map {
-                        populate_from_json(tx, &obj_id, KeyOrIndex::Key(k.clone()), v)?;
-                    }
-                }
-                JsonValue::Array(arr) => {
-                    // Create a List object
-                    let obj_id = match key_or_index {
-                        KeyOrIndex::Key(key) => {
-                            tx.put_object(parent, key.as_str(), automerge::ObjType::List)?
-                        }
-                        KeyOrIndex::Index(idx) => {
-                            tx.put_object(parent, idx, automerge::ObjType::List)?
-                        }
-                    };
-                    // Append elements to the list
-                    for (i, v) in arr.iter().enumerate() {
-                        populate_from_json(tx, &obj_id, KeyOrIndex::Index(i), v)?;
-                    }
-                }
-                JsonValue::String(s) => {
-                    // Insert as text value
-                    match key_or_index {
-                        KeyOrIndex::Key(key) => {
-                            tx.put(parent, key.as_str(), s.as_str())?;
-                        }
-                        KeyOrIndex::Index(idx) => {
-                            tx.insert(parent, idx, s.as_str())?;
-                        }
-                    }
-                }
-                JsonValue::Number(n) => {
-                    // Convert to int or double
-                    match key_or_index {
-                        KeyOrIndex::Key(key) => {
-                            if let Some(i) = n.as_i64() {
-                                tx.put(parent, key.as_str(), i)?;
-                            } else if let Some(f) = n.as_f64() {
-                                tx.put(parent, key.as_str(), f)?;
-                            }
-                        }
-                        KeyOrIndex::Index(idx) => {
-                            if let Some(i) = n.as_i64() {
-                                tx.insert(parent, idx, i)?;
-                            } else if let Some(f) = n.as_f64() {
-                                tx.insert(parent, idx, f)?;
-                            }
-                        }
-                    }
-                }
-                JsonValue::Bool(b) => {
-                    // Insert as boolean
-                    match key_or_index {
-                        KeyOrIndex::Key(key) => {
-                            tx.put(parent, key.as_str(), *b)?;
-                        }
-                        KeyOrIndex::Index(idx) => {
-                            tx.insert(parent, idx, *b)?;
-                        }
-                    }
-                }
-                JsonValue::Null => {
-                    // Insert as null
-                    match key_or_index {
-                        KeyOrIndex::Key(key) => {
-                            tx.put(parent, key.as_str(), ScalarValue::Null)?;
-                        }
-                        KeyOrIndex::Index(idx) => {
-                            tx.insert(parent, idx, ScalarValue::Null)?;
-                        }
-                    }
-                }
-            }
-            Ok(())
-        }
-
-        // Helper enum to handle both keys and indices
-        enum KeyOrIndex {
-            Key(String),
-            Index(usize),
-        }
-
-        // Start populating from root
+        // Populate the document from the root object
         if let JsonValue::Object(map) = &json_value {
             for (k, v) in map {
-                populate_from_json(&mut tx, &ROOT, KeyOrIndex::Key(k.clone()), v)?;
+                let segment = PathSegment::Key(k.clone());
+                put_json_value(&mut tx, &ROOT, &segment, false, v, false)?;
             }
         } else {
             // If root is not an object, we can't convert it directly
@@ -2638,13 +6287,188 @@ impl RedisAutomergeClient {
         let (hash, _patch) = tx.commit();
         if let Some(h) = hash {
             if let Some(change) = client.doc.get_change_by_hash(&h) {
-                client.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                client.record_change(change_bytes);
+            }
+        }
+
+        Ok(client)
+    }
+
+    /// Export the document as MessagePack.
+    ///
+    /// Unlike JSON, the encoding keeps integers, doubles, counters, timestamps,
+    /// byte strings and nulls distinct; see the `msgpack` module for the format.
+    /// Text objects are exported as strings.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, AutomergeError> {
+        let root = self
+            .get_typed_value("")?
+            .unwrap_or_else(|| TypedValue::Object(std::collections::HashMap::new()));
+        Ok(crate::msgpack::encode(&root))
+    }
+
+    /// Create a new document from MessagePack produced by `to_msgpack()`.
+    ///
+    /// The top-level value must be a map. The whole document is written in a
+    /// single change.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, MsgpackError> {
+        let root = crate::msgpack::decode(bytes).map_err(MsgpackError::Decode)?;
+        let TypedValue::Object(map) = root else {
+            return Err(MsgpackError::NotAMap);
+        };
+
+        let mut client = Self::new();
+        let mut tx = client.doc.transaction();
+        for (key, value) in &map {
+            put_typed_value(&mut tx, &ROOT, &PathSegment::Key(key.clone()), false, value)?;
+        }
+
+        let (hash, _patch) = tx.commit();
+        if let Some(h) = hash {
+            if let Some(change) = client.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                client.record_change(change_bytes);
             }
         }
 
         Ok(client)
     }
 
+    /// Merge a JSON object into this document in a single transaction.
+    ///
+    /// Unlike `from_json()`, which builds a new document, this keeps the existing
+    /// history and actor: each field in the JSON is written into the current
+    /// document, merging nested objects into existing maps. Fields not mentioned
+    /// in the JSON are left untouched, and fields that already hold the same
+    /// value generate no operations. The change carries `options`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some((hash, bytes))` - The hash and raw bytes of the change, if one was generated
+    /// - `None` - If the JSON matched the document and nothing changed
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::transaction::CommitOptions;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::from_json(r#"{"user":{"name":"Alice"}}"#).unwrap();
+    /// client
+    ///     .apply_json_merge(r#"{"user":{"age":30}}"#, CommitOptions::default())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(client.get_text("user.name").unwrap(), Some("Alice".to_string()));
+    /// assert_eq!(client.get_int("user.age").unwrap(), Some(30));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON cannot be parsed or its root is not an object.
+    pub fn apply_json_merge(
+        &mut self,
+        json: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let json_value: JsonValue = serde_json::from_str(json).map_err(|_| AutomergeError::Fail)?;
+        let JsonValue::Object(map) = &json_value else {
+            return Err(AutomergeError::Fail);
+        };
+
+        let text_as_object = self.text_as_object;
+        let mut tx = self.doc.transaction();
+        if let Err(e) = merge_json_object(&mut tx, &ROOT, map, text_as_object) {
+            tx.rollback();
+            return Err(e);
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Apply an RFC 6902 JSON Patch document.
+    ///
+    /// Supports the `add`, `remove`, `replace`, and `test` operations. Paths are
+    /// JSON Pointers (e.g., `"/users/0/name"`), where numeric tokens index into
+    /// lists and `-` appends to a list. All operations are applied in a single
+    /// transaction: if any operation fails, including a `test` whose value does
+    /// not match, the document is left unchanged and the error says which
+    /// operation failed and why.
+    pub fn apply_json_patch(&mut self, patch: &str) -> Result<(), JsonPatchError> {
+        self.apply_json_patch_with_change(patch, CommitOptions::default())
+            .map(|_| ())
+    }
+
+    /// Apply an RFC 6902 JSON Patch document and return the raw change bytes.
+    ///
+    /// Like `apply_json_patch()` but returns Automerge change bytes that can
+    /// be published to other clients for real-time synchronization.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - JSON array of patch operations
+    /// * `options` - Optional commit message and timestamp to attach to the change
+    ///
+    /// # Returns
+    ///
+    /// - `Some((hash, bytes))` - The hash and raw bytes of the change, if one was generated
+    /// - `None` - If the patch made no changes (e.g., only `test` operations)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::transaction::CommitOptions;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::from_json(r#"{"name":"Alice"}"#).unwrap();
+    /// let patch = r#"[
+    ///     {"op":"test","path":"/name","value":"Alice"},
+    ///     {"op":"replace","path":"/name","value":"Bob"}
+    /// ]"#;
+    /// let change = client
+    ///     .apply_json_patch_with_change(patch, CommitOptions::default())
+    ///     .unwrap();
+    /// ```
+    pub fn apply_json_patch_with_change(
+        &mut self,
+        patch: &str,
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, JsonPatchError> {
+        let ops: Vec<JsonValue> = serde_json::from_str(patch)
+            .map_err(|e| JsonPatchError::InvalidPatch(format!("expected a JSON array ({})", e)))?;
+
+        let text_as_object = self.text_as_object;
+        let mut tx = self.doc.transaction();
+        let result = ops
+            .iter()
+            .try_for_each(|op| apply_json_patch_op(&mut tx, op, text_as_object));
+        if let Err(e) = result {
+            tx.rollback();
+            return Err(e);
+        }
+
+        let (hash, _patch) = tx.commit_with(options);
+
+        if let Some(h) = hash {
+            if let Some(change) = self.doc.get_change_by_hash(&h) {
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Create a mark on a text object at the specified path.
     ///
     /// Marks allow attaching metadata to ranges of text, useful for rich text formatting
@@ -2680,7 +6504,7 @@ impl RedisAutomergeClient {
         end: usize,
         expand: ExpandMark,
     ) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
@@ -2734,13 +6558,15 @@ impl RedisAutomergeClient {
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
     }
 
     /// Create a mark on a text object and return the raw change bytes.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_mark_with_change(
         &mut self,
         path: &str,
@@ -2749,8 +6575,9 @@ impl RedisAutomergeClient {
         start: usize,
         end: usize,
         expand: ExpandMark,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
@@ -2800,13 +6627,13 @@ impl RedisAutomergeClient {
         let mut tx = self.doc.transaction();
         let mark = Mark::new(name.to_string(), value, start, end);
         tx.mark(&text_obj, mark, expand)?;
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
@@ -2846,7 +6673,7 @@ impl RedisAutomergeClient {
         end: usize,
         expand: ExpandMark,
     ) -> Result<(), AutomergeError> {
-        let segments = parse_path(path)?;
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
@@ -2899,7 +6726,8 @@ impl RedisAutomergeClient {
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
-                self.aof.push(change.raw_bytes().to_vec());
+                let change_bytes = change.raw_bytes().to_vec();
+                self.record_change(change_bytes);
             }
         }
         Ok(())
@@ -2913,8 +6741,9 @@ impl RedisAutomergeClient {
         start: usize,
         end: usize,
         expand: ExpandMark,
-    ) -> Result<Option<Vec<u8>>, AutomergeError> {
-        let segments = parse_path(path)?;
+        options: CommitOptions,
+    ) -> Result<Option<CommittedChange>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         if segments.is_empty() {
             return Err(AutomergeError::Fail);
@@ -2963,26 +6792,88 @@ impl RedisAutomergeClient {
 
         let mut tx = self.doc.transaction();
         tx.unmark(&text_obj, name, start, end, expand)?;
-        let (hash, _patch) = tx.commit();
+        let (hash, _patch) = tx.commit_with(options);
 
         if let Some(h) = hash {
             if let Some(change) = self.doc.get_change_by_hash(&h) {
                 let change_bytes = change.raw_bytes().to_vec();
-                self.aof.push(change_bytes.clone());
-                return Ok(Some(change_bytes));
+                self.record_change(change_bytes.clone());
+                return Ok(Some((h, change_bytes)));
             }
         }
 
         Ok(None)
     }
 
-    /// Get all marks on a text object at the specified path.
+    /// Get the marks that apply at a single position of a text object.
+    ///
+    /// A mark covering `[start, end)` applies at `start` but not at `end`, so a
+    /// mark over `[6, 11)` is active at positions 6 through 10.
+    ///
+    /// # Returns
+    ///
+    /// `(name, value)` pairs for every mark active at `pos`. Empty if the path
+    /// doesn't exist or no mark covers the position.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automerge::marks::ExpandMark;
+    /// use automerge::ScalarValue;
+    /// use redis_automerge::ext::RedisAutomergeClient;
+    ///
+    /// let mut client = RedisAutomergeClient::new();
+    /// client.put_text("doc", "Hello World").unwrap();
+    /// client
+    ///     .create_mark("doc", "bold", ScalarValue::Boolean(true), 6, 11, ExpandMark::None)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(client.marks_at("doc", 6).unwrap().len(), 1);
+    /// assert!(client.marks_at("doc", 11).unwrap().is_empty());
+    /// ```
+    pub fn marks_at(
+        &self,
+        path: &str,
+        pos: usize,
+    ) -> Result<Vec<(String, ScalarValue)>, AutomergeError> {
+        Ok(self
+            .get_marks(path, None)?
+            .into_iter()
+            .filter(|(_, _, start, end)| *start <= pos && pos < *end)
+            .map(|(name, value, _, _)| (name, value))
+            .collect())
+    }
+
+    /// Get the text at a path together with its marks.
+    ///
+    /// Returns the same values as `get_text()` and `get_marks()`, read from a
+    /// single borrow of the document so the marks always line up with the text.
+    ///
+    /// # Returns
+    ///
+    /// - `Some((text, marks))` - The text and its `(name, value, start, end)` marks
+    /// - `None` - If the path doesn't exist or isn't text
+    pub fn get_text_with_marks(
+        &self,
+        path: &str,
+    ) -> Result<Option<(String, Vec<TextMark>)>, AutomergeError> {
+        match self.get_text(path)? {
+            Some(text) => Ok(Some((text, self.get_marks(path, None)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the marks on a text object at the specified path.
     ///
     /// Returns a vector of marks containing their name, value, start, and end positions.
+    /// This is the resolved view: where marks of the same name overlap, the span is
+    /// split into non-overlapping segments, each carrying the value that won. The
+    /// mark operations that produced them are not recoverable from this view.
     ///
     /// # Arguments
     ///
     /// * `path` - Path to the text object
+    /// * `name` - Only return marks with this name; `None` returns every mark
     ///
     /// # Returns
     ///
@@ -2998,14 +6889,17 @@ impl RedisAutomergeClient {
     /// client.put_text("doc", "Hello World").unwrap();
     /// client.create_mark("doc", "bold", true.into(), 6, 11, ExpandMark::None).unwrap();
     ///
-    /// let marks = client.get_marks("doc").unwrap();
+    /// let marks = client.get_marks("doc", None).unwrap();
     /// // Returns: vec![("bold", ScalarValue::Boolean(true), 6, 11)]
+    /// let comments = client.get_marks("doc", Some("comment")).unwrap();
+    /// // Returns: vec![]
     /// ```
     pub fn get_marks(
         &self,
         path: &str,
-    ) -> Result<Vec<(String, ScalarValue, usize, usize)>, AutomergeError> {
-        let segments = parse_path(path)?;
+        name: Option<&str>,
+    ) -> Result<Vec<TextMark>, AutomergeError> {
+        let segments = parse_path(&self.doc, path)?;
 
         let text_obj = if segments.is_empty() {
             ROOT
@@ -3019,6 +6913,7 @@ impl RedisAutomergeClient {
         let marks = self.doc.marks(&text_obj)?;
         let result = marks
             .into_iter()
+            .filter(|m| name.is_none_or(|name| m.name() == name))
             .map(|m| {
                 (
                     m.name().to_string(),
@@ -3044,19 +6939,32 @@ impl RedisAutomergeExt for RedisAutomergeClient {
         Ok(Self {
             doc,
             aof: Vec::new(),
+            text_as_object: false,
+            actor_pinned: false,
+            cached_save: Mutex::new(None),
         })
     }
 
     fn save(&self) -> Vec<u8> {
-        self.doc.save()
+        self.with_saved(<[u8]>::to_vec)
     }
 
-    fn apply(&mut self, changes: Vec<Change>) -> Result<(), AutomergeError> {
+    fn apply(&mut self, changes: Vec<Change>) -> Result<Vec<ChangeHash>, AutomergeError> {
         for change in &changes {
-            self.aof.push(change.raw_bytes().to_vec());
+            let change_bytes = change.raw_bytes().to_vec();
+            self.record_change(change_bytes);
         }
+        let before = self.doc.get_heads();
         self.doc.apply_changes(changes)?;
-        Ok(())
+        if self.doc.get_heads() == before {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .doc
+            .get_changes(&before)
+            .iter()
+            .map(|change| change.hash())
+            .collect())
     }
 
     fn commands(&mut self) -> Vec<Vec<u8>> {
@@ -3,16 +3,113 @@
 ///! This module provides functionality to automatically sync Automerge document fields
 ///! to Valkey Hashes or JSON documents that can be indexed by search engines.
 
+use crate::config::ModuleConfig;
 use crate::ext::{RedisAutomergeClient, TypedValue};
 use valkey_module::{Context, ValkeyError, ValkeyResult, ValkeyString, ValkeyValue};
 use serde_json::{Map, Value as JsonValue};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, RwLock};
 
-/// Prefix for index configuration keys
-const INDEX_CONFIG_PREFIX: &str = "am:index:config:";
+/// Key prefixes of shadow documents and index configurations
+///
+/// Set with the `automerge.index-prefix` and `automerge.index-config-prefix`
+/// module configs, which can only be given when the module is loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPrefixes {
+    /// Prefix of shadow documents (`am:idx:` by default)
+    pub index: String,
+    /// Prefix of configuration keys (`am:index:config:` by default)
+    pub config: String,
+}
+
+impl KeyPrefixes {
+    /// The prefixes the module was loaded with
+    pub fn current() -> Self {
+        let config = ModuleConfig::current();
+        Self {
+            index: config.index_prefix,
+            config: config.index_config_prefix,
+        }
+    }
+
+    /// Shadow document key of a document
+    pub fn index_key(&self, am_key: &str) -> String {
+        format!("{}{}", self.index, am_key)
+    }
+
+    /// Key storing the configuration of `pattern`
+    pub fn config_key(&self, pattern: &str) -> String {
+        format!("{}{}", self.config, pattern)
+    }
+
+    /// `SCAN` pattern for the shadow documents of keys matching `pattern`
+    pub fn index_scan_pattern(&self, pattern: &str) -> String {
+        format!("{}{}", escape_glob(&self.index), pattern)
+    }
+
+    /// `SCAN` pattern for configuration keys whose pattern matches `pattern`
+    pub fn config_scan_pattern(&self, pattern: &str) -> String {
+        format!("{}{}", escape_glob(&self.config), pattern)
+    }
+
+    /// Document key a shadow document belongs to
+    pub fn document_key<'a>(&self, index_key: &'a str) -> Option<&'a str> {
+        index_key.strip_prefix(self.index.as_str())
+    }
+
+    /// Pattern stored under a configuration key
+    pub fn config_pattern<'a>(&self, config_key: &'a str) -> Option<&'a str> {
+        config_key.strip_prefix(self.config.as_str())
+    }
+
+    /// Whether a key belongs to the indexing machinery itself (a shadow
+    /// document or an index configuration) rather than to a user document
+    pub fn is_index_key(&self, key: &str) -> bool {
+        key.starts_with(self.index.as_str()) || self.is_config_key(key)
+    }
+
+    /// Whether a key stores an index configuration
+    pub fn is_config_key(&self, key: &str) -> bool {
+        key.starts_with(self.config.as_str())
+    }
+}
+
+impl Default for KeyPrefixes {
+    fn default() -> Self {
+        Self {
+            index: crate::config::DEFAULT_INDEX_PREFIX.to_string(),
+            config: crate::config::DEFAULT_INDEX_CONFIG_PREFIX.to_string(),
+        }
+    }
+}
+
+/// Escape the glob characters of a literal key prefix for use in `SCAN MATCH`
+fn escape_glob(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
 
-/// Prefix for shadow Hash keys
-const INDEX_KEY_PREFIX: &str = "am:idx:";
+/// Default separator for joining list values into a Hash field (the TAG field default)
+pub const DEFAULT_LIST_SEPARATOR: &str = ",";
+
+/// `COUNT` of the single `SCAN` step `AM.INDEX.STATUS` samples matching keys from
+pub const ESTIMATE_SCAN_COUNT: i64 = 1000;
+
+/// Index configurations by database and pattern, so writes can find theirs
+/// without calling Valkey
+///
+/// Configurations are stored in the keyspace, which isn't loaded yet when the
+/// module initializes, so each database's entry is filled by its first lookup.
+/// It is dropped again whenever a configuration key in that database changes,
+/// and when the database is flushed, swapped or loaded.
+static CONFIG_CACHE: LazyLock<RwLock<HashMap<i32, HashMap<String, IndexConfig>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
 
 /// Format for shadow index documents
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,7 +129,7 @@ impl IndexFormat {
         }
     }
 
-    fn as_str(&self) -> &str {
+    fn as_str(&self) -> &'static str {
         match self {
             IndexFormat::Hash => "hash",
             IndexFormat::Json => "json",
@@ -41,7 +138,7 @@ impl IndexFormat {
 }
 
 /// Configuration for indexing a key pattern
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IndexConfig {
     /// The key pattern (e.g., "article:*", "user:*")
     pub pattern: String,
@@ -51,6 +148,8 @@ pub struct IndexConfig {
     pub paths: Vec<String>,
     /// Format for shadow documents (hash or json)
     pub format: IndexFormat,
+    /// Separator for joining list values into a single Hash field
+    pub separator: String,
 }
 
 impl IndexConfig {
@@ -66,19 +165,20 @@ impl IndexConfig {
             enabled: true,
             paths,
             format,
+            separator: DEFAULT_LIST_SEPARATOR.to_string(),
         }
     }
 
     /// Get the Redis key for storing this configuration
     fn config_key(&self) -> String {
-        format!("{}{}", INDEX_CONFIG_PREFIX, self.pattern)
+        KeyPrefixes::current().config_key(&self.pattern)
     }
 
     /// Save configuration to Redis
     pub fn save(&self, ctx: &Context) -> ValkeyResult<()> {
         let key = ctx.create_string(self.config_key());
 
-        // Store as Hash with fields: enabled, paths, format
+        // Store as Hash with fields: enabled, paths, format, separator
         ctx.call(
             "HSET",
             &[
@@ -107,12 +207,22 @@ impl IndexConfig {
             ],
         )?;
 
+        ctx.call(
+            "HSET",
+            &[
+                &key,
+                &ctx.create_string("separator"),
+                &ctx.create_string(self.separator.as_str()),
+            ],
+        )?;
+
+        Self::invalidate_cache(Some(crate::selected_db(ctx)));
         Ok(())
     }
 
     /// Load configuration from Redis
     pub fn load(ctx: &Context, pattern: &str) -> ValkeyResult<Option<Self>> {
-        let key = ctx.create_string(format!("{}{}", INDEX_CONFIG_PREFIX, pattern));
+        let key = ctx.create_string(KeyPrefixes::current().config_key(pattern));
 
         // Check if key exists
         let exists_result = ctx.call("EXISTS", &[&key])?;
@@ -152,121 +262,337 @@ impl IndexConfig {
             _ => IndexFormat::Hash, // Default to Hash
         };
 
+        // Get separator field (default for configurations saved before it existed)
+        let separator_result = ctx.call("HGET", &[&key, &ctx.create_string("separator")])?;
+        let separator = match separator_result {
+            ValkeyValue::SimpleString(s) | ValkeyValue::BulkString(s) => s,
+            _ => DEFAULT_LIST_SEPARATOR.to_string(),
+        };
+
         Ok(Some(Self {
             pattern: pattern.to_string(),
             enabled,
             paths,
             format,
+            separator,
         }))
     }
 
+    /// Describe this configuration for `AM.INDEX.STATUS`, as field/value pairs
+    pub fn status_reply(&self, matched_keys_estimate: i64) -> ValkeyValue {
+        ValkeyValue::Array(vec![
+            ValkeyValue::SimpleStringStatic("pattern"),
+            ValkeyValue::BulkString(self.pattern.clone()),
+            ValkeyValue::SimpleStringStatic("enabled"),
+            ValkeyValue::Integer(if self.enabled { 1 } else { 0 }),
+            ValkeyValue::SimpleStringStatic("format"),
+            ValkeyValue::SimpleStringStatic(self.format.as_str()),
+            ValkeyValue::SimpleStringStatic("paths"),
+            ValkeyValue::Array(
+                self.paths
+                    .iter()
+                    .map(|path| ValkeyValue::BulkString(path.clone()))
+                    .collect(),
+            ),
+            ValkeyValue::SimpleStringStatic("separator"),
+            ValkeyValue::BulkString(self.separator.clone()),
+            ValkeyValue::SimpleStringStatic("matched_keys_estimate"),
+            ValkeyValue::Integer(matched_keys_estimate),
+        ])
+    }
+
+    /// Delete the configuration stored for `pattern`
+    ///
+    /// Returns `Ok(false)` if there was none.
+    pub fn delete(ctx: &Context, pattern: &str) -> ValkeyResult<bool> {
+        let key = ctx.create_string(KeyPrefixes::current().config_key(pattern));
+        let deleted = match ctx.call("DEL", &[&key])? {
+            ValkeyValue::Integer(n) => n > 0,
+            _ => return Err(ValkeyError::Str("Unexpected response from DEL")),
+        };
+        Self::invalidate_cache(Some(crate::selected_db(ctx)));
+        Ok(deleted)
+    }
+
     /// Find the configuration that matches a given key
+    ///
+    /// Served from the configuration cache, which is loaded on first use.
     pub fn find_matching_config(ctx: &Context, key: &str) -> ValkeyResult<Option<Self>> {
-        // Get all configuration keys
-        let pattern = format!("{}*", INDEX_CONFIG_PREFIX);
-        let result = ctx.call("KEYS", &[&ctx.create_string(pattern)])?;
-
-        // Handle the Array result
-        let config_keys: Vec<ValkeyString> = match result {
-            ValkeyValue::Array(keys) => keys
-                .into_iter()
-                .filter_map(|v| match v {
-                    ValkeyValue::BulkString(s) => Some(ctx.create_string(s)),
-                    ValkeyValue::SimpleString(s) => Some(ctx.create_string(s)),
-                    _ => None,
-                })
-                .collect(),
-            _ => return Ok(None),
-        };
+        let db = crate::selected_db(ctx);
+        if let Some(configs) = CONFIG_CACHE
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&db)
+        {
+            return Ok(Self::match_config(configs, key));
+        }
 
-        // Check each configuration to see if its pattern matches the key
-        for config_key in config_keys {
+        let configs = Self::load_all(ctx)?;
+        let config = Self::match_config(&configs, key);
+        CONFIG_CACHE
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(db, configs);
+        Ok(config)
+    }
+
+    /// Load every stored configuration, keyed by pattern
+    fn load_all(ctx: &Context) -> ValkeyResult<HashMap<String, Self>> {
+        let prefixes = KeyPrefixes::current();
+        let mut configs = HashMap::new();
+        for config_key in scan_all_keys(ctx, &prefixes.config_scan_pattern("*"))? {
             let config_key_str = config_key.to_string();
-            if let Some(pattern) = config_key_str.strip_prefix(INDEX_CONFIG_PREFIX) {
-                if Self::matches_pattern(key, pattern) {
-                    return Self::load(ctx, pattern);
+            if let Some(pattern) = prefixes.config_pattern(&config_key_str) {
+                if let Some(config) = Self::load(ctx, pattern)? {
+                    configs.insert(pattern.to_string(), config);
                 }
             }
         }
+        Ok(configs)
+    }
 
-        Ok(None)
+    /// Pick the configuration whose pattern matches `key`. If several match, the
+    /// lowest pattern in byte order wins so the choice doesn't depend on map order.
+    fn match_config(configs: &HashMap<String, Self>, key: &str) -> Option<Self> {
+        configs
+            .values()
+            .filter(|config| Self::matches_pattern(key, &config.pattern))
+            .min_by(|a, b| a.pattern.cmp(&b.pattern))
+            .cloned()
     }
 
-    /// Check if a key matches a pattern (supports * wildcard)
-    fn matches_pattern(key: &str, pattern: &str) -> bool {
-        // Simple wildcard matching (* matches any characters)
-        if pattern == "*" {
-            return true;
+    /// Drop the cached configurations of database `db`, or of every database when
+    /// `None`, so the next lookup reloads them
+    pub fn invalidate_cache(db: Option<i32>) {
+        let mut cache = CONFIG_CACHE
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match db {
+            Some(db) => {
+                cache.remove(&db);
+            }
+            None => cache.clear(),
         }
+    }
 
-        if !pattern.contains('*') {
-            return key == pattern;
-        }
+    /// Check if a key matches a pattern, with the glob syntax of `KEYS` and `SCAN`
+    fn matches_pattern(key: &str, pattern: &str) -> bool {
+        glob_match(pattern.as_bytes(), key.as_bytes())
+    }
+}
 
-        let parts: Vec<&str> = pattern.split('*').collect();
-        if parts.len() == 2 {
-            // Single wildcard: "prefix*" or "*suffix" or "prefix*suffix"
-            let prefix = parts[0];
-            let suffix = parts[1];
+/// Match `string` against a Redis-style glob `pattern`
+///
+/// Supports `*` (any run of bytes), `?` (any single byte), `[...]` classes with
+/// ranges and `^` negation, and `\` to escape the next byte.
+fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Pattern position after the most recent `*`, and where in `string` it resumes
+    let mut star: Option<(usize, usize)> = None;
 
-            if prefix.is_empty() {
-                return key.ends_with(suffix);
-            } else if suffix.is_empty() {
-                return key.starts_with(prefix);
-            } else {
-                return key.starts_with(prefix) && key.ends_with(suffix);
+    while s < string.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            star = Some((p, s));
+            continue;
+        }
+        if let Some(next) = match_token(pattern, p, string[s]) {
+            p = next;
+            s += 1;
+            continue;
+        }
+        // Mismatch: let the last `*` absorb one more byte and retry from there.
+        // Every other token consumes exactly one byte, so earlier stars never
+        // need to be revisited.
+        match star {
+            Some((star_p, star_s)) => {
+                p = star_p;
+                s = star_s + 1;
+                star = Some((star_p, s));
             }
+            None => return false,
         }
+    }
 
-        // Multiple wildcards - simplified matching
-        let mut key_pos = 0;
-        for (i, part) in parts.iter().enumerate() {
-            if part.is_empty() {
-                continue;
-            }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
 
-            if let Some(pos) = key[key_pos..].find(part) {
-                if i == 0 && pos != 0 {
-                    return false; // First part must match at start
-                }
-                key_pos += pos + part.len();
-            } else {
-                return false;
-            }
+/// Match the single-byte token at `pattern[p]` against `c`, returning the
+/// position after the token if it matches. Past the end nothing matches.
+fn match_token(pattern: &[u8], p: usize, c: u8) -> Option<usize> {
+    match *pattern.get(p)? {
+        b'?' => Some(p + 1),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        b'[' => match_class(pattern, p + 1, c),
+        literal => (literal == c).then_some(p + 1),
+    }
+}
+
+/// Match a `[...]` class whose contents start at `pattern[p]`. As in Redis, an
+/// unterminated class extends to the end of the pattern.
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    while let Some(&token) = pattern.get(p) {
+        if token == b']' {
+            p += 1;
+            break;
+        }
+        if token == b'\\' && p + 1 < pattern.len() {
+            matched |= pattern[p + 1] == c;
+            p += 2;
+        } else if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() {
+            // Ranges may be written in either order, `[a-z]` or `[z-a]`
+            let (start, end) = (token.min(pattern[p + 2]), token.max(pattern[p + 2]));
+            matched |= (start..=end).contains(&c);
+            p += 3;
+        } else {
+            matched |= token == c;
+            p += 1;
         }
+    }
+
+    (matched != negate).then_some(p)
+}
+
+/// Run one `SCAN` step over keys matching `pattern`
+///
+/// Returns the next cursor (`"0"` when the iteration is complete) and the
+/// keys found in this step, which may be empty even when more remain.
+pub fn scan_keys(
+    ctx: &Context,
+    cursor: &str,
+    pattern: &str,
+    count: i64,
+) -> ValkeyResult<(String, Vec<ValkeyString>)> {
+    let result = ctx.call(
+        "SCAN",
+        &[
+            &ctx.create_string(cursor),
+            &ctx.create_string("MATCH"),
+            &ctx.create_string(pattern),
+            &ctx.create_string("COUNT"),
+            &ctx.create_string(count.to_string()),
+        ],
+    )?;
+
+    let mut parts = match result {
+        ValkeyValue::Array(parts) if parts.len() == 2 => parts.into_iter(),
+        _ => return Err(ValkeyError::Str("Unexpected response from SCAN")),
+    };
+    let next_cursor = match parts.next() {
+        Some(ValkeyValue::BulkString(s)) | Some(ValkeyValue::SimpleString(s)) => s,
+        _ => return Err(ValkeyError::Str("Unexpected cursor from SCAN")),
+    };
+    let keys = match parts.next() {
+        Some(ValkeyValue::Array(keys)) => keys
+            .into_iter()
+            .filter_map(|v| match v {
+                ValkeyValue::BulkString(s) => Some(ctx.create_string(s)),
+                ValkeyValue::SimpleString(s) => Some(ctx.create_string(s)),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok((next_cursor, keys))
+}
 
-        // Last part must match at end
-        if let Some(last) = parts.last() {
-            if !last.is_empty() && !key.ends_with(last) {
-                return false;
+/// Collect every key matching `pattern` by iterating `SCAN` to completion
+///
+/// Unlike `KEYS` this doesn't block the server for the whole keyspace. Keys
+/// that `SCAN` reports more than once are only returned once.
+pub fn scan_all_keys(ctx: &Context, pattern: &str) -> ValkeyResult<Vec<ValkeyString>> {
+    let mut seen = HashSet::new();
+    let mut keys = Vec::new();
+    let mut cursor = "0".to_string();
+    loop {
+        let (next, batch) = scan_keys(ctx, &cursor, pattern, 100)?;
+        for key in batch {
+            if seen.insert(key.to_string()) {
+                keys.push(key);
             }
         }
-
-        true
+        if next == "0" {
+            return Ok(keys);
+        }
+        cursor = next;
     }
 }
 
 /// Extract configured paths from an Automerge document for Hash-based indexing
+///
+/// Text values are stored as-is. Ints, doubles, counters and timestamps (as Unix
+/// milliseconds) are stored as decimal strings so numeric range indexes work, and
+/// bools as `true`/`false`. A list of scalars is joined with `separator` into one
+/// field, and a map is flattened into one field per entry (`parent_child`).
 pub fn extract_indexed_fields(
     client: &RedisAutomergeClient,
     paths: &[String],
+    separator: &str,
 ) -> HashMap<String, String> {
     let mut fields = HashMap::new();
 
     for path in paths {
         // Try to get the value at this path
-        if let Ok(Some(value)) = client.get_text(path) {
-            // For nested paths, flatten with underscores for Hash field names
-            let field_name = path.replace('.', "_").replace('[', "_").replace(']', "");
-            fields.insert(field_name, value);
-        }
-        // Could also handle other types (int, bool, etc.) by converting to string
-        // For now, focus on text fields for full-text search
+        let value = match client.get_typed_value(path) {
+            Ok(Some(value)) => value,
+            _ => continue,
+        };
+        // For nested paths, flatten with underscores for Hash field names
+        let field_name = path.replace('.', "_").replace('[', "_").replace(']', "");
+        insert_hash_fields(&mut fields, field_name, &value, separator);
     }
 
     fields
 }
 
+/// Insert a value under `field_name`, flattening maps into `field_name_key` fields
+fn insert_hash_fields(
+    fields: &mut HashMap<String, String>,
+    field_name: String,
+    value: &TypedValue,
+    separator: &str,
+) {
+    match value {
+        TypedValue::Object(map) => {
+            for (key, nested) in map {
+                insert_hash_fields(fields, format!("{}_{}", field_name, key), nested, separator);
+            }
+        }
+        TypedValue::Array(items) => {
+            // Only scalar items can be searched as tokens; nested objects are skipped
+            let values: Vec<String> = items.iter().filter_map(hash_field_value).collect();
+            if !values.is_empty() {
+                fields.insert(field_name, values.join(separator));
+            }
+        }
+        _ => {
+            if let Some(value) = hash_field_value(value) {
+                fields.insert(field_name, value);
+            }
+        }
+    }
+}
+
+/// Stringify a scalar for a Hash field, or None for values that can't be indexed
+fn hash_field_value(value: &TypedValue) -> Option<String> {
+    match value {
+        TypedValue::Text(s) => Some(s.clone()),
+        TypedValue::Int(i) | TypedValue::Counter(i) | TypedValue::Timestamp(i) => {
+            Some(i.to_string())
+        }
+        TypedValue::Double(f) => Some(f.to_string()),
+        TypedValue::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 /// Build a JSON document from configured paths for RedisJSON-based indexing
 ///
 /// This extracts values from the Automerge document at the specified paths and
@@ -345,7 +671,95 @@ fn insert_nested_value(root: &mut Map<String, JsonValue>, segments: &[&str], val
 
 /// Get the index key for a given Automerge key
 pub fn get_index_key(am_key: &str) -> String {
-    format!("{}{}", INDEX_KEY_PREFIX, am_key)
+    KeyPrefixes::current().index_key(am_key)
+}
+
+/// What a document's shadow index should contain, computed from the document
+/// when it is written
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShadowDocument {
+    /// Hash fields, for `IndexFormat::Hash`
+    Hash(HashMap<String, String>),
+    /// A RedisJSON document, for `IndexFormat::Json`
+    Json(JsonValue),
+    /// None of the configured paths has a value, so the shadow index is removed
+    Empty,
+}
+
+/// Compute the shadow document of `client` for `config`
+///
+/// This only reads the document, so the result can be written to the index
+/// later, outside the command that produced it.
+pub fn build_shadow_document(
+    client: &RedisAutomergeClient,
+    config: &IndexConfig,
+) -> ShadowDocument {
+    match config.format {
+        IndexFormat::Json => match build_json_document(client, &config.paths) {
+            Some(doc) => ShadowDocument::Json(doc),
+            None => ShadowDocument::Empty,
+        },
+        IndexFormat::Hash => {
+            let fields = extract_indexed_fields(client, &config.paths, &config.separator);
+            if fields.is_empty() {
+                ShadowDocument::Empty
+            } else {
+                ShadowDocument::Hash(fields)
+            }
+        }
+    }
+}
+
+/// Replace the shadow index of `am_key` with `shadow`
+///
+/// Returns `Ok(true)` if an index was written, `Ok(false)` if it was removed
+/// because no fields were indexed.
+pub fn write_shadow_document(
+    ctx: &Context,
+    am_key: &str,
+    shadow: &ShadowDocument,
+) -> ValkeyResult<bool> {
+    let index_key = ctx.create_string(get_index_key(am_key));
+    match shadow {
+        ShadowDocument::Empty => {
+            // No fields to index - delete the index if it exists
+            ctx.call("DEL", &[&index_key])?;
+            Ok(false)
+        }
+        ShadowDocument::Json(doc) => {
+            // Serialize JSON to string
+            let json_str = serde_json::to_string(doc)
+                .map_err(|e| ValkeyError::String(format!("Failed to serialize JSON: {}", e)))?;
+
+            // Store as RedisJSON document
+            ctx.call(
+                "JSON.SET",
+                &[
+                    &index_key,
+                    &ctx.create_string("$"),
+                    &ctx.create_string(json_str),
+                ],
+            )?;
+            Ok(true)
+        }
+        ShadowDocument::Hash(fields) => {
+            // Delete existing Hash first to ensure clean state
+            ctx.call("DEL", &[&index_key])?;
+
+            // Set each field
+            for (field, value) in fields {
+                ctx.call(
+                    "HSET",
+                    &[
+                        &index_key,
+                        &ctx.create_string(field.clone()),
+                        &ctx.create_string(value.clone()),
+                    ],
+                )?;
+            }
+            Ok(true)
+        }
+    }
 }
 
 /// Update the JSON search index for a given Automerge key
@@ -369,101 +783,122 @@ pub fn update_json_index(
     client: &RedisAutomergeClient,
     config: &IndexConfig,
 ) -> ValkeyResult<bool> {
-    // Build JSON document from configured paths
-    let json_doc = match build_json_document(client, &config.paths) {
-        Some(doc) => doc,
-        None => {
-            // No fields to index - delete the index if it exists
-            let index_key = get_index_key(am_key);
-            ctx.call("DEL", &[&ctx.create_string(index_key)])?;
-            return Ok(false);
-        }
+    let shadow = match build_json_document(client, &config.paths) {
+        Some(doc) => ShadowDocument::Json(doc),
+        None => ShadowDocument::Empty,
     };
-
-    // Serialize JSON to string
-    let json_str = serde_json::to_string(&json_doc)
-        .map_err(|e| ValkeyError::String(format!("Failed to serialize JSON: {}", e)))?;
-
-    // Store as RedisJSON document
-    let index_key = get_index_key(am_key);
-    ctx.call(
-        "JSON.SET",
-        &[
-            &ctx.create_string(index_key),
-            &ctx.create_string("$"),
-            &ctx.create_string(json_str),
-        ],
-    )?;
-
-    Ok(true)
+    write_shadow_document(ctx, am_key, &shadow)
 }
 
-/// Update the search index for a given Automerge key
+/// Compute the shadow document for a given Automerge key, along with the
+/// configuration it was built from
 ///
-/// This is the main entry point for index updates. It dispatches to either
-/// Hash-based or JSON-based indexing depending on the configured format.
-pub fn update_search_index(
+/// Returns `Ok(None)` if no enabled configuration covers the key.
+pub fn prepare_search_index(
     ctx: &Context,
     am_key: &str,
     client: &RedisAutomergeClient,
-) -> ValkeyResult<bool> {
-    // Find matching configuration
-    let config = match IndexConfig::find_matching_config(ctx, am_key)? {
-        Some(cfg) if cfg.enabled => cfg,
-        _ => return Ok(false), // No config or disabled
-    };
-
-    // Dispatch based on configured format
-    match config.format {
-        IndexFormat::Json => update_json_index(ctx, am_key, client, &config),
-        IndexFormat::Hash => update_hash_index(ctx, am_key, client, &config),
+) -> ValkeyResult<Option<(IndexConfig, ShadowDocument)>> {
+    match IndexConfig::find_matching_config(ctx, am_key)? {
+        Some(config) if config.enabled => {
+            let shadow = build_shadow_document(client, &config);
+            Ok(Some((config, shadow)))
+        }
+        _ => Ok(None), // No config or disabled
     }
 }
 
-/// Update the Hash-based search index for a given Automerge key
-fn update_hash_index(
+/// Update the search index for a given Automerge key
+///
+/// This is the main entry point for index updates. It writes either a Hash or
+/// a JSON document depending on the configured format.
+pub fn update_search_index(
     ctx: &Context,
     am_key: &str,
     client: &RedisAutomergeClient,
-    config: &IndexConfig,
 ) -> ValkeyResult<bool> {
-    // Extract configured fields
-    let fields = extract_indexed_fields(client, &config.paths);
-
-    if fields.is_empty() {
-        // No fields to index - delete the index Hash
-        let index_key = get_index_key(am_key);
-        ctx.call("DEL", &[&ctx.create_string(index_key)])?;
-        return Ok(false);
+    match prepare_search_index(ctx, am_key, client)? {
+        Some((_, shadow)) => write_shadow_document(ctx, am_key, &shadow),
+        None => Ok(false),
     }
+}
 
-    // Update Hash with extracted fields
+/// Delete the search index Hash for a given Automerge key
+pub fn delete_search_index(ctx: &Context, am_key: &str) -> ValkeyResult<()> {
     let index_key = get_index_key(am_key);
-    let index_key_rs = ctx.create_string(index_key.clone());
+    ctx.call("DEL", &[&ctx.create_string(index_key)])?;
+    Ok(())
+}
 
-    // Delete existing Hash first to ensure clean state
-    ctx.call("DEL", &[&index_key_rs])?;
+/// Delete the shadow documents of keys matching `pattern`
+///
+/// Shadow documents are found by scanning their own namespace, so those whose
+/// document no longer exists are removed too. A shadow document is kept while
+/// its key is still covered by another configuration. Returns how many were
+/// deleted.
+pub fn purge_search_indexes(ctx: &Context, pattern: &str) -> ValkeyResult<i64> {
+    let prefixes = KeyPrefixes::current();
+    let mut removed = 0;
+    for index_key in scan_all_keys(ctx, &prefixes.index_scan_pattern(pattern))? {
+        let index_key_str = index_key.to_string();
+        let Some(am_key) = prefixes.document_key(&index_key_str) else {
+            continue;
+        };
+        if IndexConfig::find_matching_config(ctx, am_key)?.is_some() {
+            continue;
+        }
+        if let ValkeyValue::Integer(n) = ctx.call("DEL", &[&index_key])? {
+            removed += n;
+        }
+    }
+    Ok(removed)
+}
 
-    // Set each field
-    for (field, value) in &fields {
-        ctx.call(
-            "HSET",
-            &[
-                &index_key_rs,
-                &ctx.create_string(field.clone()),
-                &ctx.create_string(value.clone()),
-            ],
-        )?;
+/// Estimate the documents matching `pattern`, skipping the module's own index keys
+///
+/// Takes a single `SCAN` step of [`ESTIMATE_SCAN_COUNT`], so the cost doesn't grow
+/// with the keyspace. If that step covers every key the count is exact; otherwise
+/// the share of sampled keys that match is scaled up to `DBSIZE`.
+pub fn estimate_matching_keys(ctx: &Context, pattern: &str) -> ValkeyResult<i64> {
+    let prefixes = KeyPrefixes::current();
+    let (cursor, sample) = scan_keys(ctx, "0", "*", ESTIMATE_SCAN_COUNT)?;
+    let matched = sample
+        .iter()
+        .map(|key| key.to_string())
+        .filter(|key| IndexConfig::matches_pattern(key, pattern) && !prefixes.is_index_key(key))
+        .count() as i64;
+    if cursor == "0" || sample.is_empty() {
+        return Ok(matched);
     }
 
-    Ok(true)
+    let total = match ctx.call("DBSIZE", &[] as &[&str])? {
+        ValkeyValue::Integer(n) => n,
+        _ => return Err(ValkeyError::Str("Unexpected response from DBSIZE")),
+    };
+    Ok(matched * total / sample.len() as i64)
 }
 
-/// Delete the search index Hash for a given Automerge key
-pub fn delete_search_index(ctx: &Context, am_key: &str) -> ValkeyResult<()> {
-    let index_key = get_index_key(am_key);
-    ctx.call("DEL", &[&ctx.create_string(index_key)])?;
-    Ok(())
+/// Whether a key belongs to the indexing machinery itself (a shadow
+/// document or an index configuration) rather than to a user document
+pub fn is_index_key(key: &str) -> bool {
+    KeyPrefixes::current().is_index_key(key)
+}
+
+/// Whether a key stores an index configuration
+pub fn is_config_key(key: &str) -> bool {
+    KeyPrefixes::current().is_config_key(key)
+}
+
+/// Delete the shadow index of a removed document if its key matches a
+/// configured pattern, enabled or not
+///
+/// Returns `Ok(true)` if a matching configuration was found.
+pub fn remove_search_index(ctx: &Context, am_key: &str) -> ValkeyResult<bool> {
+    if is_index_key(am_key) || IndexConfig::find_matching_config(ctx, am_key)?.is_none() {
+        return Ok(false);
+    }
+    delete_search_index(ctx, am_key)?;
+    Ok(true)
 }
 
 #[cfg(test)]
@@ -485,4 +920,300 @@ mod tests {
         assert_eq!(get_index_key("article:123"), "am:idx:article:123");
         assert_eq!(get_index_key("user:abc"), "am:idx:user:abc");
     }
+
+    #[test]
+    fn test_extract_numeric_and_bool_fields() {
+        let mut client = RedisAutomergeClient::new();
+        client.put_text("title", "Hello").unwrap();
+        client.put_int("meta.views", 42).unwrap();
+        client.put_bool("published", true).unwrap();
+        client.put_bool("draft", false).unwrap();
+        client.put_double("score", 4.5).unwrap();
+        client.put_counter("likes", 7).unwrap();
+        client.put_timestamp("created", 1700000000000).unwrap();
+        client.put_null("missing_value").unwrap();
+
+        let paths: Vec<String> = [
+            "title",
+            "meta.views",
+            "published",
+            "draft",
+            "score",
+            "likes",
+            "created",
+            "missing_value",
+            "absent",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        let fields = extract_indexed_fields(&client, &paths, DEFAULT_LIST_SEPARATOR);
+
+        assert_eq!(fields.get("title").map(String::as_str), Some("Hello"));
+        assert_eq!(fields.get("meta_views").map(String::as_str), Some("42"));
+        assert_eq!(fields.get("published").map(String::as_str), Some("true"));
+        assert_eq!(fields.get("draft").map(String::as_str), Some("false"));
+        assert_eq!(fields.get("score").map(String::as_str), Some("4.5"));
+        assert_eq!(fields.get("likes").map(String::as_str), Some("7"));
+        assert_eq!(
+            fields.get("created").map(String::as_str),
+            Some("1700000000000")
+        );
+        assert!(!fields.contains_key("missing_value"));
+        assert!(!fields.contains_key("absent"));
+        assert_eq!(fields.len(), 7);
+    }
+
+    #[test]
+    fn test_extract_list_joined_with_separator() {
+        let mut client = RedisAutomergeClient::new();
+        client.create_list("tags").unwrap();
+        client.append_text("tags", "rust").unwrap();
+        client.append_text("tags", "redis").unwrap();
+        client.append_text("tags", "crdt").unwrap();
+        client.create_list("ratings").unwrap();
+        client.append_int("ratings", 4).unwrap();
+        client.append_int("ratings", 5).unwrap();
+        client.create_list("empty").unwrap();
+
+        let paths = vec![
+            "tags".to_string(),
+            "ratings".to_string(),
+            "empty".to_string(),
+        ];
+        let fields = extract_indexed_fields(&client, &paths, DEFAULT_LIST_SEPARATOR);
+        assert_eq!(
+            fields.get("tags").map(String::as_str),
+            Some("rust,redis,crdt")
+        );
+        assert_eq!(fields.get("ratings").map(String::as_str), Some("4,5"));
+        assert!(!fields.contains_key("empty"));
+
+        let fields = extract_indexed_fields(&client, &paths, " ");
+        assert_eq!(
+            fields.get("tags").map(String::as_str),
+            Some("rust redis crdt")
+        );
+    }
+
+    #[test]
+    fn test_extract_nested_map_flattens_fields() {
+        let mut client = RedisAutomergeClient::new();
+        client.put_text("author.name", "Alice").unwrap();
+        client.put_int("author.age", 30).unwrap();
+        client.put_text("author.address.city", "Paris").unwrap();
+        client.create_list("author.langs").unwrap();
+        client.append_text("author.langs", "en").unwrap();
+        client.append_text("author.langs", "fr").unwrap();
+
+        let fields = extract_indexed_fields(&client, &["author".to_string()], ",");
+        assert_eq!(fields.get("author_name").map(String::as_str), Some("Alice"));
+        assert_eq!(fields.get("author_age").map(String::as_str), Some("30"));
+        assert_eq!(
+            fields.get("author_address_city").map(String::as_str),
+            Some("Paris")
+        );
+        assert_eq!(
+            fields.get("author_langs").map(String::as_str),
+            Some("en,fr")
+        );
+        assert_eq!(fields.len(), 4);
+    }
+
+    #[test]
+    fn test_is_index_key() {
+        assert!(is_index_key("am:idx:article:123"));
+        assert!(is_index_key("am:index:config:article:*"));
+        assert!(!is_index_key("article:123"));
+        assert!(!is_index_key("am:article"));
+    }
+
+    #[test]
+    fn test_match_config_picks_first_matching_pattern() {
+        let mut configs = HashMap::new();
+        for pattern in ["user:*", "article:*", "article:draft:*"] {
+            configs.insert(
+                pattern.to_string(),
+                IndexConfig::new(pattern.to_string(), vec!["title".to_string()]),
+            );
+        }
+
+        let config = IndexConfig::match_config(&configs, "article:draft:1").unwrap();
+        assert_eq!(config.pattern, "article:*");
+        let config = IndexConfig::match_config(&configs, "user:42").unwrap();
+        assert_eq!(config.pattern, "user:*");
+        assert!(IndexConfig::match_config(&configs, "post:1").is_none());
+        assert!(IndexConfig::match_config(&HashMap::new(), "article:1").is_none());
+    }
+
+    #[test]
+    fn test_pattern_single_char_wildcard() {
+        assert!(IndexConfig::matches_pattern("user:1", "user:?"));
+        assert!(!IndexConfig::matches_pattern("user:12", "user:?"));
+        assert!(!IndexConfig::matches_pattern("user:", "user:?"));
+        assert!(IndexConfig::matches_pattern("user:12", "user:??"));
+        assert!(IndexConfig::matches_pattern("a:1:b", "?:*:?"));
+    }
+
+    #[test]
+    fn test_pattern_character_classes() {
+        assert!(IndexConfig::matches_pattern("hello", "h[ae]llo"));
+        assert!(IndexConfig::matches_pattern("hallo", "h[ae]llo"));
+        assert!(!IndexConfig::matches_pattern("hillo", "h[ae]llo"));
+        assert!(IndexConfig::matches_pattern("hbllo", "h[a-c]llo"));
+        assert!(IndexConfig::matches_pattern("hbllo", "h[c-a]llo"));
+        assert!(!IndexConfig::matches_pattern("hdllo", "h[a-c]llo"));
+        assert!(IndexConfig::matches_pattern("hillo", "h[^e]llo"));
+        assert!(!IndexConfig::matches_pattern("hello", "h[^e]llo"));
+        assert!(IndexConfig::matches_pattern("doc:7", "doc:[0-9]"));
+        assert!(!IndexConfig::matches_pattern("doc:x", "doc:[0-9]"));
+        // Escapes inside and outside classes
+        assert!(IndexConfig::matches_pattern("a]b", "a[\\]]b"));
+        assert!(IndexConfig::matches_pattern("what?", "what\\?"));
+        assert!(!IndexConfig::matches_pattern("whats", "what\\?"));
+        assert!(IndexConfig::matches_pattern("a*b", "a\\*b"));
+        assert!(!IndexConfig::matches_pattern("axb", "a\\*b"));
+    }
+
+    #[test]
+    fn test_pattern_multiple_wildcards() {
+        assert!(IndexConfig::matches_pattern("abc", "a*b*c"));
+        assert!(IndexConfig::matches_pattern("aXbYc", "a*b*c"));
+        assert!(IndexConfig::matches_pattern("abbbc", "a*b*c"));
+        assert!(!IndexConfig::matches_pattern("acb", "a*b*c"));
+        assert!(!IndexConfig::matches_pattern("abcd", "a*b*c"));
+        assert!(IndexConfig::matches_pattern(
+            "tenant:1:user:2",
+            "tenant:*:user:*"
+        ));
+        assert!(IndexConfig::matches_pattern("anything", "**"));
+        assert!(IndexConfig::matches_pattern("", "*"));
+    }
+
+    #[test]
+    fn test_pattern_overlapping_literals() {
+        // Prefix and suffix may not share bytes of the key
+        assert!(!IndexConfig::matches_pattern("aba", "ab*ba"));
+        assert!(IndexConfig::matches_pattern("abba", "ab*ba"));
+        // An early greedy match of "b" must not strand the later parts
+        assert!(IndexConfig::matches_pattern("abxbc", "a*bc"));
+        assert!(IndexConfig::matches_pattern("abcbc", "a*b*c"));
+        assert!(IndexConfig::matches_pattern("aaab", "*aab"));
+        assert!(!IndexConfig::matches_pattern("aab", "a*aab"));
+    }
+
+    #[test]
+    fn test_status_reply_reports_format() {
+        let hash = IndexConfig::new("article:*".to_string(), vec!["title".to_string()]);
+        let mut json = IndexConfig::new_with_format(
+            "product:*".to_string(),
+            vec!["name".to_string(), "price".to_string()],
+            IndexFormat::Json,
+        );
+        json.enabled = false;
+
+        assert_eq!(
+            hash.status_reply(2),
+            ValkeyValue::Array(vec![
+                ValkeyValue::SimpleStringStatic("pattern"),
+                ValkeyValue::BulkString("article:*".to_string()),
+                ValkeyValue::SimpleStringStatic("enabled"),
+                ValkeyValue::Integer(1),
+                ValkeyValue::SimpleStringStatic("format"),
+                ValkeyValue::SimpleStringStatic("hash"),
+                ValkeyValue::SimpleStringStatic("paths"),
+                ValkeyValue::Array(vec![ValkeyValue::BulkString("title".to_string())]),
+                ValkeyValue::SimpleStringStatic("separator"),
+                ValkeyValue::BulkString(",".to_string()),
+                ValkeyValue::SimpleStringStatic("matched_keys_estimate"),
+                ValkeyValue::Integer(2),
+            ])
+        );
+
+        let ValkeyValue::Array(fields) = json.status_reply(0) else {
+            panic!("status should be an array");
+        };
+        assert_eq!(fields[3], ValkeyValue::Integer(0));
+        assert_eq!(fields[5], ValkeyValue::SimpleStringStatic("json"));
+        assert_eq!(
+            fields[7],
+            ValkeyValue::Array(vec![
+                ValkeyValue::BulkString("name".to_string()),
+                ValkeyValue::BulkString("price".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_custom_key_prefixes() {
+        let prefixes = KeyPrefixes {
+            index: "tenant[1]:idx:".to_string(),
+            config: "tenant[1]:cfg:".to_string(),
+        };
+
+        // Write: the shadow document lives under the index prefix
+        let index_key = prefixes.index_key("article:1");
+        assert_eq!(index_key, "tenant[1]:idx:article:1");
+        assert_eq!(prefixes.document_key(&index_key), Some("article:1"));
+
+        // Reindex and delete handling: both namespaces are recognized as index keys
+        assert!(prefixes.is_index_key(&index_key));
+        assert!(prefixes.is_index_key("tenant[1]:cfg:article:*"));
+        assert!(!prefixes.is_index_key("am:idx:article:1"));
+        assert!(!prefixes.is_config_key("am:index:config:article:*"));
+
+        // Status and purge: SCAN patterns match the keys built above, with the
+        // prefix's glob characters escaped
+        let config_key = prefixes.config_key("article:*");
+        assert_eq!(prefixes.config_pattern(&config_key), Some("article:*"));
+        assert!(glob_match(
+            prefixes.config_scan_pattern("*").as_bytes(),
+            config_key.as_bytes()
+        ));
+        assert!(glob_match(
+            prefixes.index_scan_pattern("article:*").as_bytes(),
+            index_key.as_bytes()
+        ));
+        assert!(!glob_match(
+            prefixes.index_scan_pattern("article:*").as_bytes(),
+            b"tenant1:idx:article:1"
+        ));
+    }
+
+    #[test]
+    fn test_default_key_prefixes() {
+        assert_eq!(KeyPrefixes::current(), KeyPrefixes::default());
+        let prefixes = KeyPrefixes::default();
+        assert_eq!(prefixes.index_key("doc"), "am:idx:doc");
+        assert_eq!(prefixes.config_key("doc:*"), "am:index:config:doc:*");
+        assert_eq!(prefixes.config_scan_pattern("*"), "am:index:config:*");
+    }
+
+    #[test]
+    fn test_build_shadow_document_follows_format() {
+        let mut client = RedisAutomergeClient::new();
+        client.put_text("title", "Hello").unwrap();
+        client.put_int("meta.views", 42).unwrap();
+        let paths = vec!["title".to_string(), "meta.views".to_string()];
+
+        let hash = IndexConfig::new("article:*".to_string(), paths.clone());
+        let ShadowDocument::Hash(fields) = build_shadow_document(&client, &hash) else {
+            panic!("expected a Hash shadow document");
+        };
+        assert_eq!(fields.get("title").map(String::as_str), Some("Hello"));
+        assert_eq!(fields.get("meta_views").map(String::as_str), Some("42"));
+
+        let json = IndexConfig::new_with_format("article:*".to_string(), paths, IndexFormat::Json);
+        assert_eq!(
+            build_shadow_document(&client, &json),
+            ShadowDocument::Json(serde_json::json!({"title": "Hello", "meta": {"views": 42}}))
+        );
+
+        // A document without any configured path has nothing to index
+        let missing = IndexConfig::new("article:*".to_string(), vec!["body".to_string()]);
+        assert_eq!(
+            build_shadow_document(&client, &missing),
+            ShadowDocument::Empty
+        );
+    }
 }
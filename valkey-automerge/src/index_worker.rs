@@ -0,0 +1,265 @@
+//! Background search index maintenance (`automerge.index-async`).
+//!
+//! With the setting on, write commands compute the shadow document while they hold the
+//! key and queue it here instead of issuing the `HSET`/`JSON.SET` calls themselves. A
+//! single worker thread applies the queue in order under the global lock, so the index
+//! of each key ends up matching its latest write. Removals of deleted documents go
+//! through the same queue, so they can't be overtaken by an earlier update.
+//!
+//! Each job records the database of the command that queued it, which the worker
+//! selects before applying it. Jobs still queued for a database when it is flushed, or
+//! swapped with `SWAPDB`, are dropped; the worker only takes jobs from the queue once it
+//! holds the lock, so none can be applied to the keyspace that replaced them.
+//!
+//! An update also records the configuration it was computed from, and is skipped if
+//! that configuration has since been dropped, disabled or changed. Synchronous index
+//! writes, made once `automerge.index-async` is turned off or by `AM.INDEX.REINDEX`,
+//! cancel the jobs still queued for their key, which they supersede.
+
+use crate::index::{self, IndexConfig, ShadowDocument};
+use std::collections::HashSet;
+use std::sync::{Condvar, LazyLock, Mutex, MutexGuard, Once};
+use valkey_module::{raw, Context, ThreadSafeContext};
+
+/// A queued change to the shadow index of one key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexJob {
+    /// Replace the shadow index with the document computed at write time from
+    /// the given configuration
+    Update(i32, String, IndexConfig, ShadowDocument),
+    /// The document was deleted; remove its shadow index
+    Remove(i32, String),
+}
+
+impl IndexJob {
+    /// The database holding the document.
+    pub fn db(&self) -> i32 {
+        match self {
+            IndexJob::Update(db, _, _, _) | IndexJob::Remove(db, _) => *db,
+        }
+    }
+
+    /// The document key this job applies to.
+    pub fn key(&self) -> &str {
+        match self {
+            IndexJob::Update(_, key, _, _) | IndexJob::Remove(_, key) => key,
+        }
+    }
+
+    fn apply(&self, ctx: &Context) {
+        // The thread-safe context starts out on database 0
+        if unsafe { raw::RedisModule_SelectDb.unwrap()(ctx.ctx, self.db()) }
+            != raw::REDISMODULE_OK as i32
+        {
+            ctx.log_warning(&format!(
+                "Failed to select database {} to index {}",
+                self.db(),
+                self.key()
+            ));
+            return;
+        }
+        let result = match self {
+            IndexJob::Update(_, key, config, shadow) => {
+                // Skip the update if its configuration was dropped, disabled or
+                // changed after the write queued it
+                match IndexConfig::find_matching_config(ctx, key) {
+                    Ok(Some(current)) if current == *config => {
+                        index::write_shadow_document(ctx, key, shadow)
+                    }
+                    Ok(_) => Ok(false),
+                    Err(e) => Err(e),
+                }
+            }
+            IndexJob::Remove(_, key) => index::remove_search_index(ctx, key),
+        };
+        if let Err(e) = result {
+            ctx.log_warning(&format!(
+                "Failed to update search index for {}: {}",
+                self.key(),
+                e
+            ));
+        }
+    }
+}
+
+/// Drop the jobs that a later job on the same key supersedes.
+///
+/// Every job replaces the whole shadow index, so only the last one per key
+/// matters. The survivors keep their relative order.
+pub fn coalesce(jobs: Vec<IndexJob>) -> Vec<IndexJob> {
+    let mut seen = HashSet::new();
+    let mut latest: Vec<IndexJob> = jobs
+        .into_iter()
+        .rev()
+        .filter(|job| seen.insert((job.db(), job.key().to_string())))
+        .collect();
+    latest.reverse();
+    latest
+}
+
+/// Drop the jobs for database `db`, or for every database if `None`.
+pub fn discard(jobs: &mut Vec<IndexJob>, db: Option<i32>) {
+    jobs.retain(|job| db.is_some_and(|db| job.db() != db));
+}
+
+/// Drop the jobs for `key` in database `db`.
+pub fn discard_key(jobs: &mut Vec<IndexJob>, db: i32, key: &str) {
+    jobs.retain(|job| job.db() != db || job.key() != key);
+}
+
+/// Jobs waiting for the worker, which is started on first use.
+struct Queue {
+    jobs: Mutex<Vec<IndexJob>>,
+    ready: Condvar,
+    started: Once,
+}
+
+impl Queue {
+    fn jobs(&self) -> MutexGuard<'_, Vec<IndexJob>> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+static QUEUE: LazyLock<Queue> = LazyLock::new(|| Queue {
+    jobs: Mutex::new(Vec::new()),
+    ready: Condvar::new(),
+    started: Once::new(),
+});
+
+/// Queue a job for the worker thread, starting it if needed.
+pub fn enqueue(job: IndexJob) {
+    QUEUE.started.call_once(|| {
+        std::thread::spawn(run);
+    });
+    QUEUE.jobs().push(job);
+    QUEUE.ready.notify_one();
+}
+
+/// Drop the queued jobs of a flushed or swapped database, or of all of them if
+/// `None`.
+///
+/// Called while the flush or swap holds the lock, so the worker can't be
+/// applying them.
+pub fn clear_db(db: Option<i32>) {
+    discard(&mut QUEUE.jobs(), db);
+}
+
+/// Drop the queued jobs for `key` in database `db`, which a synchronous index
+/// write is about to supersede.
+///
+/// Called while the writer holds the lock, so the worker can't be applying them.
+pub fn cancel(db: i32, key: &str) {
+    discard_key(&mut QUEUE.jobs(), db, key);
+}
+
+/// Apply jobs as they arrive. Jobs queued while the worker waited for the lock
+/// are applied in the same batch.
+fn run() {
+    loop {
+        {
+            let mut jobs = QUEUE.jobs();
+            while jobs.is_empty() {
+                jobs = QUEUE
+                    .ready
+                    .wait(jobs)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        }
+
+        let thread_ctx = ThreadSafeContext::new();
+        let ctx = thread_ctx.lock();
+        // Taken only now, after any flush that ran while waiting for the lock
+        let jobs = std::mem::take(&mut *QUEUE.jobs());
+        for job in coalesce(jobs) {
+            job.apply(&ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(key: &str, title: &str) -> IndexJob {
+        let config = IndexConfig::new("*".to_string(), vec!["title".to_string()]);
+        let fields = [("title".to_string(), title.to_string())].into();
+        IndexJob::Update(0, key.to_string(), config, ShadowDocument::Hash(fields))
+    }
+
+    #[test]
+    fn test_coalesce_keeps_last_job_per_key_in_order() {
+        let jobs = vec![
+            update("a", "1"),
+            update("b", "1"),
+            update("a", "2"),
+            IndexJob::Remove(0, "c".to_string()),
+            update("b", "2"),
+            update("a", "3"),
+        ];
+        assert_eq!(
+            coalesce(jobs),
+            vec![
+                IndexJob::Remove(0, "c".to_string()),
+                update("b", "2"),
+                update("a", "3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_lets_removal_win_over_earlier_update() {
+        let jobs = vec![update("a", "1"), IndexJob::Remove(0, "a".to_string())];
+        assert_eq!(coalesce(jobs), vec![IndexJob::Remove(0, "a".to_string())]);
+
+        // A document recreated after the delete is indexed again
+        let jobs = vec![IndexJob::Remove(0, "a".to_string()), update("a", "2")];
+        assert_eq!(coalesce(jobs), vec![update("a", "2")]);
+    }
+
+    #[test]
+    fn test_coalesce_keeps_same_key_in_other_databases() {
+        let jobs = vec![
+            update("a", "1"),
+            IndexJob::Remove(1, "a".to_string()),
+            update("a", "2"),
+        ];
+        assert_eq!(
+            coalesce(jobs),
+            vec![IndexJob::Remove(1, "a".to_string()), update("a", "2")]
+        );
+    }
+
+    #[test]
+    fn test_discard_drops_jobs_of_flushed_database() {
+        let mut jobs = vec![
+            update("a", "1"),
+            IndexJob::Remove(1, "b".to_string()),
+            IndexJob::Remove(2, "c".to_string()),
+        ];
+        discard(&mut jobs, Some(1));
+        assert_eq!(
+            jobs,
+            vec![update("a", "1"), IndexJob::Remove(2, "c".to_string())]
+        );
+
+        discard(&mut jobs, None);
+        assert!(jobs.is_empty());
+    }
+
+    #[test]
+    fn test_discard_key_drops_only_that_key_in_that_database() {
+        let mut jobs = vec![
+            update("a", "1"),
+            IndexJob::Remove(1, "a".to_string()),
+            update("b", "1"),
+            IndexJob::Remove(0, "a".to_string()),
+        ];
+        discard_key(&mut jobs, 0, "a");
+        assert_eq!(
+            jobs,
+            vec![IndexJob::Remove(1, "a".to_string()), update("b", "1")]
+        );
+    }
+}
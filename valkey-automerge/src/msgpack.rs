@@ -0,0 +1,445 @@
+//! MessagePack encoding of typed document trees (`AM.TOMSGPACK`/`AM.FROMMSGPACK`).
+//!
+//! Unlike JSON, MessagePack keeps integers, doubles, byte strings and nulls apart,
+//! so a [`TypedValue`] tree survives the round trip. The two Automerge types that
+//! MessagePack has no native form for use extension types:
+//!
+//! - Timestamps use the standard timestamp extension (type `-1`); all three of its
+//!   widths are accepted on decode and the 96-bit form is written.
+//! - Counters use extension type [`COUNTER_EXT`] holding the value as a big-endian
+//!   `i64`.
+//!
+//! Map keys are written in sorted order, so equal documents encode to equal bytes.
+
+use crate::ext::TypedValue;
+
+/// Extension type of the standard MessagePack timestamp.
+const TIMESTAMP_EXT: i8 = -1;
+
+/// Application extension type used for Automerge counters.
+pub const COUNTER_EXT: i8 = 1;
+
+/// How deeply arrays and maps may nest in decoded data, to bound recursion.
+const MAX_DEPTH: usize = 64;
+
+/// Why MessagePack data couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The data ends in the middle of a value
+    Truncated,
+    /// More bytes follow the top-level value
+    TrailingBytes,
+    /// A marker byte that MessagePack doesn't define
+    InvalidMarker(u8),
+    /// A string that isn't valid UTF-8
+    InvalidUtf8,
+    /// A map key that isn't a string
+    NonStringKey,
+    /// A NaN or infinite double, which documents can't store
+    NonFiniteDouble,
+    /// An integer or timestamp outside the `i64` range
+    OutOfRange,
+    /// An extension type (or size) other than timestamps and counters
+    UnsupportedExtension(i8),
+    /// Arrays and maps nested deeper than [`MAX_DEPTH`]
+    TooDeep,
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "unexpected end of data"),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after the value"),
+            DecodeError::InvalidMarker(marker) => write!(f, "invalid marker 0x{:02x}", marker),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            DecodeError::NonStringKey => write!(f, "map key is not a string"),
+            DecodeError::NonFiniteDouble => write!(f, "double is NaN or infinite"),
+            DecodeError::OutOfRange => write!(f, "integer or timestamp out of range"),
+            DecodeError::UnsupportedExtension(ext_type) => {
+                write!(f, "unsupported extension type {}", ext_type)
+            }
+            DecodeError::TooDeep => write!(f, "nested deeper than {} levels", MAX_DEPTH),
+        }
+    }
+}
+
+/// Encode a typed value tree as MessagePack.
+pub fn encode(value: &TypedValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Decode MessagePack produced by [`encode`], or by any encoder using the same
+/// extension types. Trailing bytes after the value are rejected.
+pub fn decode(bytes: &[u8]) -> Result<TypedValue, DecodeError> {
+    let mut reader = Reader { bytes };
+    let value = reader.value(0)?;
+    if !reader.bytes.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(value)
+}
+
+fn write_value(out: &mut Vec<u8>, value: &TypedValue) {
+    match value {
+        TypedValue::Null => out.push(0xc0),
+        TypedValue::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        TypedValue::Int(i) => write_int(out, *i),
+        TypedValue::Double(f) => {
+            out.push(0xcb);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+        TypedValue::Text(s) => {
+            write_len(out, s.len(), Some(0xa0), [0xd9, 0xda, 0xdb]);
+            out.extend_from_slice(s.as_bytes());
+        }
+        TypedValue::Bytes(b) => {
+            write_len(out, b.len(), None, [0xc4, 0xc5, 0xc6]);
+            out.extend_from_slice(b);
+        }
+        TypedValue::Timestamp(ms) => {
+            // timestamp 96: nanoseconds as u32, then seconds as i64
+            let nanos = (ms.rem_euclid(1000) * 1_000_000) as u32;
+            out.extend_from_slice(&[0xc7, 12, TIMESTAMP_EXT as u8]);
+            out.extend_from_slice(&nanos.to_be_bytes());
+            out.extend_from_slice(&ms.div_euclid(1000).to_be_bytes());
+        }
+        TypedValue::Counter(c) => {
+            out.extend_from_slice(&[0xd7, COUNTER_EXT as u8]);
+            out.extend_from_slice(&c.to_be_bytes());
+        }
+        TypedValue::Array(items) => {
+            write_container_len(out, items.len(), 0x90, [0xdc, 0xdd]);
+            for item in items {
+                write_value(out, item);
+            }
+        }
+        TypedValue::Object(map) => {
+            write_container_len(out, map.len(), 0x80, [0xde, 0xdf]);
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, child) in entries {
+                write_value(out, &TypedValue::Text(key.clone()));
+                write_value(out, child);
+            }
+        }
+    }
+}
+
+/// Write an integer in its smallest encoding.
+fn write_int(out: &mut Vec<u8>, i: i64) {
+    match i {
+        0..=0x7f => out.push(i as u8),
+        -32..=-1 => out.push(i as i8 as u8),
+        0x80..=0xff => out.extend_from_slice(&[0xcc, i as u8]),
+        -128..=-33 => out.extend_from_slice(&[0xd0, i as i8 as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend_from_slice(&(i as u16).to_be_bytes());
+        }
+        -32768..=-129 => {
+            out.push(0xd1);
+            out.extend_from_slice(&(i as i16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend_from_slice(&(i as u32).to_be_bytes());
+        }
+        -2_147_483_648..=-32769 => {
+            out.push(0xd2);
+            out.extend_from_slice(&(i as i32).to_be_bytes());
+        }
+        _ if i > 0 => {
+            out.push(0xcf);
+            out.extend_from_slice(&(i as u64).to_be_bytes());
+        }
+        _ => {
+            out.push(0xd3);
+            out.extend_from_slice(&i.to_be_bytes());
+        }
+    }
+}
+
+/// Write a string or binary header: the fix form when it fits, then 8, 16 or
+/// 32-bit lengths.
+fn write_len(out: &mut Vec<u8>, len: usize, fix: Option<u8>, markers: [u8; 3]) {
+    match (fix, len) {
+        (Some(fix), 0..=31) => out.push(fix | len as u8),
+        (_, 0..=0xff) => out.extend_from_slice(&[markers[0], len as u8]),
+        (_, 0x100..=0xffff) => {
+            out.push(markers[1]);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(markers[2]);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+/// Write an array or map header: the fix form up to 15 entries, then 16 or
+/// 32-bit lengths.
+fn write_container_len(out: &mut Vec<u8>, len: usize, fix: u8, markers: [u8; 2]) {
+    match len {
+        0..=15 => out.push(fix | len as u8),
+        16..=0xffff => {
+            out.push(markers[0]);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(markers[1]);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+/// A decoded double, which like any document double must be finite.
+fn finite(value: f64) -> Result<TypedValue, DecodeError> {
+    if value.is_finite() {
+        Ok(TypedValue::Double(value))
+    } else {
+        Err(DecodeError::NonFiniteDouble)
+    }
+}
+
+/// Cursor over the bytes being decoded.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < n {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let mut buf = [0; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self, width: usize) -> Result<usize, DecodeError> {
+        Ok(match width {
+            1 => self.u8()? as usize,
+            2 => u16::from_be_bytes(self.array()?) as usize,
+            _ => u32::from_be_bytes(self.array()?) as usize,
+        })
+    }
+
+    fn value(&mut self, depth: usize) -> Result<TypedValue, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::TooDeep);
+        }
+        let marker = self.u8()?;
+        Ok(match marker {
+            0x00..=0x7f => TypedValue::Int(marker as i64),
+            0xe0..=0xff => TypedValue::Int(marker as i8 as i64),
+            0x80..=0x8f => self.map((marker & 0x0f) as usize, depth)?,
+            0x90..=0x9f => self.list((marker & 0x0f) as usize, depth)?,
+            0xa0..=0xbf => self.text((marker & 0x1f) as usize)?,
+            0xc0 => TypedValue::Null,
+            0xc2 => TypedValue::Bool(false),
+            0xc3 => TypedValue::Bool(true),
+            0xc4..=0xc6 => {
+                let len = self.len(1 << (marker - 0xc4))?;
+                TypedValue::Bytes(self.take(len)?.to_vec())
+            }
+            0xc7..=0xc9 => {
+                let len = self.len(1 << (marker - 0xc7))?;
+                self.ext(len)?
+            }
+            0xca => finite(f32::from_be_bytes(self.array()?) as f64)?,
+            0xcb => finite(f64::from_be_bytes(self.array()?))?,
+            0xcc => TypedValue::Int(self.u8()? as i64),
+            0xcd => TypedValue::Int(u16::from_be_bytes(self.array()?) as i64),
+            0xce => TypedValue::Int(u32::from_be_bytes(self.array()?) as i64),
+            0xcf => TypedValue::Int(
+                i64::try_from(u64::from_be_bytes(self.array()?))
+                    .map_err(|_| DecodeError::OutOfRange)?,
+            ),
+            0xd0 => TypedValue::Int(self.u8()? as i8 as i64),
+            0xd1 => TypedValue::Int(i16::from_be_bytes(self.array()?) as i64),
+            0xd2 => TypedValue::Int(i32::from_be_bytes(self.array()?) as i64),
+            0xd3 => TypedValue::Int(i64::from_be_bytes(self.array()?)),
+            0xd4..=0xd8 => self.ext(1 << (marker - 0xd4))?,
+            0xd9..=0xdb => {
+                let len = self.len(1 << (marker - 0xd9))?;
+                self.text(len)?
+            }
+            0xdc | 0xdd => {
+                let len = self.len(2 << (marker - 0xdc))?;
+                self.list(len, depth)?
+            }
+            0xde | 0xdf => {
+                let len = self.len(2 << (marker - 0xde))?;
+                self.map(len, depth)?
+            }
+            _ => return Err(DecodeError::InvalidMarker(marker)),
+        })
+    }
+
+    fn text(&mut self, len: usize) -> Result<TypedValue, DecodeError> {
+        let bytes = self.take(len)?;
+        let text = std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)?;
+        Ok(TypedValue::Text(text.to_string()))
+    }
+
+    fn list(&mut self, len: usize, depth: usize) -> Result<TypedValue, DecodeError> {
+        // The length is untrusted, so let the list grow as elements are read
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(self.value(depth + 1)?);
+        }
+        Ok(TypedValue::Array(items))
+    }
+
+    fn map(&mut self, len: usize, depth: usize) -> Result<TypedValue, DecodeError> {
+        let mut map = std::collections::HashMap::new();
+        for _ in 0..len {
+            let TypedValue::Text(key) = self.value(depth + 1)? else {
+                return Err(DecodeError::NonStringKey);
+            };
+            let value = self.value(depth + 1)?;
+            map.insert(key, value);
+        }
+        Ok(TypedValue::Object(map))
+    }
+
+    /// Read an extension value whose data is `len` bytes, after its type byte.
+    fn ext(&mut self, len: usize) -> Result<TypedValue, DecodeError> {
+        let ext_type = self.u8()? as i8;
+        let data = self.take(len)?;
+        match (ext_type, data.len()) {
+            (TIMESTAMP_EXT, 4) => {
+                let secs = u32::from_be_bytes(data.try_into().unwrap()) as i64;
+                Ok(TypedValue::Timestamp(secs * 1000))
+            }
+            (TIMESTAMP_EXT, 8) => {
+                // timestamp 64: 30 bits of nanoseconds, 34 bits of seconds
+                let raw = u64::from_be_bytes(data.try_into().unwrap());
+                let nanos = (raw >> 34) as i64;
+                let secs = (raw & 0x3_ffff_ffff) as i64;
+                Ok(TypedValue::Timestamp(secs * 1000 + nanos / 1_000_000))
+            }
+            (TIMESTAMP_EXT, 12) => {
+                let nanos = u32::from_be_bytes(data[..4].try_into().unwrap()) as i64;
+                let secs = i64::from_be_bytes(data[4..].try_into().unwrap());
+                secs.checked_mul(1000)
+                    .and_then(|ms| ms.checked_add(nanos / 1_000_000))
+                    .map(TypedValue::Timestamp)
+                    .ok_or(DecodeError::OutOfRange)
+            }
+            (COUNTER_EXT, 8) => Ok(TypedValue::Counter(i64::from_be_bytes(
+                data.try_into().unwrap(),
+            ))),
+            _ => Err(DecodeError::UnsupportedExtension(ext_type)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers_use_smallest_encoding() {
+        assert_eq!(encode(&TypedValue::Int(5)), vec![0x05]);
+        assert_eq!(encode(&TypedValue::Int(-1)), vec![0xff]);
+        assert_eq!(encode(&TypedValue::Int(200)), vec![0xcc, 200]);
+        assert_eq!(encode(&TypedValue::Int(-100)), vec![0xd0, 0x9c]);
+        assert_eq!(encode(&TypedValue::Int(70_000)).len(), 5);
+
+        for i in [
+            0,
+            127,
+            128,
+            -32,
+            -33,
+            255,
+            256,
+            65_535,
+            65_536,
+            -32_768,
+            -32_769,
+            u32::MAX as i64,
+            u32::MAX as i64 + 1,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            assert_eq!(
+                decode(&encode(&TypedValue::Int(i))).unwrap(),
+                TypedValue::Int(i)
+            );
+        }
+    }
+
+    #[test]
+    fn test_extension_types_round_trip() {
+        for ms in [0, 1_700_000_000_123, -1, -1_500] {
+            let value = TypedValue::Timestamp(ms);
+            assert_eq!(decode(&encode(&value)).unwrap(), value);
+        }
+        let counter = TypedValue::Counter(-42);
+        assert_eq!(encode(&counter)[..2], [0xd7, COUNTER_EXT as u8]);
+        assert_eq!(decode(&encode(&counter)).unwrap(), counter);
+
+        // timestamp 32 as written by other encoders: seconds only
+        assert_eq!(
+            decode(&[0xd6, 0xff, 0, 0, 0, 10]).unwrap(),
+            TypedValue::Timestamp(10_000)
+        );
+    }
+
+    #[test]
+    fn test_map_keys_are_sorted() {
+        let mut map = std::collections::HashMap::new();
+        map.insert("b".to_string(), TypedValue::Null);
+        map.insert("a".to_string(), TypedValue::Bool(true));
+        let encoded = encode(&TypedValue::Object(map.clone()));
+        assert_eq!(encoded, vec![0x82, 0xa1, b'a', 0xc3, 0xa1, b'b', 0xc0]);
+        assert_eq!(decode(&encoded).unwrap(), TypedValue::Object(map));
+    }
+
+    #[test]
+    fn test_rejects_invalid_data() {
+        // Truncated string, trailing bytes, non-string map key, unknown extension
+        assert_eq!(decode(&[0xa3, b'a']), Err(DecodeError::Truncated));
+        assert_eq!(decode(&[0xc0, 0xc0]), Err(DecodeError::TrailingBytes));
+        assert_eq!(decode(&[0x81, 0x01, 0xc0]), Err(DecodeError::NonStringKey));
+        assert_eq!(
+            decode(&[0xd4, 0x05, 0x00]),
+            Err(DecodeError::UnsupportedExtension(5))
+        );
+        assert_eq!(decode(&[]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&[0xc1]), Err(DecodeError::InvalidMarker(0xc1)));
+        assert_eq!(decode(&[0xa1, 0xff]), Err(DecodeError::InvalidUtf8));
+
+        // Non-finite doubles, in both widths
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let mut float64 = vec![0xcb];
+            float64.extend_from_slice(&value.to_be_bytes());
+            assert_eq!(decode(&float64), Err(DecodeError::NonFiniteDouble));
+            let mut float32 = vec![0xca];
+            float32.extend_from_slice(&(value as f32).to_be_bytes());
+            assert_eq!(decode(&float32), Err(DecodeError::NonFiniteDouble));
+        }
+
+        // A list claiming more elements than there are bytes fails without
+        // reserving space for them
+        assert!(decode(&[0xdd, 0xff, 0xff, 0xff, 0xff, 0x91, 0xdd, 0xff, 0xff]).is_err());
+
+        // Nesting beyond the limit
+        let deep = vec![0x91; MAX_DEPTH + 2];
+        assert_eq!(decode(&deep), Err(DecodeError::TooDeep));
+    }
+}
@@ -0,0 +1,352 @@
+//! Blocking waits for document changes (`AM.WATCH`).
+//!
+//! A watching client is parked here together with the heads it already has, keyed by
+//! the database and key it watches. Every write to the key wakes the watchers on it,
+//! and each watcher the document has moved beyond is answered with the changes it is
+//! missing, in the same shape as `AM.CHANGES`. Deleting, expiring or flushing the key
+//! answers its watchers with an error.
+//!
+//! Timeouts and disconnects are left to the server's blocked client machinery: the
+//! timeout and disconnect callbacks only take the watcher out of the registry, so a
+//! later write can't unblock it a second time.
+
+use crate::ext::RedisAutomergeClient;
+use automerge::ChangeHash;
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_void};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use valkey_module::{raw, Context, ValkeyError, ValkeyString, ValkeyValue};
+
+/// A document key within one database.
+pub type WatchKey = (i32, Vec<u8>);
+
+/// The watch key of `key` in the database `ctx` has selected.
+pub fn watch_key(ctx: &Context, key: &[u8]) -> WatchKey {
+    (crate::selected_db(ctx), key.to_vec())
+}
+
+/// A parked client and the heads it is waiting to move beyond.
+struct Watcher<C> {
+    have: Vec<ChangeHash>,
+    client: C,
+}
+
+/// Clients waiting for changes, grouped by database and key.
+pub struct Watchers<C> {
+    by_key: HashMap<WatchKey, Vec<Watcher<C>>>,
+}
+
+impl<C: PartialEq> Watchers<C> {
+    pub fn new() -> Self {
+        Watchers {
+            by_key: HashMap::new(),
+        }
+    }
+
+    /// Park `client` until `key` has changes beyond `have`.
+    pub fn add(&mut self, key: WatchKey, have: Vec<ChangeHash>, client: C) {
+        self.by_key
+            .entry(key)
+            .or_default()
+            .push(Watcher { have, client });
+    }
+
+    /// Remove a watcher, returning its client unless it was already woken.
+    pub fn remove(&mut self, client: &C) -> Option<C> {
+        let key = self
+            .by_key
+            .iter()
+            .find(|(_, watchers)| watchers.iter().any(|w| w.client == *client))
+            .map(|(key, _)| key.clone())?;
+        let watchers = self.by_key.get_mut(&key)?;
+        let index = watchers.iter().position(|w| w.client == *client)?;
+        let watcher = watchers.swap_remove(index);
+        if watchers.is_empty() {
+            self.by_key.remove(&key);
+        }
+        Some(watcher.client)
+    }
+
+    /// Whether any client is watching `key`.
+    pub fn is_watched(&self, key: &WatchKey) -> bool {
+        self.by_key.contains_key(key)
+    }
+
+    /// Take the watchers on `key` for which `pending` finds changes, together with
+    /// those changes. Watchers with nothing pending stay parked.
+    pub fn wake<F>(&mut self, key: &WatchKey, pending: F) -> Vec<(C, Vec<Vec<u8>>)>
+    where
+        F: Fn(&[ChangeHash]) -> Vec<Vec<u8>>,
+    {
+        let Some(watchers) = self.by_key.remove(key) else {
+            return Vec::new();
+        };
+
+        let mut woken = Vec::new();
+        let mut waiting = Vec::new();
+        for watcher in watchers {
+            let changes = pending(&watcher.have);
+            if changes.is_empty() {
+                waiting.push(watcher);
+            } else {
+                woken.push((watcher.client, changes));
+            }
+        }
+        if !waiting.is_empty() {
+            self.by_key.insert(key.clone(), waiting);
+        }
+        woken
+    }
+
+    /// Take every watcher on `key`.
+    pub fn take(&mut self, key: &WatchKey) -> Vec<C> {
+        self.by_key
+            .remove(key)
+            .into_iter()
+            .flatten()
+            .map(|w| w.client)
+            .collect()
+    }
+
+    /// Take every watcher in database `db`, or in all databases when `None`.
+    pub fn take_db(&mut self, db: Option<i32>) -> Vec<C> {
+        let keys: Vec<WatchKey> = self
+            .by_key
+            .keys()
+            .filter(|(key_db, _)| db.is_none_or(|db| db == *key_db))
+            .cloned()
+            .collect();
+        keys.iter().flat_map(|key| self.take(key)).collect()
+    }
+}
+
+impl<C: PartialEq> Default for Watchers<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle of a client blocked in `AM.WATCH`.
+#[derive(Debug, PartialEq, Eq)]
+struct Blocked(*mut raw::RedisModuleBlockedClient);
+
+// The handle is only used from the main thread, under the registry lock
+unsafe impl Send for Blocked {}
+
+/// How a parked client is answered, handed to the reply callback as private data.
+enum Wakeup {
+    Changes(Vec<Vec<u8>>),
+    Deleted,
+}
+
+/// Clients blocked in `AM.WATCH`.
+static WATCHERS: LazyLock<Mutex<Watchers<Blocked>>> = LazyLock::new(|| Mutex::new(Watchers::new()));
+
+fn watchers() -> MutexGuard<'static, Watchers<Blocked>> {
+    WATCHERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn unblock(client: Blocked, wakeup: Wakeup) {
+    let data = Box::into_raw(Box::new(wakeup)).cast::<c_void>();
+    unsafe { raw::RedisModule_UnblockClient.unwrap()(client.0, data) };
+}
+
+/// Raw bytes of the changes in `client` that are not covered by `have`.
+pub fn pending_changes(client: &RedisAutomergeClient, have: &[ChangeHash]) -> Vec<Vec<u8>> {
+    client
+        .get_changes(have)
+        .iter()
+        .map(|change| change.raw_bytes().to_vec())
+        .collect()
+}
+
+/// Reply with the changes, in the same shape as `AM.CHANGES`.
+pub fn changes_reply(changes: Vec<Vec<u8>>) -> ValkeyValue {
+    ValkeyValue::Array(changes.into_iter().map(ValkeyValue::StringBuffer).collect())
+}
+
+/// Block the calling client until `key` has changes beyond `have`, or until
+/// `timeout_ms` passes, when it is answered with Null. A timeout of 0 waits
+/// indefinitely.
+pub fn watch(ctx: &Context, key: WatchKey, have: Vec<ChangeHash>, timeout_ms: i64) {
+    let client = unsafe {
+        raw::RedisModule_BlockClient.unwrap()(
+            ctx.ctx,
+            Some(reply_callback),
+            Some(timeout_callback),
+            Some(free_callback),
+            timeout_ms,
+        )
+    };
+    unsafe { raw::RedisModule_SetDisconnectCallback.unwrap()(client, Some(disconnect_callback)) };
+    watchers().add(key, have, Blocked(client));
+}
+
+/// Whether any client is blocked watching `key`.
+pub fn is_watched(key: &WatchKey) -> bool {
+    watchers().is_watched(key)
+}
+
+/// Answer the watchers on `key` that `client` has moved beyond.
+pub fn notify(key: &WatchKey, client: &RedisAutomergeClient) {
+    let woken = watchers().wake(key, |have| pending_changes(client, have));
+    for (blocked, changes) in woken {
+        unblock(blocked, Wakeup::Changes(changes));
+    }
+}
+
+/// Answer every watcher on `key`, which no longer holds a document.
+pub fn clear(key: &WatchKey) {
+    let woken = watchers().take(key);
+    for blocked in woken {
+        unblock(blocked, Wakeup::Deleted);
+    }
+}
+
+/// Answer every watcher in database `db`, or in all databases when `None`.
+pub fn clear_db(db: Option<i32>) {
+    let woken = watchers().take_db(db);
+    for blocked in woken {
+        unblock(blocked, Wakeup::Deleted);
+    }
+}
+
+/// Wake the watchers on `key` after a write, or clear them when the key no
+/// longer holds a document.
+pub fn key_written(ctx: &Context, key: &[u8]) {
+    let watch_key = watch_key(ctx, key);
+    if !is_watched(&watch_key) {
+        return;
+    }
+    let key_name = ValkeyString::create_from_slice(ctx.ctx, key);
+    let key = ctx.open_key(&key_name);
+    match key.get_value::<RedisAutomergeClient>(&crate::VALKEY_AUTOMERGE_TYPE) {
+        Ok(Some(client)) => notify(&watch_key, client),
+        _ => clear(&watch_key),
+    }
+}
+
+unsafe extern "C" fn reply_callback(
+    ctx: *mut raw::RedisModuleCtx,
+    _argv: *mut *mut raw::RedisModuleString,
+    _argc: c_int,
+) -> c_int {
+    let ctx = Context::new(ctx);
+    let wakeup = raw::RedisModule_GetBlockedClientPrivateData.unwrap()(ctx.ctx).cast::<Wakeup>();
+    let reply = match wakeup.as_mut() {
+        Some(Wakeup::Changes(changes)) => Ok(changes_reply(std::mem::take(changes))),
+        Some(Wakeup::Deleted) => Err(ValkeyError::Str("ERR no such key")),
+        None => Ok(ValkeyValue::Null),
+    };
+    ctx.reply(reply) as c_int
+}
+
+unsafe extern "C" fn timeout_callback(
+    ctx: *mut raw::RedisModuleCtx,
+    _argv: *mut *mut raw::RedisModuleString,
+    _argc: c_int,
+) -> c_int {
+    // The server unblocks the client itself after a timeout
+    let handle = raw::RedisModule_GetBlockedClientHandle.unwrap()(ctx);
+    watchers().remove(&Blocked(handle));
+    Context::new(ctx).reply(Ok(ValkeyValue::Null)) as c_int
+}
+
+unsafe extern "C" fn disconnect_callback(
+    _ctx: *mut raw::RedisModuleCtx,
+    client: *mut raw::RedisModuleBlockedClient,
+) {
+    // A client that was already woken has been unblocked
+    if let Some(blocked) = watchers().remove(&Blocked(client)) {
+        raw::RedisModule_UnblockClient.unwrap()(blocked.0, std::ptr::null_mut());
+    }
+}
+
+unsafe extern "C" fn free_callback(_ctx: *mut raw::RedisModuleCtx, data: *mut c_void) {
+    if !data.is_null() {
+        drop(Box::from_raw(data.cast::<Wakeup>()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(db: i32, name: &str) -> WatchKey {
+        (db, name.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_wake_answers_only_watchers_behind() {
+        let mut client = RedisAutomergeClient::new();
+        client.put_text("title", "Draft").unwrap();
+        let heads = client.get_heads();
+
+        let mut watchers = Watchers::new();
+        watchers.add(key(0, "doc"), heads.clone(), "current");
+        watchers.add(key(0, "doc"), Vec::new(), "empty");
+        watchers.add(key(0, "other"), heads.clone(), "other key");
+
+        // The watcher with no heads is already behind; the current one keeps waiting
+        let woken = watchers.wake(&key(0, "doc"), |have| pending_changes(&client, have));
+        assert_eq!(woken.len(), 1);
+        assert_eq!(woken[0].0, "empty");
+        assert_eq!(woken[0].1.len(), 1);
+        assert!(watchers.is_watched(&key(0, "doc")));
+
+        client.put_int("version", 2).unwrap();
+        let woken = watchers.wake(&key(0, "doc"), |have| pending_changes(&client, have));
+        assert_eq!(woken.len(), 1);
+        assert_eq!(woken[0].0, "current");
+        assert_eq!(
+            woken[0].1,
+            pending_changes(&client, &heads),
+            "the watcher receives exactly the changes after its heads"
+        );
+        assert!(!watchers.is_watched(&key(0, "doc")));
+        assert!(watchers.is_watched(&key(0, "other")));
+    }
+
+    #[test]
+    fn test_remove_after_wake_returns_none() {
+        let mut client = RedisAutomergeClient::new();
+        let mut watchers = Watchers::new();
+        watchers.add(key(0, "doc"), Vec::new(), 1);
+        watchers.add(key(0, "doc"), Vec::new(), 2);
+
+        assert_eq!(watchers.remove(&1), Some(1));
+        assert_eq!(watchers.remove(&1), None);
+
+        client.put_text("title", "Draft").unwrap();
+        let answered = watchers.wake(&key(0, "doc"), |have| pending_changes(&client, have));
+        assert_eq!(answered.len(), 1);
+        // A timeout or disconnect after the wake finds nothing to answer
+        assert_eq!(watchers.remove(&2), None);
+        assert!(!watchers.is_watched(&key(0, "doc")));
+    }
+
+    #[test]
+    fn test_watchers_are_kept_per_database() {
+        let mut client = RedisAutomergeClient::new();
+        client.put_text("title", "Draft").unwrap();
+
+        let mut watchers = Watchers::new();
+        watchers.add(key(0, "doc"), Vec::new(), "db0");
+        watchers.add(key(3, "doc"), Vec::new(), "db3");
+        watchers.add(key(3, "other"), Vec::new(), "db3 other");
+
+        // A write in one database leaves the same key in another untouched
+        let woken = watchers.wake(&key(3, "doc"), |have| pending_changes(&client, have));
+        assert_eq!(woken.len(), 1);
+        assert_eq!(woken[0].0, "db3");
+        assert!(watchers.is_watched(&key(0, "doc")));
+
+        // Flushing a database takes only its watchers
+        assert_eq!(watchers.take_db(Some(3)), vec!["db3 other"]);
+        assert!(watchers.is_watched(&key(0, "doc")));
+        assert_eq!(watchers.take_db(None), vec!["db0"]);
+        assert!(watchers.take(&key(0, "doc")).is_empty());
+    }
+}